@@ -0,0 +1,37 @@
+//! Benchmarks of parsing lines and formatting durations. Run with `cargo bench` in `bench/`.
+
+use std::hint::black_box;
+
+use chrono::Duration;
+use criterion::{criterion_group, criterion_main, Criterion};
+use duration_calculator_rs::style::Style;
+use duration_calculator_rs::{DisplayableDuration, DurationParse};
+
+fn from_str(c: &mut Criterion) {
+    let inputs = [
+        "1h 30m",
+        "2h - 15m + 1:30:00 - 1.5h",
+        "1h..2h + 1h/2h/4h + max(0s, 40h - 42h)",
+    ];
+    for input in inputs {
+        c.bench_function(&format!("from_str {}", input), |b| {
+            b.iter(|| Duration::from_str(black_box(input)))
+        });
+    }
+}
+
+fn display(c: &mut Criterion) {
+    let d = Duration::seconds(123_456);
+    let compact = Style {
+        compact: true,
+        ..Style::default()
+    };
+    for (name, style) in [("default", Style::default()), ("compact", compact)] {
+        c.bench_function(&format!("display {}", name), |b| {
+            b.iter(|| DisplayableDuration(black_box(d), style).to_string())
+        });
+    }
+}
+
+criterion_group!(benches, from_str, display);
+criterion_main!(benches);
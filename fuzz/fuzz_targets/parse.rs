@@ -0,0 +1,18 @@
+//! Feeds arbitrary input to the parser, which must neither panic nor take long, see
+//! `check_limits`. Run with `cargo fuzz run parse` in the repository root.
+
+#![no_main]
+
+use chrono::{Duration, Local};
+use duration_calculator_rs::{line_duration, range_spread, validate, DurationParse};
+
+libfuzzer_sys::fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let _ = line_duration(input);
+    let _ = Duration::scan(input);
+    let _ = validate::validate(input);
+    let _ = range_spread(input, Local::now().naive_local());
+});
@@ -0,0 +1,89 @@
+//! Echo of the input with the duration of each line appended as comment and the total at the
+//! end, e.g. as "format buffer" filter of an editor, see `--annotate`.
+
+use chrono::Duration;
+
+use crate::options::Style;
+use crate::summary;
+use crate::{line_duration, DisplayableDuration, DurationCalculate};
+
+/// Starts the comments with the durations.
+pub const MARKER: &str = "# =>";
+
+/// Removes a previous annotation from `line`, `None` if the whole line is a previous total.
+pub fn strip(line: &str) -> Option<&str> {
+    match line.find(MARKER) {
+        Some(i)
+            if line[..i].trim().is_empty()
+                && line[i + MARKER.len()..].trim_start().starts_with("total ") =>
+        {
+            None
+        }
+        Some(i) => Some(line[..i].trim_end()),
+        None => Some(line),
+    }
+}
+
+/// `input` line by line, lines with a duration followed by `# => <duration>`, lines that cannot
+/// be parsed by `# => error`, and a last line `# => total <duration>`. Previous annotations
+/// and totals are replaced, so annotating again gives the same result.
+pub fn annotate(input: &str, style: Style) -> String {
+    let mut output = String::with_capacity(input.len() * 2);
+    let mut total = Duration::zero();
+
+    for line in input.lines().filter_map(strip) {
+        output.push_str(line);
+        match line_duration(line) {
+            Ok(Some(d)) => {
+                summary::count_line(false);
+                total = total.saturated_add(&d);
+                output.push_str(&format!(" {} {}", MARKER, DisplayableDuration(d, style)));
+            }
+            Ok(None) => summary::count_line(true),
+            Err(_) => {
+                summary::count_failed();
+                output.push_str(&format!(" {} error", MARKER));
+            }
+        }
+        output.push('\n');
+    }
+
+    output.push_str(&format!(
+        "{} total {}\n",
+        MARKER,
+        DisplayableDuration(total, style)
+    ));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotate() {
+        let input = "# week 2\n2025-01-06 2h 30m # dev\n\n45m +billable\n2h +\n";
+        assert_eq!(
+            annotate(input, Style::default()),
+            "# week 2
+2025-01-06 2h 30m # dev # => 2h 30m 00s
+
+45m +billable # => 0h 45m 00s
+2h + # => error
+# => total 3h 15m 00s
+"
+        );
+
+        let annotated = annotate(input, Style::default());
+        assert_eq!(annotate(&annotated, Style::default()), annotated);
+        assert_eq!(
+            annotate(
+                "1h # => 5h 00m 00s\n# => total 5h 00m 00s\n",
+                Style::default()
+            ),
+            "1h # => 1h 00m 00s\n# => total 1h 00m 00s\n"
+        );
+        assert_eq!(strip("  # => total 1h"), None);
+        assert_eq!(strip("1h # => total 1h"), Some("1h"));
+    }
+}
@@ -0,0 +1,329 @@
+//! Monetary value of durations at an hourly rate, see `--rate`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::Duration;
+
+use crate::options::Style;
+use crate::report::Report;
+use crate::{DisplayableDuration, DurationCalculate};
+
+/// How durations are rounded to whole billing increments.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Rounding {
+    /// Every started increment counts
+    #[default]
+    Up,
+    Nearest,
+    Down,
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "up" => Ok(Rounding::Up),
+            "nearest" => Ok(Rounding::Nearest),
+            "down" => Ok(Rounding::Down),
+            _ => Err(format!(
+                "invalid rounding {:?}, expected up, nearest or down",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Billing {
+    /// Cents per hour
+    pub rate: i64,
+    /// Appended to amounts, e.g. `EUR`
+    pub currency: Option<String>,
+    /// Durations are rounded to multiples of this before they are billed
+    pub increment: Option<Duration>,
+    pub rounding: Rounding,
+}
+
+impl Billing {
+    /// `d` rounded to whole increments, `d` itself without increment.
+    pub fn billed(&self, d: Duration) -> Duration {
+        let increment = match self.increment.map(|i| i.num_milliseconds()) {
+            Some(i) if i > 0 => i,
+            _ => return d,
+        };
+
+        let ms = d.num_milliseconds();
+        let remainder = ms.rem_euclid(increment);
+        let down = ms.saturating_sub(remainder);
+        let rounded = match self.rounding {
+            Rounding::Down => down,
+            Rounding::Up if remainder == 0 => down,
+            Rounding::Up => down.saturating_add(increment),
+            Rounding::Nearest if remainder >= increment - remainder => {
+                down.saturating_add(increment)
+            }
+            Rounding::Nearest => down,
+        };
+        // saturates at the longest durations, like the sums
+        Duration::try_milliseconds(rounded).unwrap_or(if rounded < 0 {
+            Duration::MIN
+        } else {
+            Duration::MAX
+        })
+    }
+
+    /// Amount for `d` in cents, rounded half away from zero.
+    pub fn amount(&self, d: Duration) -> i64 {
+        let millicents = self.billed(d).num_milliseconds() as i128 * self.rate as i128;
+        let cents = (millicents.abs() + 1_800_000) / 3_600_000;
+        (cents * millicents.signum()) as i64
+    }
+}
+
+/// Parses a rate like `95.50` into cents.
+pub fn parse_rate(s: &str) -> Result<i64, String> {
+    let invalid = || format!("invalid rate {:?}, expected an amount like 95.50", s);
+
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    if whole.is_empty()
+        || fraction.len() > 2
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    let whole: i64 = whole.parse().map_err(|_| invalid())?;
+    let fraction: i64 = format!("{:0<2}", fraction).parse().map_err(|_| invalid())?;
+    whole
+        .checked_mul(100)
+        .and_then(|w| w.checked_add(fraction))
+        .ok_or_else(invalid)
+}
+
+/// An amount in cents with two decimals and the currency, if any, e.g. `358.13 EUR`.
+pub struct Amount<'a>(pub i64, pub Option<&'a str>);
+
+impl fmt::Display for Amount<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, cents / 100, cents % 100)?;
+        match self.1 {
+            Some(currency) => write!(f, " {}", currency),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The groups of a `Report` (first field) with their billed duration and amount, followed by
+/// the total (third field) labelled with the fourth field. Groups are rounded one by one, the
+/// total as a whole.
+pub struct DisplayableBilling<'a>(
+    pub &'a Report,
+    pub &'a Billing,
+    pub Duration,
+    pub &'a str,
+    pub Style,
+);
+
+impl fmt::Display for DisplayableBilling<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let billing = self.1;
+        let currency = billing.currency.as_deref();
+        let rows: Vec<(&str, String, String)> = self
+            .0
+            .groups
+            .iter()
+            .map(|(l, d)| (l.as_str(), *d))
+            .chain([(self.3, self.2)])
+            .map(|(label, d)| {
+                (
+                    label,
+                    DisplayableDuration(billing.billed(d), self.4).to_string(),
+                    Amount(billing.amount(d), currency).to_string(),
+                )
+            })
+            .collect();
+
+        let width = rows.iter().map(|r| r.0.chars().count()).max().unwrap_or(0);
+        let duration_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0);
+        let amount_width = rows.iter().map(|r| r.2.len()).max().unwrap_or(0);
+
+        for (label, duration, amount) in rows {
+            writeln!(
+                f,
+                "{:width$} {:>duration_width$} {:>amount_width$}",
+                label, duration, amount
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Common billing policies for `RoundingReport`: label, increment in minutes (none for 0) and
+/// rounding.
+const POLICIES: [(&str, i64, Rounding); 4] = [
+    ("exact", 0, Rounding::Up),
+    ("nearest 6m", 6, Rounding::Nearest),
+    ("nearest 15m", 15, Rounding::Nearest),
+    ("up 15m", 15, Rounding::Up),
+];
+
+/// The total of the lines (first field) with each line rounded by each of the `POLICIES`, and
+/// the difference to the exact total. With a `Billing` (second field), also the amount at its
+/// rate and currency.
+pub struct RoundingReport<'a>(pub &'a [Duration], pub Option<&'a Billing>, pub Style);
+
+impl fmt::Display for RoundingReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let exact = self
+            .0
+            .iter()
+            .fold(Duration::zero(), |sum, d| sum.saturated_add(d));
+        let rows: Vec<(&str, String, String, String)> = POLICIES
+            .iter()
+            .map(|(label, minutes, rounding)| {
+                let policy = Billing {
+                    rate: self.1.map_or(0, |b| b.rate),
+                    currency: self.1.and_then(|b| b.currency.clone()),
+                    increment: Some(Duration::minutes(*minutes)),
+                    rounding: *rounding,
+                };
+                let total = self.0.iter().fold(Duration::zero(), |sum, d| {
+                    sum.saturated_add(&policy.billed(*d))
+                });
+                let difference = total.saturated_sub(&exact);
+                let sign = if difference < Duration::zero() {
+                    ""
+                } else {
+                    "+"
+                };
+                let amount = match self.1 {
+                    // the total is already a multiple of the increment
+                    Some(_) => Amount(policy.amount(total), policy.currency.as_deref()).to_string(),
+                    None => String::new(),
+                };
+                (
+                    *label,
+                    DisplayableDuration(total, self.2).to_string(),
+                    format!("{}{}", sign, DisplayableDuration(difference, self.2)),
+                    amount,
+                )
+            })
+            .collect();
+
+        let width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0);
+        let duration_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0);
+        let difference_width = rows.iter().map(|r| r.2.len()).max().unwrap_or(0);
+        let amount_width = rows.iter().map(|r| r.3.len()).max().unwrap_or(0);
+
+        for (label, duration, difference, amount) in rows {
+            let line = format!(
+                "{:width$} {:>duration_width$} {:>difference_width$} {:>amount_width$}",
+                label, duration, difference, amount
+            );
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("95.50"), Ok(9550));
+        assert_eq!(parse_rate("95.5"), Ok(9550));
+        assert_eq!(parse_rate("120"), Ok(12000));
+        assert!(parse_rate("9.999").is_err());
+        assert!(parse_rate("-5").is_err());
+        assert!(parse_rate(".5").is_err());
+    }
+
+    #[test]
+    fn test_billing() {
+        let mut billing = Billing {
+            rate: 9550,
+            currency: Some("EUR".to_owned()),
+            ..Billing::default()
+        };
+        let d = Duration::minutes(3 * 60 + 45) + Duration::seconds(5);
+
+        assert_eq!(billing.amount(d), 35826);
+        assert_eq!(
+            Amount(billing.amount(d), Some("EUR")).to_string(),
+            "358.26 EUR"
+        );
+        assert_eq!(Amount(-50, None).to_string(), "-0.50");
+
+        billing.increment = Some(Duration::minutes(15));
+        assert_eq!(billing.billed(d), Duration::minutes(4 * 60));
+        assert_eq!(billing.amount(d), 38200);
+        billing.rounding = Rounding::Nearest;
+        assert_eq!(billing.billed(d), Duration::minutes(3 * 60 + 45));
+        assert_eq!(billing.billed(Duration::minutes(8)), Duration::minutes(15));
+        billing.rounding = Rounding::Down;
+        assert_eq!(billing.billed(Duration::minutes(14)), Duration::zero());
+        assert_eq!(billing.billed(Duration::MIN), Duration::MIN);
+        billing.rounding = Rounding::Up;
+        assert_eq!(billing.billed(Duration::MAX), Duration::MAX);
+
+        let report = Report {
+            groups: vec![
+                ("Website".to_owned(), Duration::minutes(130)),
+                ("Support".to_owned(), Duration::minutes(20)),
+            ],
+            ..Report::default()
+        };
+        billing.rounding = Rounding::Up;
+        let total = Duration::minutes(150);
+        assert_eq!(
+            DisplayableBilling(&report, &billing, total, "total", Style::default()).to_string(),
+            "Website 2h 15m 00s 214.88 EUR
+Support 0h 30m 00s  47.75 EUR
+total   2h 30m 00s 238.75 EUR
+"
+        );
+    }
+
+    #[test]
+    fn test_rounding_report() {
+        let lines = [
+            Duration::minutes(7),
+            Duration::minutes(52),
+            Duration::minutes(20),
+        ];
+        assert_eq!(
+            RoundingReport(&lines, None, Style::default()).to_string(),
+            "exact       1h 19m 00s +0h 00m 00s
+nearest 6m  1h 18m 00s -0h 01m 00s
+nearest 15m 1h 00m 00s -0h 19m 00s
+up 15m      1h 45m 00s +0h 26m 00s
+"
+        );
+
+        let billing = Billing {
+            rate: 6000,
+            currency: Some("EUR".to_owned()),
+            ..Billing::default()
+        };
+        let report = RoundingReport(&lines, Some(&billing), Style::default()).to_string();
+        assert_eq!(
+            report.lines().next(),
+            Some("exact       1h 19m 00s +0h 00m 00s  79.00 EUR")
+        );
+        assert_eq!(
+            report.lines().last(),
+            Some("up 15m      1h 45m 00s +0h 26m 00s 105.00 EUR")
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Projected completion of a remaining amount of work at a given rate.
+
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::workhours::Schedule;
+use crate::DurationParse;
+
+/// Work done per day or per week, e.g. `6h/day` or `30h/week`.
+#[derive(Debug, PartialEq)]
+pub enum Rate {
+    Daily(Duration),
+    Weekly(Duration),
+}
+
+impl FromStr for Rate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (amount, per) = s
+            .rsplit_once('/')
+            .ok_or_else(|| format!("invalid rate {:?}, expected e.g. 6h/day", s))?;
+        let amount = Duration::from_str(amount)
+            .filter(|a| *a > Duration::zero())
+            .ok_or_else(|| format!("invalid amount {:?} in rate, must be positive", amount))?;
+
+        match per.trim() {
+            "day" | "d" => Ok(Rate::Daily(amount)),
+            "week" | "w" => Ok(Rate::Weekly(amount)),
+            per => Err(format!(
+                "invalid rate period {:?}, expected day or week",
+                per
+            )),
+        }
+    }
+}
+
+/// The day `remaining` is done when working from `today` at `rate`, on every day or, with a
+/// schedule, only on its working days. A weekly rate is spread evenly over the days worked in a
+/// week. `None` if no day is worked or the date is out of range.
+pub fn completion(
+    remaining: Duration,
+    rate: &Rate,
+    today: NaiveDate,
+    schedule: Option<&Schedule>,
+) -> Option<NaiveDate> {
+    let is_workday = |d: NaiveDate| schedule.is_none_or(|s| s.is_working_day(d.weekday()));
+    let days_per_week = today.iter_days().take(7).filter(|d| is_workday(*d)).count() as i64;
+    if days_per_week == 0 {
+        return None;
+    }
+
+    let daily = match rate {
+        Rate::Daily(d) => d.num_seconds(),
+        Rate::Weekly(w) => w.num_seconds() / days_per_week,
+    };
+    if daily <= 0 {
+        return None;
+    }
+    // the day work is finished on counts, too, so at least one
+    let days = (remaining.num_seconds().max(1) + daily - 1) / daily;
+
+    let mut date = today;
+    while !is_workday(date) {
+        date = date.succ_opt()?;
+    }
+    // each week has the same number of working days
+    date = date.checked_add_signed(Duration::try_weeks((days - 1) / days_per_week)?)?;
+    for _ in 0..(days - 1) % days_per_week {
+        date = date.succ_opt()?;
+        while !is_workday(date) {
+            date = date.succ_opt()?;
+        }
+    }
+
+    Some(date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        // 2025-01-06 is a Monday
+        NaiveDate::from_ymd_opt(2025, 1, d).unwrap()
+    }
+
+    #[test]
+    fn test_rate() {
+        assert_eq!(
+            Rate::from_str("6h/day"),
+            Ok(Rate::Daily(Duration::hours(6)))
+        );
+        assert_eq!(
+            Rate::from_str("30h / week"),
+            Ok(Rate::Weekly(Duration::hours(30)))
+        );
+        assert!(Rate::from_str("6h").is_err());
+        assert!(Rate::from_str("0h/day").is_err());
+        assert!(Rate::from_str("6h/month").is_err());
+    }
+
+    #[test]
+    fn test_completion() {
+        let six = Rate::Daily(Duration::hours(6));
+        let weekdays = Schedule::default();
+
+        assert_eq!(
+            completion(Duration::hours(6), &six, day(6), None),
+            Some(day(6))
+        );
+        assert_eq!(
+            completion(Duration::hours(7), &six, day(6), None),
+            Some(day(7))
+        );
+        assert_eq!(
+            completion(Duration::zero(), &six, day(6), None),
+            Some(day(6))
+        );
+        // 7 working days from Thursday: Thu, Fri, Mon-Fri
+        assert_eq!(
+            completion(Duration::hours(40), &six, day(9), Some(&weekdays)),
+            Some(day(17))
+        );
+        // starting on a Saturday
+        assert_eq!(
+            completion(Duration::hours(12), &six, day(11), Some(&weekdays)),
+            Some(day(14))
+        );
+        assert_eq!(
+            completion(
+                Duration::hours(60),
+                &Rate::Weekly(Duration::hours(30)),
+                day(6),
+                Some(&weekdays)
+            ),
+            Some(day(17))
+        );
+        assert_eq!(completion(Duration::MAX, &six, day(6), None), None);
+    }
+}
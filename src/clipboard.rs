@@ -0,0 +1,106 @@
+//! System clipboard access through the usual command line tools, so no display server libraries
+//! are linked. Only built with the `clipboard` feature.
+
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+const PASTE: [&[&str]; 4] = [
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-o"],
+    &["xsel", "--clipboard", "--output"],
+    &["pbpaste"],
+];
+
+const COPY: [&[&str]; 4] = [
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+    &["pbcopy"],
+];
+
+/// Returns the clipboard text from the first tool found.
+pub fn read() -> io::Result<String> {
+    for command in PASTE {
+        match Command::new(command[0]).args(&command[1..]).output() {
+            Ok(output) if output.status.success() => {
+                return Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+            }
+            Ok(output) => return Err(failed(command[0], &output.stderr)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(not_found())
+}
+
+/// Replaces the clipboard text using the first tool found.
+pub fn write(text: &str) -> io::Result<()> {
+    for command in COPY {
+        let mut child = match Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        child.stdin.take().unwrap().write_all(text.as_bytes())?;
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(failed(command[0], &output.stderr));
+        }
+        return Ok(());
+    }
+    Err(not_found())
+}
+
+/// Lines of clipboard text as one expression for the arguments, each line a separate term.
+pub fn expression(text: &str) -> String {
+    text.lines()
+        .map(|l| l.split('#').next().unwrap().trim())
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            if l.starts_with(['+', '-']) {
+                l.to_owned()
+            } else {
+                format!("+{}", l)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn failed(tool: &str, stderr: &[u8]) -> io::Error {
+    io::Error::other(format!(
+        "{} failed: {}",
+        tool,
+        String::from_utf8_lossy(stderr).trim()
+    ))
+}
+
+fn not_found() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        "none of wl-clipboard, xclip, xsel or pbcopy found",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DurationParse;
+    use chrono::Duration;
+
+    #[test]
+    fn test_expression() {
+        let text = "1h # design\n-5m\n\n30m 20s\r\n";
+        assert_eq!(expression(text), "+1h -5m +30m 20s");
+        assert_eq!(
+            Duration::from_str(&expression(text)),
+            Some(Duration::minutes(85) + Duration::seconds(20))
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Elapsed time between local timestamps, as in `until` terms, `work-hours` and org CLOCK lines.
+//! Across a daylight saving time transition the real time differs from what the wall clock
+//! says, e.g. a night shift from 22:00 to 06:00 lasts 7 hours in spring and 9 in autumn.
+//! `--wall-clock` switches back to the difference of the wall clock times.
+
+use std::env;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::{Duration, Local, LocalResult, NaiveDateTime, Offset, TimeZone};
+
+static WALL_CLOCK: AtomicBool = AtomicBool::new(false);
+
+pub fn set_wall_clock(wall_clock: bool) {
+    WALL_CLOCK.store(wall_clock, Ordering::Relaxed);
+}
+
+/// Makes `zone` like `Europe/Berlin` the local time zone, as `TZ` does. Fails for zones that are
+/// neither `UTC` nor in the time zone data of the system, in `TZDIR` or `/usr/share/zoneinfo`,
+/// rather than falling back to UTC silently.
+pub fn set_time_zone(zone: &str) -> Result<(), String> {
+    let dir = env::var_os("TZDIR").unwrap_or_else(|| "/usr/share/zoneinfo".into());
+    let known = zone == "UTC"
+        || (!zone.is_empty()
+            && !zone.starts_with('/')
+            && !zone.split('/').any(|part| part == "..")
+            && Path::new(&dir).join(zone).is_file());
+    if !known {
+        return Err(format!("unknown time zone {:?}", zone));
+    }
+
+    // before any other thread is started
+    env::set_var("TZ", zone);
+    Ok(())
+}
+
+/// The time from `from` to `to` in the local time zone, or on the wall clock with
+/// `--wall-clock`. Negative if `to` is before `from`.
+pub fn elapsed(from: NaiveDateTime, to: NaiveDateTime) -> Duration {
+    if WALL_CLOCK.load(Ordering::Relaxed) {
+        return to - from;
+    }
+    elapsed_in(&Local, from, to)
+}
+
+/// The real time from `from` to `to`, both local times in `tz`.
+pub fn elapsed_in<Tz: TimeZone>(tz: &Tz, from: NaiveDateTime, to: NaiveDateTime) -> Duration {
+    match (utc(tz, from), utc(tz, to)) {
+        (Some(from), Some(to)) => to - from,
+        _ => to - from,
+    }
+}
+
+/// The UTC time of the local time `at` in `tz`. Of the times repeated when the clocks go back,
+/// the first counts. Times skipped when they go forward count as if they hadn't yet, so 02:30
+/// in a skipped hour is 03:30.
+fn utc<Tz: TimeZone>(tz: &Tz, at: NaiveDateTime) -> Option<NaiveDateTime> {
+    let offset = match tz.offset_from_local_datetime(&at) {
+        LocalResult::Single(o) | LocalResult::Ambiguous(o, _) => o.fix(),
+        // transitions are far more than a day apart
+        LocalResult::None => tz
+            .offset_from_local_datetime(&(at - Duration::days(1)))
+            .earliest()?
+            .fix(),
+    };
+    at.checked_sub_signed(Duration::seconds(offset.local_minus_utc().into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, NaiveDate, Utc};
+
+    /// Central European time of 2025, summer time from March 30 to October 26, so the tests
+    /// don't depend on the time zone data of the system.
+    #[derive(Debug, Clone)]
+    struct Berlin2025;
+
+    impl Berlin2025 {
+        fn offset(hours: i32) -> FixedOffset {
+            FixedOffset::east_opt(hours * 3600).unwrap()
+        }
+    }
+
+    impl TimeZone for Berlin2025 {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Berlin2025
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            // summer time first, it's the earlier of repeated times
+            let offsets: Vec<FixedOffset> = [2, 1]
+                .into_iter()
+                .map(Berlin2025::offset)
+                .filter(|o| self.offset_from_utc_datetime(&(*local - *o)) == *o)
+                .collect();
+            match offsets[..] {
+                [o] => LocalResult::Single(o),
+                [a, b] => LocalResult::Ambiguous(a, b),
+                _ => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let transition = |month, day| {
+                NaiveDate::from_ymd_opt(2025, month, day)
+                    .unwrap()
+                    .and_hms_opt(1, 0, 0)
+                    .unwrap()
+            };
+            let summer = (transition(3, 30)..transition(10, 26)).contains(utc);
+            Berlin2025::offset(if summer { 2 } else { 1 })
+        }
+    }
+
+    fn at(month: u32, day: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, month, day)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_elapsed_in() {
+        let tz = FixedOffset::east_opt(3600).unwrap();
+        assert_eq!(
+            elapsed_in(&tz, at(3, 30, 1, 0), at(3, 30, 4, 0)),
+            Duration::hours(3)
+        );
+        assert_eq!(
+            elapsed_in(&Utc, at(3, 30, 4, 0), at(3, 30, 1, 0)),
+            Duration::hours(-3)
+        );
+    }
+
+    #[test]
+    fn test_daylight_saving_time() {
+        let tz = Berlin2025;
+        let (h, m) = (Duration::hours, Duration::minutes);
+        assert_eq!(elapsed_in(&tz, at(3, 23, 1, 0), at(3, 23, 4, 0)), h(3));
+        assert_eq!(elapsed_in(&tz, at(3, 30, 1, 0), at(3, 30, 4, 0)), h(2));
+        assert_eq!(elapsed_in(&tz, at(10, 26, 1, 0), at(10, 26, 4, 0)), h(4));
+        assert_eq!(elapsed_in(&tz, at(3, 29, 22, 0), at(3, 30, 6, 0)), h(7));
+        assert_eq!(elapsed_in(&tz, at(10, 25, 22, 0), at(10, 26, 6, 0)), h(9));
+
+        // skipped 02:30 is 03:30, the first of the repeated 02:30s counts
+        assert_eq!(elapsed_in(&tz, at(3, 30, 2, 30), at(3, 30, 4, 0)), m(30));
+        assert_eq!(
+            elapsed_in(&tz, at(10, 26, 2, 30), at(10, 26, 4, 0)),
+            h(2) + m(30)
+        );
+    }
+}
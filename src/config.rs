@@ -0,0 +1,231 @@
+//! The configuration file, a small subset of TOML: `[section]` headers, `key = "value"` lines
+//! and `#` comments. Values may also be left unquoted. The `[units]` section defines custom
+//! units like `sprint = "2w"`, which the `alias` subcommand edits in place.
+
+use std::env;
+use std::path::PathBuf;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    /// Custom unit names and their durations as written, in file order
+    pub units: Vec<(String, String)>,
+}
+
+/// `$XDG_CONFIG_HOME/duration-calculator-rs/config.toml`, with `~/.config` if the variable is
+/// not set.
+pub fn default_path() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("duration-calculator-rs").join("config.toml"))
+}
+
+/// The value of a `key = value` line up to a comment, without quotes and escapes.
+fn value(s: &str) -> Option<String> {
+    let s = s.trim();
+    let Some(quoted) = s.strip_prefix('"') else {
+        return Some(s.split('#').next().unwrap().trim().to_owned());
+    };
+
+    let mut value = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let rest = chars.as_str().trim();
+                return (rest.is_empty() || rest.starts_with('#')).then_some(value);
+            }
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                c @ ('"' | '\\') => value.push(c),
+                _ => return None,
+            },
+            c => value.push(c),
+        }
+    }
+
+    None
+}
+
+impl Config {
+    pub fn parse(input: &str) -> Result<Config, String> {
+        let mut config = Config::default();
+        let mut section = String::new();
+
+        for (i, line) in input.lines().enumerate() {
+            let error = |message: &str| format!("line {}: {}", i + 1, message);
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if let Some(header) = trimmed.strip_prefix('[') {
+                let name = header.split('#').next().unwrap().trim_end();
+                section = name
+                    .strip_suffix(']')
+                    .map(|n| n.trim().to_owned())
+                    .ok_or_else(|| error("expected [section]"))?;
+                if section != "units" {
+                    return Err(error(&format!("unknown section [{}]", section)));
+                }
+                continue;
+            }
+
+            let (key, v) = trimmed
+                .split_once('=')
+                .ok_or_else(|| error("expected key = value"))?;
+            let key = key.trim();
+            let v = value(v).ok_or_else(|| error("invalid value"))?;
+            if key.is_empty() || v.is_empty() {
+                return Err(error("expected key = value"));
+            }
+
+            match section.as_str() {
+                "units" => config.units.push((key.to_owned(), v)),
+                _ => return Err(error(&format!("{} outside of a section", key))),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// The key of a `key = value` line of a valid configuration, which is always a unit.
+fn unit_key(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    if trimmed.starts_with('#') || trimmed.starts_with('[') {
+        return None;
+    }
+    trimmed.split_once('=').map(|(key, _)| key.trim())
+}
+
+/// A `name = "value"` line, with quotes and backslashes of `value` escaped.
+fn unit_line(name: &str, value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    format!("{} = \"{}\"", name, escaped)
+}
+
+/// `input`, a valid configuration, with unit `name` set to `value`. The first line defining it
+/// is replaced and any later ones removed, otherwise it's added at the end of the `[units]`
+/// section, which is created if needed. Comments and the other lines are kept as they are.
+pub fn set_unit(input: &str, name: &str, value: &str) -> String {
+    let mut lines: Vec<String> = input.lines().map(str::to_owned).collect();
+    match lines.iter().position(|l| unit_key(l) == Some(name)) {
+        Some(first) => {
+            lines[first] = unit_line(name, value);
+            lines = lines
+                .into_iter()
+                .enumerate()
+                .filter(|(i, l)| *i == first || unit_key(l) != Some(name))
+                .map(|(_, l)| l)
+                .collect();
+        }
+        None => {
+            // [units] is the only section, so its end is the end of the file
+            if !lines.iter().any(|l| l.trim().starts_with('[')) {
+                if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                    lines.push(String::new());
+                }
+                lines.push("[units]".to_owned());
+            }
+            lines.push(unit_line(name, value));
+        }
+    }
+
+    lines.iter().map(|l| format!("{}\n", l)).collect()
+}
+
+/// `input`, a valid configuration, without the lines defining unit `name`, `None` if there are
+/// none.
+pub fn remove_unit(input: &str, name: &str) -> Option<String> {
+    let lines: Vec<&str> = input.lines().collect();
+    let kept: Vec<&str> = lines
+        .iter()
+        .copied()
+        .filter(|l| unit_key(l) != Some(name))
+        .collect();
+    (kept.len() < lines.len()).then(|| kept.iter().map(|l| format!("{}\n", l)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(
+            "# team calendar\n[units]\nsprint = \"2w\" # two weeks\nshift = 7h 30m\n\nday=\"7h 42m\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.units,
+            vec![
+                ("sprint".to_owned(), "2w".to_owned()),
+                ("shift".to_owned(), "7h 30m".to_owned()),
+                ("day".to_owned(), "7h 42m".to_owned()),
+            ]
+        );
+
+        assert_eq!(
+            Config::parse("sprint = 2w\n"),
+            Err("line 1: sprint outside of a section".to_owned())
+        );
+        assert_eq!(
+            Config::parse("[colors]\n"),
+            Err("line 1: unknown section [colors]".to_owned())
+        );
+        assert_eq!(
+            Config::parse("[units]\nsprint = \"2w\" 3d\n"),
+            Err("line 2: invalid value".to_owned())
+        );
+        assert_eq!(Config::parse(""), Ok(Config::default()));
+    }
+
+    #[test]
+    fn test_set_unit() {
+        let input = "# team calendar\n[units]\nsprint = \"2w\" # two weeks\nshift = 7h 30m\n";
+        let output = set_unit(input, "shift", "8h");
+        assert_eq!(
+            output,
+            "# team calendar\n[units]\nsprint = \"2w\" # two weeks\nshift = \"8h\"\n"
+        );
+        let output = set_unit(&output, "day", "7h 42m");
+        assert!(output.ends_with("shift = \"8h\"\nday = \"7h 42m\"\n"));
+        assert_eq!(
+            Config::parse(&output).unwrap().units,
+            vec![
+                ("sprint".to_owned(), "2w".to_owned()),
+                ("shift".to_owned(), "8h".to_owned()),
+                ("day".to_owned(), "7h 42m".to_owned()),
+            ]
+        );
+
+        assert_eq!(set_unit("", "sprint", "2w"), "[units]\nsprint = \"2w\"\n");
+        assert_eq!(
+            set_unit("# units\n", "sprint", "2w"),
+            "# units\n\n[units]\nsprint = \"2w\"\n"
+        );
+        assert_eq!(
+            set_unit("[units]\na = 1h\nb = 2h\na = 3h\n", "a", "4h"),
+            "[units]\na = \"4h\"\nb = 2h\n"
+        );
+        let quoted = set_unit("", "odd", "1h \"x\"\\");
+        assert_eq!(Config::parse(&quoted).unwrap().units[0].1, "1h \"x\"\\");
+    }
+
+    #[test]
+    fn test_remove_unit() {
+        let input = "[units]\n# two weeks\nsprint = \"2w\"\nshift = 7h 30m\n";
+        assert_eq!(
+            remove_unit(input, "sprint"),
+            Some("[units]\n# two weeks\nshift = 7h 30m\n".to_owned())
+        );
+        assert_eq!(remove_unit(input, "day"), None);
+    }
+}
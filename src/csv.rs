@@ -0,0 +1,200 @@
+//! Import of time tracker CSV reports, e.g. the detailed exports of Toggl Track or Clockify.
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::report::Entry;
+
+/// Returns the records of a time tracker CSV export, labelled with their project. Columns are
+/// found by their header, case insensitive:
+///
+/// * `Duration` or `Duration (h)` as `h:mm:ss` or `h:mm`
+/// * `Start Date`, `Start Time`, `End Date` and `End Time`, used if there is no duration
+/// * `Project` and `Description`
+/// * `Tags`, comma separated
+///
+/// Records whose duration can be determined neither way are an error.
+pub fn entries(content: &str) -> Result<Vec<Entry>, String> {
+    let mut records = parse(content.trim_start_matches('\u{feff}')).into_iter();
+    let header: Vec<String> = records
+        .next()
+        .ok_or("empty CSV file")?
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+
+    let duration_column = column(&["duration", "duration (h)"]);
+    let start_columns = (column(&["start date"]), column(&["start time"]));
+    let end_columns = (column(&["end date"]), column(&["end time"]));
+    let project_column = column(&["project"]);
+    let description_column = column(&["description"]);
+    let tags_column = column(&["tags"]);
+
+    let mut entries = Vec::new();
+
+    for (i, record) in records.enumerate() {
+        // blank lines
+        if record.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+
+        let field = |c: Option<usize>| c.and_then(|c| record.get(c)).map(|f| f.trim());
+        let date_time = |(date, time): (Option<usize>, Option<usize>)| {
+            parse_date_time(field(date)?, field(time)?)
+        };
+
+        let start = date_time(start_columns);
+        let duration = field(duration_column)
+            .map(parse_duration)
+            .transpose()
+            .map_err(|e| format!("{} in record {}", e, i + 1))?
+            .flatten();
+        let duration = match (duration, start) {
+            (Some(d), _) => d,
+            (None, Some(s)) => date_time(end_columns)
+                .map(|e| e - s)
+                .ok_or_else(|| format!("no duration or end in record {}", i + 1))?,
+            (None, None) => return Err(format!("no duration or start in record {}", i + 1)),
+        };
+
+        let label = match field(project_column) {
+            Some(p) if !p.is_empty() => p.to_owned(),
+            _ => "(no project)".to_owned(),
+        };
+
+        entries.push(Entry {
+            label,
+            description: field(description_column).unwrap_or_default().to_owned(),
+            start,
+            duration,
+            tags: field(tags_column)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Splits CSV content into records of fields. Fields may be quoted, quotes inside quoted fields
+/// are doubled and quoted fields may span lines.
+fn parse(content: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records
+}
+
+/// Parses `1:30:00` or `1:30`, hours may exceed 24. `None` for other values, an error for
+/// durations beyond the longest.
+fn parse_duration(value: &str) -> Result<Option<Duration>, String> {
+    let Some(parts) = value
+        .split(':')
+        .map(|p| p.parse::<i64>().ok())
+        .collect::<Option<Vec<i64>>>()
+    else {
+        return Ok(None);
+    };
+
+    let (h, m, s) = match parts[..] {
+        [h, m, s] => (h, m, s),
+        [h, m] => (h, m, 0),
+        _ => return Ok(None),
+    };
+    Duration::try_hours(h)
+        .zip(Duration::try_minutes(m))
+        .and_then(|(h, m)| h.checked_add(&m))
+        .zip(Duration::try_seconds(s))
+        .and_then(|(hm, s)| hm.checked_add(&s))
+        .map(Some)
+        .ok_or_else(|| format!("duration {:?} out of range", value))
+}
+
+/// Parses the date and time formats of the common exports, e.g. `2025-01-06` or `01/06/2025`
+/// and `09:00:00` or `09:00 AM`.
+fn parse_date_time(date: &str, time: &str) -> Option<NaiveDateTime> {
+    let date = ["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y"]
+        .iter()
+        .find_map(|f| NaiveDate::parse_from_str(date, f).ok())?;
+    let time = ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"]
+        .iter()
+        .find_map(|f| NaiveTime::parse_from_str(time, f).ok())?;
+
+    Some(date.and_time(time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_toggl() {
+        let content = "\u{feff}User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags
+Jane,jane@example.com,ACME,Website,,\"Fix header, again\",Yes,2025-01-06,09:00:00,2025-01-06,10:30:00,01:30:00,
+Jane,jane@example.com,,,,Email,No,2025-01-06,11:00:00,2025-01-06,11:20:00,00:20:00,
+Jane,jane@example.com,ACME,Website,,Deploy,Yes,2025-01-07,09:00:00,2025-01-07,09:45:00,00:45:00,\"release, ops\"
+";
+        let entries = entries(content).unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].label, "Website");
+        assert_eq!(entries[0].description, "Fix header, again");
+        assert_eq!(entries[0].duration, Duration::minutes(90));
+        assert_eq!(entries[1].label, "(no project)");
+        assert!(entries[1].tags.is_empty());
+        assert_eq!(entries[2].tags, vec!["release", "ops"]);
+        assert_eq!(entries[2].start, parse_date_time("2025-01-07", "09:00:00"));
+    }
+
+    #[test]
+    fn test_entries_start_end() {
+        let content = "Project,Description,Start Date,Start Time,End Date,End Time
+Support,\"Customer \"\"A\"\"\",01/06/2025,11:00 PM,01/07/2025,01:15 AM
+";
+        let entries = entries(content).unwrap();
+
+        assert_eq!(entries[0].description, "Customer \"A\"");
+        assert_eq!(entries[0].duration, Duration::minutes(135));
+
+        assert!(super::entries("Project,Description\nSupport,Call\n").is_err());
+    }
+
+    #[test]
+    fn test_entries_out_of_range() {
+        assert_eq!(
+            entries("Project,Duration\nX,9999999999999:00:00\n").err(),
+            Some("duration \"9999999999999:00:00\" out of range in record 1".to_owned())
+        );
+        assert_eq!(parse_duration("1:30"), Ok(Some(Duration::minutes(90))));
+        assert_eq!(parse_duration("1.5"), Ok(None));
+    }
+}
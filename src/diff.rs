@@ -0,0 +1,190 @@
+//! Comparison of two sets of entries, e.g. planned and actual durations.
+
+use std::fmt;
+
+use chrono::Duration;
+
+use crate::options::Style;
+use crate::report::{Percent, Report};
+use crate::{DisplayableDuration, DurationCalculate};
+
+/// Durations of two reports side by side, per label.
+#[derive(Debug, PartialEq)]
+pub struct Diff {
+    /// Labels of the first and second report
+    pub names: (String, String),
+    /// label, first and second duration
+    pub rows: Vec<(String, Duration, Duration)>,
+}
+
+impl Diff {
+    /// Compares the totals of `a` and `b`.
+    pub fn totals(names: (String, String), a: &Report, b: &Report) -> Diff {
+        let rows = vec![("total".to_owned(), a.total(), b.total())];
+        Diff { names, rows }
+    }
+
+    /// Compares `a` and `b` label by label, labels only in `b` last.
+    pub fn groups(names: (String, String), a: &Report, b: &Report) -> Diff {
+        let find = |r: &Report, label: &str| {
+            r.groups
+                .iter()
+                .find(|(l, _)| l == label)
+                .map_or(Duration::zero(), |(_, d)| *d)
+        };
+
+        let rows = a
+            .groups
+            .iter()
+            .map(|(l, _)| l)
+            .chain(
+                b.groups
+                    .iter()
+                    .map(|(l, _)| l)
+                    .filter(|l| !a.groups.iter().any(|(al, _)| al == *l)),
+            )
+            .map(|l| (l.clone(), find(a, l), find(b, l)))
+            .collect();
+
+        Diff { names, rows }
+    }
+
+    /// Sorts the rows by label, see `Report::sort`.
+    pub fn sort(&mut self) {
+        self.rows.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+    }
+}
+
+/// A `Diff` as plain text table with the difference (second minus first) and the deviation
+/// relative to the first duration.
+pub struct DisplayableDiff<'a>(pub &'a Diff, pub Style);
+
+impl fmt::Display for DisplayableDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut table = vec![vec![
+            String::new(),
+            self.0.names.0.clone(),
+            self.0.names.1.clone(),
+            "diff".to_owned(),
+            "deviation".to_owned(),
+        ]];
+
+        for (label, a, b) in &self.0.rows {
+            let diff = b.saturated_sub(a);
+            table.push(vec![
+                label.clone(),
+                DisplayableDuration(*a, self.1).to_string(),
+                DisplayableDuration(*b, self.1).to_string(),
+                format!("{}{}", sign(diff), DisplayableDuration(diff, self.1)),
+                if a.is_zero() {
+                    Percent(diff, *a).to_string()
+                } else {
+                    format!("{}{}", sign(diff), Percent(diff, *a))
+                },
+            ]);
+        }
+
+        let widths: Vec<usize> = (0..5)
+            .map(|c| {
+                table
+                    .iter()
+                    .map(|r| r[c].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        for row in table {
+            writeln!(
+                f,
+                "{:w0$} {:>w1$} {:>w2$} {:>w3$} {:>w4$}",
+                row[0],
+                row[1],
+                row[2],
+                row[3],
+                row[4],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+                w3 = widths[3],
+                w4 = widths[4]
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Change from a baseline (first field) to a new duration (second field), signed and with the
+/// deviation in percent, e.g. `+0h 10m 00s (+20.0%)`.
+pub struct DisplayableDelta(pub Duration, pub Duration, pub Style);
+
+impl fmt::Display for DisplayableDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let diff = self.1.saturated_sub(&self.0);
+        write!(f, "{}{} (", sign(diff), DisplayableDuration(diff, self.2))?;
+        if !self.0.is_zero() {
+            write!(f, "{}", sign(diff))?;
+        }
+        write!(f, "{})", Percent(diff, self.0))
+    }
+}
+
+/// `+` for positive durations, negative ones bring their own sign.
+fn sign(d: Duration) -> &'static str {
+    if d > Duration::zero() {
+        "+"
+    } else {
+        ""
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(groups: &[(&str, i64)]) -> Report {
+        Report {
+            groups: groups
+                .iter()
+                .map(|(l, m)| (l.to_string(), Duration::minutes(*m)))
+                .collect(),
+            ..Report::default()
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let names = ("planned".to_owned(), "actual".to_owned());
+        let planned = report(&[("a", 60), ("b", 30)]);
+        let actual = report(&[("b", 45), ("c", 10)]);
+
+        let diff = Diff::groups(names.clone(), &planned, &actual);
+        assert_eq!(
+            diff.rows,
+            vec![
+                ("a".to_owned(), Duration::minutes(60), Duration::zero()),
+                ("b".to_owned(), Duration::minutes(30), Duration::minutes(45)),
+                ("c".to_owned(), Duration::zero(), Duration::minutes(10)),
+            ]
+        );
+
+        let diff = Diff::totals(names, &planned, &actual);
+        assert_eq!(
+            DisplayableDiff(&diff, Style::COMPACT).to_string(),
+            "       planned   actual      diff deviation
+total 1h30m00s 0h55m00s -0h35m00s    -38.9%
+"
+        );
+    }
+
+    #[test]
+    fn test_delta() {
+        let delta = |a, b| {
+            DisplayableDelta(Duration::minutes(a), Duration::minutes(b), Style::COMPACT).to_string()
+        };
+        assert_eq!(delta(50, 60), "+0h10m00s (+20.0%)");
+        assert_eq!(delta(60, 45), "-0h15m00s (-25.0%)");
+        assert_eq!(delta(0, 5), "+0h05m00s (-)");
+    }
+}
@@ -0,0 +1,203 @@
+//! Resident mode for editor plugins, answering JSON requests framed like the Language Server
+//! Protocol, i.e. each message is preceded by a `Content-Length: <bytes>` header and a blank line.
+//!
+//! Requests are `{"id": 1, "method": "parse", "params": {"text": "..."}}` and get the duration
+//! of each line of the text, diagnostics for the lines that cannot be parsed and the total.
+//! `shutdown` is answered with `null` and ends the loop.
+
+use std::io::{self, BufRead, Write};
+
+use chrono::Duration;
+
+use crate::json::Value;
+use crate::options::Style;
+use crate::validate::{validate, Diagnostic};
+use crate::{line_duration, DisplayableDuration, DurationCalculate};
+
+/// Upper limit for the size of a message, larger ones are an error.
+const MAX_MESSAGE: usize = 16 * 1024 * 1024;
+
+/// Reads the next message, `None` at the end of the input.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = length
+        .filter(|l| *l <= MAX_MESSAGE)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid Content-Length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+fn duration(d: Duration, style: Style) -> Value {
+    Value::Object(vec![
+        (
+            "duration".to_owned(),
+            Value::String(DisplayableDuration(d, style).to_string()),
+        ),
+        ("seconds".to_owned(), Value::Number(d.num_seconds() as f64)),
+    ])
+}
+
+fn diagnostic(line: usize, d: Diagnostic) -> Value {
+    let mut members = vec![
+        ("line".to_owned(), Value::Number(line as f64)),
+        ("column".to_owned(), Value::Number(d.column as f64)),
+        ("severity".to_owned(), Value::String(d.severity.to_string())),
+        ("message".to_owned(), Value::String(d.message)),
+    ];
+    if let Some(suggestion) = d.suggestion {
+        members.push(("suggestion".to_owned(), Value::String(suggestion)));
+    }
+    Value::Object(members)
+}
+
+/// The result of a `parse` request for `text`: per line its duration or `null` for blank and
+/// comment lines and lines with errors, the diagnostics of `validate` and the total.
+pub fn parse(text: &str, style: Style) -> Value {
+    let mut lines = Vec::new();
+    let mut diagnostics = Vec::new();
+    let mut total = Duration::zero();
+
+    for (i, line) in text.lines().enumerate() {
+        match line_duration(line) {
+            Ok(Some(d)) => {
+                total = total.saturated_add(&d);
+                lines.push(duration(d, style));
+            }
+            Ok(None) | Err(_) => lines.push(Value::Null),
+        }
+        diagnostics.extend(validate(line).into_iter().map(|d| diagnostic(i, d)));
+    }
+
+    Value::Object(vec![
+        ("lines".to_owned(), Value::Array(lines)),
+        ("diagnostics".to_owned(), Value::Array(diagnostics)),
+        ("total".to_owned(), duration(total, style)),
+    ])
+}
+
+/// Answers a single request, `Err` with a message for invalid ones.
+pub fn respond(request: &Value, style: Style) -> Result<Value, String> {
+    match request.get("method").and_then(Value::as_str) {
+        Some("parse") => request
+            .get("params")
+            .and_then(|p| p.get("text"))
+            .and_then(Value::as_str)
+            .map(|text| parse(text, style))
+            .ok_or_else(|| "parse needs params.text".to_owned()),
+        Some("shutdown") => Ok(Value::Null),
+        Some(method) => Err(format!("unknown method {:?}", method)),
+        None => Err("no method".to_owned()),
+    }
+}
+
+/// Answers requests from `reader` on `writer` until `shutdown` or the end of the input.
+pub fn run(reader: &mut impl BufRead, writer: &mut impl Write, style: Style) -> io::Result<()> {
+    while let Some(message) = read_message(reader)? {
+        let request = Value::parse(&message);
+        let shutdown = request
+            .as_ref()
+            .is_ok_and(|r| r.get("method").and_then(Value::as_str) == Some("shutdown"));
+        let (id, result) = match request {
+            Ok(request) => (
+                request.get("id").cloned().unwrap_or(Value::Null),
+                respond(&request, style),
+            ),
+            Err(e) => (Value::Null, Err(format!("invalid JSON: {}", e))),
+        };
+
+        let response = match result {
+            Ok(result) => ("result".to_owned(), result),
+            Err(message) => (
+                "error".to_owned(),
+                Value::Object(vec![("message".to_owned(), Value::String(message))]),
+            ),
+        };
+        write_message(
+            writer,
+            &Value::Object(vec![("id".to_owned(), id), response]),
+        )?;
+
+        if shutdown {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(body: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    #[test]
+    fn test_parse() {
+        let result = parse(
+            "2h # dev\n\n2025-01-06 30m +billable\n2h +\n1h 90m\n3hrs\n",
+            Style::default(),
+        );
+        assert_eq!(
+            result.to_string(),
+            r#"{"lines":[{"duration":"2h 00m 00s","seconds":7200},null,{"duration":"0h 30m 00s","seconds":1800},null,{"duration":"2h 30m 00s","seconds":9000},null],"diagnostics":[{"line":3,"column":0,"severity":"error","message":"cannot parse \"2h +\" as duration"},{"line":4,"column":3,"severity":"warning","message":"90m out of range next to a larger unit, expected less than 60"},{"line":5,"column":1,"severity":"error","message":"unknown unit \"hrs\"","suggestion":"h"}],"total":{"duration":"5h 00m 00s","seconds":18000}}"#
+        );
+    }
+
+    #[test]
+    fn test_run() {
+        let input = [
+            message(r#"{"id":1,"method":"parse","params":{"text":"1h\n-15m"}}"#),
+            message(r#"{"id":2,"method":"format"}"#),
+            message(r#"{"id":3,"method":"shutdown"}"#),
+            message(r#"{"id":4,"method":"parse","params":{"text":"1h"}}"#),
+        ]
+        .concat();
+        let mut output = Vec::new();
+        run(&mut input.as_bytes(), &mut output, Style::default()).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let mut reader = output.as_bytes();
+        let mut responses = Vec::new();
+        while let Some(m) = read_message(&mut reader).unwrap() {
+            responses.push(m);
+        }
+        assert_eq!(
+            responses,
+            vec![
+                r#"{"id":1,"result":{"lines":[{"duration":"1h 00m 00s","seconds":3600},{"duration":"-0h 15m 00s","seconds":-900}],"diagnostics":[],"total":{"duration":"0h 45m 00s","seconds":2700}}}"#,
+                r#"{"id":2,"error":{"message":"unknown method \"format\""}}"#,
+                r#"{"id":3,"result":null}"#,
+            ]
+        );
+
+        assert!(read_message(&mut "Content-Length: x\r\n\r\n".as_bytes()).is_err());
+    }
+}
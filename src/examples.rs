@@ -0,0 +1,98 @@
+//! A gallery of expressions for the `examples` subcommand, printed with the results of the parser
+//! and formatter of this build, and `selftest`, which checks them against the expected durations.
+
+use chrono::Duration;
+
+use crate::options::Style;
+use crate::output::Output;
+use crate::{line_duration, DisplayableDuration};
+
+/// Expressions and their expected durations in milliseconds, covering the grammar with the
+/// default settings.
+const EXAMPLES: &[(&str, i64)] = &[
+    ("1h 30m", 5_400_000),
+    ("2h - 15m", 6_300_000),
+    ("45m - 1h", -900_000),
+    ("1:30:00", 5_400_000),
+    ("1.5h", 5_400_000),
+    ("1w 2d", 777_600_000),
+    ("1y", 31_536_000_000),
+    ("1_000s 500ms", 1_000_500),
+    ("1e3s", 1_000_000),
+    ("90m # comment", 5_400_000),
+    ("2025-01-06 2h +billable", 7_200_000),
+    ("1h..2h", 5_400_000),
+    ("1h/2h/4h", 7_800_000),
+    ("2h ±30m + 4h~", 21_600_000),
+    ("30m daily for 1w", 12_600_000),
+    ("2h @5", 36_000_000),
+    ("10% of 8h", 2_880_000),
+    ("8h * 110%", 31_680_000),
+    ("max(0s, 40h - 42h)", 0),
+    ("clamp(50h, 0s, 40h)", 144_000_000),
+    ("abs(2h - 3h 30m)", 5_400_000),
+];
+
+/// Prints each example and its result like `1h 30m  →  1h 30m 00s`.
+pub fn print(out: &mut Output, style: Style) {
+    let width = EXAMPLES
+        .iter()
+        .map(|(e, _)| e.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (input, _) in EXAMPLES {
+        let result = match line_duration(input) {
+            Ok(d) => DisplayableDuration(d.unwrap_or_else(Duration::zero), style).to_string(),
+            Err(e) => format!("error: {}", e),
+        };
+        out.println_all(format_args!(
+            "{:<width$}  →  {}",
+            input,
+            result,
+            width = width
+        ));
+    }
+}
+
+/// The examples whose result differs from the expected duration, with a description of what
+/// went wrong.
+fn failures(style: Style) -> Vec<(&'static str, String)> {
+    let mut failures = Vec::new();
+    for (input, ms) in EXAMPLES {
+        let expected = Duration::milliseconds(*ms);
+        match line_duration(input) {
+            Ok(Some(d)) if d == expected => continue,
+            Ok(d) => {
+                let got = DisplayableDuration(d.unwrap_or_else(Duration::zero), style);
+                let expected = DisplayableDuration(expected, style);
+                failures.push((*input, format!("expected {}, got {}", expected, got)));
+            }
+            Err(e) => failures.push((*input, e)),
+        }
+    }
+    failures
+}
+
+/// Checks the examples and prints the failures and a summary. Returns whether all passed.
+pub fn selftest(out: &mut Output, style: Style) -> bool {
+    let failures = failures(style);
+    for (input, message) in &failures {
+        out.println_all(format_args!("FAIL {}: {}", input, message));
+    }
+    out.println_all(format_args!(
+        "{} examples, {} failed",
+        EXAMPLES.len(),
+        failures.len()
+    ));
+    failures.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_examples() {
+        assert_eq!(failures(Style::default()), vec![]);
+    }
+}
@@ -0,0 +1,212 @@
+//! Glob expansion for file arguments, for shells that don't do it themselves.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Expands a pattern with `*`, `?`, `[...]` and `**` (any number of directories) to the
+/// matching paths in sorted order. A pattern without any of these is returned as it is, whether
+/// the file exists or not. Like in shells, wildcards don't match a leading `.`.
+pub fn expand(pattern: &str) -> Result<Vec<String>, String> {
+    if !is_pattern(pattern) {
+        return Ok(vec![pattern.to_owned()]);
+    }
+
+    let mut candidates = vec![PathBuf::new()];
+
+    for component in Path::new(pattern).components() {
+        let part = match component {
+            Component::Normal(part) => part.to_string_lossy().into_owned(),
+            other => {
+                // root, prefix or `..`
+                for c in &mut candidates {
+                    c.push(other.as_os_str());
+                }
+                continue;
+            }
+        };
+
+        candidates = candidates
+            .iter()
+            .flat_map(|dir| match part.as_str() {
+                "**" => sub_directories(dir),
+                p if is_pattern(p) => list(dir)
+                    .into_iter()
+                    .filter(|name| matches(p, name))
+                    .map(|name| dir.join(name))
+                    .collect(),
+                p => vec![dir.join(p)],
+            })
+            .collect();
+    }
+
+    let mut paths: Vec<String> = candidates
+        .into_iter()
+        .filter(|p| p.is_file())
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    if paths.is_empty() {
+        return Err(format!("no files match {}", pattern));
+    }
+
+    Ok(paths)
+}
+
+fn is_pattern(s: &str) -> bool {
+    s.contains(['*', '?', '['])
+}
+
+fn list(dir: &Path) -> Vec<String> {
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `dir` itself and all directories below it, not following hidden ones.
+fn sub_directories(dir: &Path) -> Vec<PathBuf> {
+    let mut result = vec![dir.to_path_buf()];
+
+    for name in list(dir) {
+        let path = dir.join(&name);
+        if !name.starts_with('.') && path.is_dir() {
+            result.extend(sub_directories(&path));
+        }
+    }
+
+    result
+}
+
+/// Whether `name` matches the single component `pattern`.
+fn matches(pattern: &str, name: &str) -> bool {
+    if name.starts_with('.') && !pattern.starts_with('.') {
+        return false;
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    matches_from(&pattern, &name)
+}
+
+fn matches_from(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|i| matches_from(&pattern[1..], &name[i..])),
+        Some('?') => !name.is_empty() && matches_from(&pattern[1..], &name[1..]),
+        Some('[') => {
+            let end = match pattern.iter().skip(2).position(|c| *c == ']') {
+                Some(end) => end + 2,
+                // no closing bracket, a literal `[`
+                None => {
+                    return name.first() == Some(&'[') && matches_from(&pattern[1..], &name[1..])
+                }
+            };
+            let (negated, class) = match pattern[1] {
+                '!' | '^' => (true, &pattern[2..end]),
+                _ => (false, &pattern[1..end]),
+            };
+
+            match name.first() {
+                Some(c) if in_class(class, *c) != negated => {
+                    matches_from(&pattern[end + 1..], &name[1..])
+                }
+                _ => false,
+            }
+        }
+        Some(p) => name.first() == Some(p) && matches_from(&pattern[1..], &name[1..]),
+    }
+}
+
+/// Whether `c` is in a character class like `abc` or `a-z0-9`.
+fn in_class(class: &[char], c: char) -> bool {
+    let mut i = 0;
+
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            if (class[i]..=class[i + 2]).contains(&c) {
+                return true;
+            }
+            i += 3;
+        } else {
+            if class[i] == c {
+                return true;
+            }
+            i += 1;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let cases = vec![
+            ("*.txt", "week1.txt", true),
+            ("*.txt", "week1.csv", false),
+            ("*.txt", ".hidden.txt", false),
+            (".*", ".hidden", true),
+            ("2025-0?-*", "2025-01-06.txt", true),
+            ("2025-0?-*", "2025-10-06.txt", false),
+            ("week[1-3].txt", "week2.txt", true),
+            ("week[!1-3].txt", "week2.txt", false),
+            ("week[!1-3].txt", "week4.txt", true),
+            ("[abc", "[abc", true),
+        ];
+
+        for (pattern, name, expected) in cases {
+            assert_eq!(matches(pattern, name), expected, "{} {}", pattern, name);
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        let dir = std::env::temp_dir().join(format!("duration-glob-{}", std::process::id()));
+        fs::create_dir_all(dir.join("b/c")).unwrap();
+        for f in ["2.txt", "1.txt", "x.csv", "b/3.txt", "b/c/4.txt"] {
+            fs::write(dir.join(f), "").unwrap();
+        }
+        let d = dir.to_string_lossy();
+        let expand = |p: &str| {
+            expand(&format!("{}/{}", d, p)).map(|paths| {
+                paths
+                    .iter()
+                    .map(|p| p[d.len() + 1..].replace('\\', "/"))
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(
+            expand("*.txt"),
+            Ok(vec!["1.txt".to_owned(), "2.txt".to_owned()])
+        );
+        assert_eq!(
+            expand("**/*.txt"),
+            Ok(vec![
+                "1.txt".to_owned(),
+                "2.txt".to_owned(),
+                "b/3.txt".to_owned(),
+                "b/c/4.txt".to_owned()
+            ])
+        );
+        assert_eq!(expand("missing.txt"), Ok(vec!["missing.txt".to_owned()]));
+        assert!(expand("*.org").is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+//! Minimal HTTP/1.1 API on localhost, so other languages can use the parser without bindings.
+//!
+//! * `POST /sum` - sums up the duration lines of the body
+//! * `GET /parse?q=3h20m` - parses a single expression
+//!
+//! Both answer with a JSON object, errors with `{"error": "..."}` and a 4xx status.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time;
+
+use chrono::Duration;
+
+use crate::json::Value;
+use crate::options::{Options, Style};
+use crate::{
+    check_typos, expression, line_duration, DisplayableDuration, DurationCalculate, DurationParse,
+};
+
+/// Largest accepted request body.
+const MAX_BODY: usize = 1 << 20;
+
+/// How long reading a request or writing a response may stall before the connection is dropped,
+/// so idle clients don't keep their threads forever.
+const TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+/// Answers requests on `address` (e.g. `127.0.0.1:8080`), each connection in its own thread,
+/// until killed.
+pub fn serve(address: &str, options: Options) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    let options = Arc::new(options);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let options = Arc::clone(&options);
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &options) {
+                eprintln!("connection error: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+fn handle(stream: TcpStream, options: &Options) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let (status, body) = if content_length > MAX_BODY {
+        (413, error("request body too large"))
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        respond(request_line.trim_end(), &body, options)
+    };
+
+    let body = body.to_string();
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    )
+}
+
+/// Status and JSON body for a request line like `GET /parse?q=1h HTTP/1.1`.
+fn respond(request_line: &str, body: &[u8], options: &Options) -> (u16, Value) {
+    let mut parts = request_line.split(' ');
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    match (method, path) {
+        ("POST", "/sum") => {
+            let body = String::from_utf8_lossy(body);
+            let (mut total, mut lines) = (Duration::zero(), 0);
+            for (i, line) in body.lines().enumerate() {
+                let line = line.trim_end_matches('\r');
+                let source = format!("line {} of the request body", i + 1);
+                if let Err(e) = check_typos(&expression(line), &source) {
+                    return (400, error(&e));
+                }
+                match line_duration(line) {
+                    Ok(Some(d)) => {
+                        total = total.saturated_add(&d);
+                        lines += 1;
+                    }
+                    Ok(None) => {}
+                    Err(e) => return (400, error(&format!("line {}: {}", i + 1, e))),
+                }
+            }
+            let mut result = duration(total, options.style);
+            if let Value::Object(members) = &mut result {
+                members.push(("lines".to_owned(), Value::Number(lines as f64)));
+            }
+            (200, result)
+        }
+        ("GET", "/parse") => {
+            let q = query
+                .split('&')
+                .filter_map(|p| p.split_once('='))
+                .find(|(k, _)| *k == "q")
+                .map(|(_, v)| decode_component(v));
+            let Some(q) = q else {
+                return (400, error("missing query parameter q"));
+            };
+            if let Err(e) = check_typos(&expression(&q), "the query parameter q") {
+                return (400, error(&e));
+            }
+            match Duration::from_str(&q) {
+                Some(d) => (200, duration(d, options.style)),
+                None => (400, error(&format!("cannot parse {:?} as duration", q))),
+            }
+        }
+        (_, "/sum") | (_, "/parse") => (405, error("method not allowed")),
+        _ => (404, error("not found")),
+    }
+}
+
+fn duration(d: Duration, style: Style) -> Value {
+    Value::Object(vec![
+        (
+            "duration".to_owned(),
+            Value::String(DisplayableDuration(d, style).to_string()),
+        ),
+        ("seconds".to_owned(), Value::Number(d.num_seconds() as f64)),
+    ])
+}
+
+fn error(message: &str) -> Value {
+    Value::Object(vec![(
+        "error".to_owned(),
+        Value::String(message.to_owned()),
+    )])
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "",
+    }
+}
+
+/// Decodes `%XX` escapes and `+` for spaces of a query component. Invalid escapes are kept.
+fn decode_component(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_respond() {
+        let options = Options::default();
+        let respond = |line, body: &str| {
+            let (status, value) = respond(line, body.as_bytes(), &options);
+            (status, value.to_string())
+        };
+
+        assert_eq!(
+            respond("GET /parse?q=3h20m HTTP/1.1", ""),
+            (
+                200,
+                r#"{"duration":"3h 20m 00s","seconds":12000}"#.to_owned()
+            )
+        );
+        assert_eq!(
+            respond("GET /parse?q=1h+-%2010m HTTP/1.1", ""),
+            (
+                200,
+                r#"{"duration":"0h 50m 00s","seconds":3000}"#.to_owned()
+            )
+        );
+        assert_eq!(
+            respond("POST /sum HTTP/1.1", "1h\n30m # review\n"),
+            (
+                200,
+                r#"{"duration":"1h 30m 00s","seconds":5400,"lines":2}"#.to_owned()
+            )
+        );
+        assert_eq!(
+            respond("POST /sum HTTP/1.1", "1h\nsoon\n"),
+            (
+                400,
+                r#"{"error":"line 2: cannot parse \"soon\" as duration"}"#.to_owned()
+            )
+        );
+        assert_eq!(respond("GET /parse?q=soon HTTP/1.1", "").0, 400);
+        assert_eq!(respond("GET /parse HTTP/1.1", "").0, 400);
+        assert_eq!(respond("GET /sum HTTP/1.1", "").0, 405);
+        assert_eq!(respond("GET / HTTP/1.1", "").0, 404);
+    }
+
+    #[test]
+    fn test_decode_component() {
+        assert_eq!(decode_component("1h+30m%20%2B5m"), "1h 30m +5m");
+        assert_eq!(decode_component("100%"), "100%");
+    }
+}
@@ -0,0 +1,177 @@
+//! Minimal iCalendar (RFC 5545) parsing, just enough to sum up event durations.
+
+use chrono::{Duration, NaiveDateTime};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::report::Entry;
+
+/// Returns the timed events of an iCalendar file, labelled with their summary. All-day events,
+/// events without an end or duration and events with unparsable dates are skipped. Recurrence
+/// rules are not expanded, i.e. a recurring event counts once. Times are taken as they are,
+/// regardless of time zone.
+pub fn events(content: &str) -> Vec<Entry> {
+    let mut events = Vec::new();
+
+    let mut in_event = false;
+    let mut summary = String::new();
+    let mut start = None;
+    let mut end = None;
+    let mut duration = None;
+
+    for line in unfold(content.trim_start_matches('\u{feff}')) {
+        let (name, value) = match line.split_once(':') {
+            Some(nv) => nv,
+            None => continue,
+        };
+        // DTSTART;TZID=Europe/Berlin -> DTSTART
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+
+        match (name, value) {
+            ("BEGIN", "VEVENT") => {
+                in_event = true;
+                summary.clear();
+                start = None;
+                end = None;
+                duration = None;
+            }
+            ("END", "VEVENT") if in_event => {
+                in_event = false;
+                let duration = match (start, end, duration) {
+                    (Some(s), Some(e), _) => Some(e - s),
+                    (Some(_), None, d) => d,
+                    _ => None,
+                };
+                if let (Some(start), Some(duration)) = (start, duration) {
+                    events.push(Entry {
+                        label: summary.clone(),
+                        description: summary.clone(),
+                        start: Some(start),
+                        duration,
+                        tags: Vec::new(),
+                    });
+                }
+            }
+            _ if !in_event => {}
+            ("SUMMARY", v) => summary = unescape(v),
+            ("DTSTART", v) if !params.contains("VALUE=DATE") => start = parse_date_time(v),
+            ("DTEND", v) if !params.contains("VALUE=DATE") => end = parse_date_time(v),
+            ("DURATION", v) => duration = parse_duration(v),
+            _ => {}
+        }
+    }
+
+    events
+}
+
+/// Joins folded lines, i.e. lines starting with a space or tab continue the previous line.
+fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// Parses `20250105T090000` or `20250105T090000Z`. Date values return `None`.
+fn parse_date_time(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S").ok()
+}
+
+/// Parses an iCalendar duration like `PT1H30M`, `P1DT12H` or `-P2W`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    lazy_static! {
+        static ref ICS_DURATION_PATTERN: Regex = Regex::new(
+            r"^(?P<sign>[+-])?P(?:(?P<w>\d+)W)?(?:(?P<d>\d+)D)?(?:T(?:(?P<h>\d+)H)?(?:(?P<m>\d+)M)?(?:(?P<s>\d+)S)?)?$"
+        )
+        .unwrap();
+    }
+
+    let caps = ICS_DURATION_PATTERN.captures(value)?;
+    let count = |name: &str| {
+        caps.name(name)
+            .map_or(Some(0), |c| c.as_str().parse::<i64>().ok())
+    };
+
+    let duration = Duration::weeks(count("w")?)
+        + Duration::days(count("d")?)
+        + Duration::hours(count("h")?)
+        + Duration::minutes(count("m")?)
+        + Duration::seconds(count("s")?);
+
+    match caps.name("sign").map(|s| s.as_str()) {
+        Some("-") => Some(-duration),
+        _ => Some(duration),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+SUMMARY:Weekly sync\\, team A\r
+DTSTART;TZID=Europe/Berlin:20250106T090000\r
+DTEND;TZID=Europe/Berlin:20250106T093000\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Planning with a very long summary that got\r
+  folded\r
+DTSTART:20250107T130000Z\r
+DURATION:PT1H15M\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Holiday\r
+DTSTART;VALUE=DATE:20250108\r
+DTEND;VALUE=DATE:20250109\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn test_events() {
+        let events = events(CALENDAR);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].label, "Weekly sync, team A");
+        assert_eq!(events[0].duration, Duration::minutes(30));
+        assert_eq!(
+            events[1].description,
+            "Planning with a very long summary that got folded"
+        );
+        assert_eq!(events[1].duration, Duration::minutes(75));
+        assert!(events[1].in_date_range(NaiveDate::from_ymd_opt(2025, 1, 7), None));
+        assert!(!events[1].in_date_range(None, NaiveDate::from_ymd_opt(2025, 1, 6)));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let cases = vec![
+            ("PT45M", Some(Duration::minutes(45))),
+            ("P1DT2H", Some(Duration::hours(26))),
+            ("P1W", Some(Duration::days(7))),
+            ("-PT30S", Some(-Duration::seconds(30))),
+            ("1H", None),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse_duration(input), expected, "{}", input);
+        }
+    }
+}
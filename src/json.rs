@@ -0,0 +1,226 @@
+//! A small JSON value type with parser and serializer, enough for the imports and exports.
+
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// Keys in document order
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Returns the member `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Parses a complete JSON document.
+    pub fn parse(input: &str) -> Result<Value, String> {
+        let mut chars = input.chars().peekable();
+        let value = parse_value(&mut chars)?;
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            None => Ok(value),
+            Some(c) => Err(format!("unexpected {:?} after JSON value", c)),
+        }
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn expect(chars: &mut Peekable<Chars>, expected: &str) -> Result<(), String> {
+    for e in expected.chars() {
+        match chars.next() {
+            Some(c) if c == e => {}
+            c => return Err(format!("expected {:?}, found {:?}", expected, c)),
+        }
+    }
+    Ok(())
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Result<Value, String> {
+    skip_whitespace(chars);
+
+    match chars.peek() {
+        Some('n') => expect(chars, "null").map(|_| Value::Null),
+        Some('t') => expect(chars, "true").map(|_| Value::Bool(true)),
+        Some('f') => expect(chars, "false").map(|_| Value::Bool(false)),
+        Some('"') => parse_string(chars).map(Value::String),
+        Some('[') => {
+            chars.next();
+            let mut array = Vec::new();
+            skip_whitespace(chars);
+            if chars.next_if_eq(&']').is_some() {
+                return Ok(Value::Array(array));
+            }
+            loop {
+                array.push(parse_value(chars)?);
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some(']') => return Ok(Value::Array(array)),
+                    c => return Err(format!("expected ',' or ']', found {:?}", c)),
+                }
+            }
+        }
+        Some('{') => {
+            chars.next();
+            let mut members = Vec::new();
+            skip_whitespace(chars);
+            if chars.next_if_eq(&'}').is_some() {
+                return Ok(Value::Object(members));
+            }
+            loop {
+                skip_whitespace(chars);
+                let key = parse_string(chars)?;
+                skip_whitespace(chars);
+                expect(chars, ":")?;
+                members.push((key, parse_value(chars)?));
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some('}') => return Ok(Value::Object(members)),
+                    c => return Err(format!("expected ',' or '}}', found {:?}", c)),
+                }
+            }
+        }
+        Some(c) if *c == '-' || c.is_ascii_digit() => {
+            let mut number = String::new();
+            while let Some(c) =
+                chars.next_if(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+            {
+                number.push(c);
+            }
+            number
+                .parse()
+                .map(Value::Number)
+                .map_err(|_| format!("invalid number {:?}", number))
+        }
+        c => Err(format!("unexpected {:?}", c)),
+    }
+}
+
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    expect(chars, "\"")?;
+    let mut s = String::new();
+
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(s),
+            Some('\\') => match chars.next() {
+                Some('n') => s.push('\n'),
+                Some('t') => s.push('\t'),
+                Some('r') => s.push('\r'),
+                Some('b') => s.push('\u{8}'),
+                Some('f') => s.push('\u{c}'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    let code = u32::from_str_radix(&hex, 16)
+                        .map_err(|_| format!("invalid escape \\u{}", hex))?;
+                    // surrogate pairs are not combined, but at least they don't fail
+                    s.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                Some(c) => s.push(c),
+                None => return Err("unterminated string".to_owned()),
+            },
+            Some(c) => s.push(c),
+            None => return Err("unterminated string".to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    /// Serializes the value as compact JSON.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) if n.is_finite() => write!(f, "{}", n),
+            Value::Number(_) => write!(f, "null"),
+            Value::String(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        '\r' => write!(f, "\\r")?,
+                        '\t' => write!(f, "\\t")?,
+                        c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Value::Array(a) => {
+                write!(f, "[")?;
+                for (i, v) in a.iter().enumerate() {
+                    write!(f, "{}{}", if i > 0 { "," } else { "" }, v)?;
+                }
+                write!(f, "]")
+            }
+            Value::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in members.iter().enumerate() {
+                    let key = Value::String(k.clone());
+                    write!(f, "{}{}:{}", if i > 0 { "," } else { "" }, key, v)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let input =
+            r#" [{"id": 1, "tags": ["a", "b\"c"], "x": null, "y": true, "z": -1.5e2}, {}] "#;
+        let value = Value::parse(input).unwrap();
+
+        let first = &value.as_array().unwrap()[0];
+        assert_eq!(first.get("id"), Some(&Value::Number(1.0)));
+        assert_eq!(
+            first.get("tags").unwrap().as_array().unwrap()[1].as_str(),
+            Some("b\"c")
+        );
+        assert_eq!(first.get("z"), Some(&Value::Number(-150.0)));
+        assert_eq!(
+            value.to_string(),
+            r#"[{"id":1,"tags":["a","b\"c"],"x":null,"y":true,"z":-150},{}]"#
+        );
+
+        assert!(Value::parse("[1, 2").is_err());
+        assert!(Value::parse("{\"a\" 1}").is_err());
+        assert!(Value::parse("1 2").is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! Mapping of raw labels to display names, e.g. ticket IDs to projects, see `--label-map`.
+
+/// Label for groups that no rule of a `LabelMap` matches.
+pub const OTHER: &str = "other";
+
+/// Rules from a mapping file, one `<label> = <name>` per line. A label ending in `*` matches
+/// every label starting with the rest, e.g. `PROJ-* = Project`. `#` starts a comment.
+#[derive(Debug, Default, PartialEq)]
+pub struct LabelMap {
+    /// label or prefix, whether it is a prefix, and the name, in file order
+    rules: Vec<(String, bool, String)>,
+}
+
+impl LabelMap {
+    pub fn parse(input: &str) -> Result<LabelMap, String> {
+        let mut rules = Vec::new();
+
+        for (i, line) in input.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (label, name) = line
+                .split_once('=')
+                .map(|(l, n)| (l.trim(), n.trim()))
+                .filter(|(l, n)| !l.is_empty() && !n.is_empty())
+                .ok_or_else(|| format!("line {}: expected <label> = <name>", i + 1))?;
+
+            rules.push(match label.strip_suffix('*') {
+                Some(prefix) => (prefix.to_owned(), true, name.to_owned()),
+                None => (label.to_owned(), false, name.to_owned()),
+            });
+        }
+
+        Ok(LabelMap { rules })
+    }
+
+    /// The name of the first rule matching `label`, `OTHER` if there is none.
+    pub fn map(&self, label: &str) -> &str {
+        self.rules
+            .iter()
+            .find(|(l, prefix, _)| {
+                if *prefix {
+                    label.starts_with(l)
+                } else {
+                    label == l
+                }
+            })
+            .map_or(OTHER, |(_, _, name)| name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map() {
+        let map = LabelMap::parse(
+            "# tickets\nPROJ-1 = Special\nPROJ-* = Project\n\nOPS-* = Operations # on call\n",
+        )
+        .unwrap();
+
+        assert_eq!(map.map("PROJ-1"), "Special");
+        assert_eq!(map.map("PROJ-12"), "Project");
+        assert_eq!(map.map("OPS-7"), "Operations");
+        assert_eq!(map.map("lunch"), OTHER);
+
+        assert_eq!(
+            LabelMap::parse("a = b\nno name =\n"),
+            Err("line 2: expected <label> = <name>".to_owned())
+        );
+    }
+}
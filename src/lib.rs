@@ -0,0 +1,1893 @@
+//! Parsing, arithmetic and formatting of duration strings like `2h 30m - 15m`, shared by the
+//! `duration-calculator-rs` command line, its benchmarks and its fuzz target.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::RwLock;
+
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+pub mod clock;
+pub mod log;
+pub mod style;
+pub mod validate;
+
+use style::{CompactStyle, NegativeStyle, Style};
+
+/// Sums up the first capture group (or the whole match if there is none) of every match of
+/// `pattern` in `line`, each parsed as a duration. Fails on the first one that isn't.
+pub fn extract_durations(pattern: &Regex, line: &str) -> Result<Duration, String> {
+    pattern
+        .captures_iter(line)
+        .try_fold(Duration::zero(), |d, caps| {
+            let m = caps.get(1).or_else(|| caps.get(0)).unwrap().as_str();
+            check_limits(m)?;
+            Duration::from_str(m)
+                .map(|t| d.saturated_add(&t))
+                .ok_or_else(|| {
+                    format!("cannot parse {:?} extracted from {:?} as duration", m, line)
+                })
+        })
+}
+
+/// Multiplies `d` by a decimal factor like `1.2`, exactly up to milliseconds. Returns `None`
+/// for an invalid factor or on overflow.
+pub fn scale(d: Duration, factor: &str) -> Option<Duration> {
+    let (whole, fraction) = factor.split_once('.').unwrap_or((factor, ""));
+    let digits = format!("{}{}", whole, fraction);
+    if whole.is_empty() || fraction.len() > 9 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let numerator: i128 = digits.parse().ok()?;
+    let denominator = 10i128.pow(fraction.len() as u32);
+    let ms = d.num_milliseconds() as i128 * numerator;
+    // round half away from zero
+    let ms = (ms.abs() + denominator / 2) / denominator * ms.signum();
+    Duration::try_milliseconds(ms.try_into().ok()?)
+}
+
+/// A tag like `+billable` with the tag name as first capture. Tags start with a letter, so
+/// they don't clash with added durations like `+30m`.
+const TAG: &str = r"(?:^|\s)\+([\pL_][\w-]*)";
+
+/// Takes tags like `+billable` out of a line, up to the comment, see `TAG`.
+pub fn split_tags(line: &str) -> (Vec<String>, String) {
+    lazy_static! {
+        static ref TAG_PATTERN: Regex = Regex::new(TAG).unwrap();
+    }
+
+    let (content, comment) = match line.split_once('#') {
+        Some((content, comment)) => (content, Some(comment)),
+        None => (line, None),
+    };
+    let tags = TAG_PATTERN
+        .captures_iter(content)
+        .map(|caps| caps[1].to_owned())
+        .collect();
+    let mut rest = TAG_PATTERN.replace_all(content, " ").into_owned();
+    if let Some(comment) = comment {
+        rest = format!("{}#{}", rest, comment);
+    }
+
+    (tags, rest)
+}
+
+/// A line without its leading key, date and tags, e.g. `2h 30m # dev` of
+/// `2025-01-06 2h 30m +billable # dev`.
+pub fn expression(line: &str) -> String {
+    let (_, rest) = split_key(line);
+    let (_, rest) = split_date(rest);
+    split_tags(rest).1
+}
+
+/// The duration of a single line like `2025-01-06 2h 30m +billable # dev`, `None` for blank
+/// and comment lines. Dates and tags are skipped.
+pub fn line_duration(line: &str) -> Result<Option<Duration>, String> {
+    let rest = expression(line);
+    if rest.split('#').next().unwrap().trim().is_empty() {
+        return Ok(None);
+    }
+
+    check_limits(&rest)?;
+    Duration::from_str(rest.trim())
+        .map(Some)
+        .ok_or_else(|| format!("cannot parse {:?} as duration", line.trim()))
+}
+
+/// Most bytes of a line before its comment the parser accepts, see `check_limits`.
+const MAX_LINE_LENGTH: usize = 4096;
+/// Most signed terms like `- 15m` of a line.
+const MAX_TERMS: usize = 256;
+/// Most numbers of a line.
+const MAX_NUMBERS: usize = 1024;
+
+/// Rejects lines beyond the limits of the parser, so pathological input like thousands of sign
+/// groups from untrusted log data fails right away instead of being expanded and evaluated.
+pub fn check_limits(line: &str) -> Result<(), String> {
+    let line = line.split('#').next().unwrap();
+    let numbers = line
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|n| !n.is_empty());
+    if line.len() > MAX_LINE_LENGTH {
+        Err(format!("line longer than {} bytes", MAX_LINE_LENGTH))
+    } else if line.matches(['+', '-']).count() > MAX_TERMS {
+        Err(format!("more than {} terms", MAX_TERMS))
+    } else if numbers.count() > MAX_NUMBERS {
+        Err(format!("more than {} numbers", MAX_NUMBERS))
+    } else {
+        Ok(())
+    }
+}
+
+/// How much lower and higher than its duration a line with ranges like `2h..3h` can be, `None`
+/// for lines without ranges. Dates and tags are skipped.
+pub fn range_spread(line: &str, now: NaiveDateTime) -> Option<(Duration, Duration)> {
+    let rest = expression(line);
+    let rest = rest.trim();
+    if !rest.split('#').next().unwrap().contains("..") {
+        return None;
+    }
+
+    let bound = |b| evaluate(rest, now, b, &mut |_| {});
+    let middle = bound(Bound::Middle)?;
+    Some((bound(Bound::Low)? - middle, bound(Bound::High)? - middle))
+}
+
+/// Splits the key off a line of a keyed accumulator like `mon=8h 15m`.
+pub fn split_key(line: &str) -> (Option<&str>, &str) {
+    lazy_static! {
+        static ref KEY_PATTERN: Regex = Regex::new(r"^\s*([\pL_]\w*)\s*=").unwrap();
+    }
+
+    match KEY_PATTERN.captures(line) {
+        Some(caps) => (Some(caps.get(1).unwrap().as_str()), &line[caps[0].len()..]),
+        None => (None, line),
+    }
+}
+
+/// Replaces the keys of accumulators in an expression like `mon + tue - 16h` with their sums in
+/// milliseconds. The sign in front of a negative sum is flipped.
+pub fn expand_keys(line: &str, sums: &[(String, Duration)]) -> String {
+    lazy_static! {
+        static ref NAME_PATTERN: Regex =
+            Regex::new(r"(?P<sign>[+-]?)(?P<space>\s*)\b(?P<name>[\pL_]\w*)\b").unwrap();
+    }
+
+    NAME_PATTERN
+        .replace_all(line, |caps: &regex::Captures| {
+            let Some((_, sum)) = sums.iter().find(|(k, _)| *k == caps["name"]) else {
+                return caps[0].to_owned();
+            };
+            signed_ms(&caps["sign"], &caps["space"], *sum)
+        })
+        .into_owned()
+}
+
+/// Replaces references to earlier results in `--batch`, `$prev` for the one of the line before
+/// and `$3` for the one of line 3, with them in milliseconds. `history` holds the result of each
+/// line so far, `None` for lines without one.
+pub fn expand_history(line: &str, history: &[Option<Duration>]) -> Result<String, String> {
+    lazy_static! {
+        static ref REFERENCE_PATTERN: Regex =
+            Regex::new(r"(?P<sign>[+-]?)(?P<space>\s*)\$(?P<ref>prev\b|\d+)").unwrap();
+    }
+
+    let (expression, comment) = line.split_at(line.find('#').unwrap_or(line.len()));
+    let mut error = None;
+    let expression = REFERENCE_PATTERN.replace_all(expression, |caps: &regex::Captures| {
+        let result = match &caps["ref"] {
+            "prev" => history.last(),
+            n => n
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| history.get(n.checked_sub(1)?)),
+        };
+        match result {
+            Some(Some(d)) => signed_ms(&caps["sign"], &caps["space"], *d),
+            _ => {
+                error.get_or_insert_with(|| format!("no result for ${}", &caps["ref"]));
+                String::new()
+            }
+        }
+    });
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(format!("{}{}", expression, comment)),
+    }
+}
+
+/// `d` in milliseconds after `sign` (`+`, `-` or none) and `space`, flipping the sign if `d` is
+/// negative.
+fn signed_ms(sign: &str, space: &str, d: Duration) -> String {
+    let ms = d.num_milliseconds();
+    let sign = match (sign, ms < 0) {
+        ("-", false) | ("+" | "", true) => "-",
+        ("", false) => "",
+        _ => "+",
+    };
+    format!("{}{}{}ms", sign, space, ms.unsigned_abs())
+}
+
+/// Splits a leading date like `2025-01-06` off a line.
+pub fn split_date(line: &str) -> (Option<NaiveDate>, &str) {
+    lazy_static! {
+        static ref DATE_PATTERN: Regex = Regex::new(r"^\s*(\d{4}-\d{2}-\d{2})(?:\s+|$)").unwrap();
+    }
+
+    match DATE_PATTERN.captures(line) {
+        Some(caps) => match NaiveDate::parse_from_str(&caps[1], "%Y-%m-%d") {
+            Ok(date) => (Some(date), &line[caps[0].len()..]),
+            Err(_) => (None, line),
+        },
+        None => (None, line),
+    }
+}
+
+/// Parses a timestamp like `2025-01-06T09:00`, `2025-01-06 09:00:30` or a date like
+/// `2025-01-06` (midnight). `now` is accepted as the current time.
+pub fn parse_datetime(input: &str, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    if input == "now" {
+        return Some(now);
+    }
+
+    for format in [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ] {
+        if let Ok(at) = NaiveDateTime::parse_from_str(input, format) {
+            return Some(at);
+        }
+    }
+
+    NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .ok()
+        .map(|d| d.and_time(NaiveTime::MIN))
+}
+
+/// A duration in hours, minutes and seconds, e.g. `1h 05m 00s`, or compact in the shape of the
+/// `Style`.
+pub struct DisplayableDuration(pub Duration, pub Style);
+
+impl fmt::Display for DisplayableDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sgn = match Duration::zero().cmp(&self.0) {
+            /* so, er, if 0 is greater than the duration, the sign is negative. I'm
+            deleting this from working memory and hopefully never have to look again.
+            */
+            Ordering::Greater => -1,
+            _ => 1,
+        };
+
+        /*  if the duration is negative, display sign prefixing the whole duration,
+           but keep the portions positive. -2h-05m-20s looks odd, doesn't it?
+        */
+        let n = sgn * self.0.num_seconds();
+        let style = self.1;
+        let mut buf = [0; HMS_LENGTH];
+        let unsigned = if !style.compact && !style.unpadded && style.hour_width <= 20 {
+            // the default style of the per-line modes, without the format! machinery
+            Cow::Borrowed(hms(n.unsigned_abs(), style.hour_width, &mut buf))
+        } else {
+            Cow::Owned(Self::unsigned(n, style))
+        };
+
+        match (sgn < 0, style.negative_style) {
+            (false, _) => f.write_str(&unsigned),
+            (true, NegativeStyle::Minus) => write!(f, "-{}", unsigned),
+            (true, NegativeStyle::Parentheses) => write!(f, "({})", unsigned),
+            (true, NegativeStyle::Trailing) => write!(f, "{}-", unsigned),
+        }
+    }
+}
+
+/// Longest output of `hms`: 20 digits of hours (padded) and `h 00m 00s`.
+const HMS_LENGTH: usize = 29;
+
+/// Formats `n` seconds like `12h 05m 09s` into the end of `buf`, with the hours padded with
+/// zeros to `hour_width` (at most 20) digits.
+fn hms(n: u64, hour_width: u8, buf: &mut [u8; HMS_LENGTH]) -> &str {
+    let (minutes, seconds) = ((n % 3600 / 60) as u8, (n % 60) as u8);
+    let mut i = buf.len() - 9;
+    buf[i..].copy_from_slice(&[
+        b'h',
+        b' ',
+        b'0' + minutes / 10,
+        b'0' + minutes % 10,
+        b'm',
+        b' ',
+        b'0' + seconds / 10,
+        b'0' + seconds % 10,
+        b's',
+    ]);
+
+    let end = i;
+    let mut hours = n / 3600;
+    while hours > 0 || i == end || end - i < hour_width as usize {
+        i -= 1;
+        buf[i] = b'0' + (hours % 10) as u8;
+        hours /= 10;
+    }
+
+    std::str::from_utf8(&buf[i..]).unwrap()
+}
+
+impl DisplayableDuration {
+    /// `n` seconds in `style`, without the sign.
+    fn unsigned(n: i64, style: Style) -> String {
+        let hours = n / 3600;
+        let minutes = (n % 3600) / 60;
+        let seconds = n % 60;
+
+        let hours = format!("{:0w$}", hours, w = style.hour_width as usize);
+        let (minutes, seconds) = if style.unpadded {
+            (minutes.to_string(), seconds.to_string())
+        } else {
+            (format!("{:02}", minutes), format!("{:02}", seconds))
+        };
+
+        if !style.compact {
+            format!("{}h {}m {}s", hours, minutes, seconds)
+        } else {
+            match style.compact_style {
+                CompactStyle::Colon => format!("{}:{}:{}", hours, minutes, seconds),
+                CompactStyle::Underscore => format!("{}h_{}m_{}s", hours, minutes, seconds),
+                CompactStyle::Narrow if n < 3600 => format!("{}m{}s", n / 60, seconds),
+                CompactStyle::Units | CompactStyle::Narrow => {
+                    format!("{}h{}m{}s", hours, minutes, seconds)
+                }
+            }
+        }
+    }
+}
+
+/// A trait for performing arithmetic operations on durations not already covered in the standard
+pub trait DurationCalculate {
+    /// Adds two durations and returns the result or maximum value for overflow
+    fn saturated_add(&self, rhs: &Self) -> Self;
+
+    /// Adds two durations and returns the result or minimum value for overflow
+    fn saturated_sub(&self, rhs: &Self) -> Self;
+}
+
+impl DurationCalculate for Duration {
+    fn saturated_add(&self, rhs: &Duration) -> Duration {
+        self.checked_add(rhs).unwrap_or(Duration::MAX)
+    }
+
+    fn saturated_sub(&self, rhs: &Duration) -> Duration {
+        self.checked_sub(rhs).unwrap_or(Duration::MIN)
+    }
+}
+
+/// A trait for parsing duration strings.
+pub trait DurationParse {
+    /// Parses a "line" of a duration string and returns a `Duration` or `None` if the input is invalid.
+    fn from_str(input: &str) -> Option<Duration>;
+
+    /// Same as `from_str`, but resolves `until` terms relative to `now` instead of the local time.
+    fn from_str_at(input: &str, now: NaiveDateTime) -> Option<Duration>;
+
+    /// Sums up every duration-looking token (e.g. "took 3m 42s") in free-form text, ignoring
+    /// everything else including signs.
+    fn scan(input: &str) -> Duration;
+
+    /// Converts the smallest token (e.g. "5m", "4s") to a `Duration` object or `None` for invalid input.
+    fn token_to_duration(count: i64, unit: &str) -> Option<Duration>;
+
+    /// Same as `token_to_duration`, but the count may have a fraction, e.g. "75.3" and "s".
+    /// Digits beyond nanoseconds are ignored.
+    fn fractional_token_to_duration(count: &str, unit: &str) -> Option<Duration>;
+}
+
+/// What happens to lines with likely typos, like units used twice within a composite.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Policy {
+    /// Used silently
+    #[default]
+    Allow,
+    /// Used with a warning
+    Warn,
+    /// The line is rejected
+    Error,
+}
+
+impl FromStr for Policy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Policy::Allow),
+            "warn" => Ok(Policy::Warn),
+            "error" => Ok(Policy::Error),
+            _ => Err(format!(
+                "invalid policy {:?}, expected error, warn or allow",
+                s
+            )),
+        }
+    }
+}
+
+/// Whether an uppercase `M` unit means months (30 days) rather than minutes, see `--month-unit`.
+static UPPERCASE_MONTHS: AtomicBool = AtomicBool::new(false);
+
+/// Whether units of varying real length like days or years are rejected, see
+/// `--no-calendar-units`.
+static NO_CALENDAR_UNITS: AtomicBool = AtomicBool::new(false);
+
+/// Policy for units used twice within a composite, see `--dup-units`.
+static DUPLICATE_UNITS: RwLock<Policy> = RwLock::new(Policy::Allow);
+
+/// Policy for components beyond their natural bound, see `--sane`.
+static SANE: RwLock<Policy> = RwLock::new(Policy::Allow);
+
+pub fn set_uppercase_months(months: bool) {
+    UPPERCASE_MONTHS.store(months, atomic::Ordering::Relaxed);
+}
+
+pub fn set_no_calendar_units(reject: bool) {
+    NO_CALENDAR_UNITS.store(reject, atomic::Ordering::Relaxed);
+}
+
+pub fn set_policies(duplicate_units: Policy, sane: Policy) {
+    *DUPLICATE_UNITS.write().unwrap() = duplicate_units;
+    *SANE.write().unwrap() = sane;
+}
+
+fn duplicate_units_policy() -> Policy {
+    *DUPLICATE_UNITS.read().unwrap()
+}
+
+fn sane_policy() -> Policy {
+    *SANE.read().unwrap()
+}
+
+/// Whether an uppercase `M` means months, see `--month-unit`.
+fn months() -> bool {
+    UPPERCASE_MONTHS.load(atomic::Ordering::Relaxed)
+}
+
+/// Applies `--dup-units` and `--sane` to a line: warns about its likely typos, or fails with the
+/// message of the first one that is an error. The command line exits with 38 then.
+pub fn check_typos(line: &str, source: &str) -> Result<(), String> {
+    let duplicates = validate::duplicate_units(line)
+        .into_iter()
+        .map(|(_, unit)| {
+            (
+                duplicate_units_policy(),
+                format!("duplicate unit {:?}", unit),
+            )
+        });
+    let sane = sane_policy();
+    let out_of_range = (sane != Policy::Allow)
+        .then(|| validate::out_of_range(line, true))
+        .into_iter()
+        .flatten()
+        .map(|(_, message)| (sane, message));
+
+    for (policy, message) in duplicates.chain(out_of_range) {
+        let message = format!("{} in {}: {:?}", message, source, line.trim());
+        match policy {
+            Policy::Allow => {}
+            Policy::Warn => eprintln!("warning: {}", message),
+            Policy::Error => return Err(message),
+        }
+    }
+
+    Ok(())
+}
+
+/// The duration of a line, or for `--lenient` of the part of it from its first to its last
+/// duration, so the words around are ignored: `Spent roughly 2h 15m on the migration` is 2h 15m.
+/// `None` for lines of just words, an error for words in between like `2h on X, 1h on Y` and for
+/// numbers that aren't durations like in `took 5 minutes`.
+pub fn lenient_duration(line: &str) -> Result<Option<Duration>, String> {
+    lazy_static! {
+        static ref LENIENT_PATTERN: Regex =
+            Regex::new(&format!(r"(?:[+-]\s*)?\b{}\b", TOKEN)).unwrap();
+    }
+
+    if let Some(d) = Duration::from_str(line) {
+        return Ok(Some(d));
+    }
+
+    let expression = line.split('#').next().unwrap();
+    let (Some(first), Some(last)) = (
+        LENIENT_PATTERN.find(expression),
+        LENIENT_PATTERN.find_iter(expression).last(),
+    ) else {
+        if expression.contains(|c: char| c.is_ascii_digit()) {
+            return Err(format!("no duration found in {:?}", expression.trim()));
+        }
+        return Ok(None);
+    };
+    let expression = &expression[first.start()..last.end()];
+    debug!("lenient {:?}", expression);
+    Duration::from_str(expression)
+        .map(Some)
+        .ok_or_else(|| format!("cannot parse {:?} as duration, see --scan", expression))
+}
+
+/// Whether `unit` varies in real length: `y`, `w`, `d`, and `M` if it means `months`.
+fn is_calendar_unit(unit: &str, months: bool) -> bool {
+    matches!(unit.to_lowercase().as_str(), "y" | "w" | "d") || unit == "M" && months
+}
+
+/// Whether `unit` is rejected with `--no-calendar-units`, see `is_calendar_unit`.
+fn is_rejected_calendar_unit(unit: &str) -> bool {
+    NO_CALENDAR_UNITS.load(atomic::Ordering::Relaxed) && is_calendar_unit(unit, months())
+}
+
+/// Unit of lines that are a bare number, see `--bare-unit`. Such lines are invalid without.
+static BARE_UNIT: RwLock<Option<String>> = RwLock::new(None);
+
+/// Units of the configuration file, see `load_config`.
+static CUSTOM_UNITS: RwLock<Option<CustomUnits>> = RwLock::new(None);
+
+pub fn set_bare_unit(unit: Option<String>) {
+    *BARE_UNIT.write().unwrap() = unit;
+}
+
+pub fn set_custom_units(units: CustomUnits) {
+    *CUSTOM_UNITS.write().unwrap() = Some(units);
+}
+
+/// The units of the configuration file with their durations, in the order of the file.
+pub fn custom_unit_durations() -> Vec<(String, Duration)> {
+    CUSTOM_UNITS
+        .read()
+        .unwrap()
+        .as_ref()
+        .map_or(Vec::new(), |units| units.units.clone())
+}
+
+/// Duration of a count of a unit, `None` on overflow.
+pub type Convert = fn(i64) -> Option<Duration>;
+
+/// Built-in units, matched case-insensitively, and the duration of a count of them.
+pub const UNITS: [(&str, Convert); 10] = [
+    ("y", |count| Duration::try_days(count.checked_mul(365)?)),
+    ("w", Duration::try_weeks),
+    ("d", Duration::try_days),
+    ("h", Duration::try_hours),
+    ("m", Duration::try_minutes),
+    ("min", Duration::try_minutes),
+    ("s", Duration::try_seconds),
+    ("ms", Duration::try_milliseconds),
+    ("µs", |count| Some(Duration::microseconds(count))),
+    ("us", |count| Some(Duration::microseconds(count))),
+];
+
+/// User defined units like `sprint = "2w"`, see `expand_units`.
+pub struct CustomUnits {
+    units: Vec<(String, Duration)>,
+    /// A count followed by any of the units
+    pattern: Regex,
+}
+
+impl CustomUnits {
+    /// Fails for names that are not just letters or clash with a built-in unit.
+    pub fn new(units: Vec<(String, Duration)>) -> Result<CustomUnits, String> {
+        for (name, _) in &units {
+            if name.is_empty() || !name.chars().all(char::is_alphabetic) {
+                return Err(format!(
+                    "invalid unit name {:?}, expected letters only",
+                    name
+                ));
+            }
+            if UNITS.iter().any(|(u, _)| u.eq_ignore_ascii_case(name)) {
+                return Err(format!("unit {} is built in", name));
+            }
+        }
+
+        let mut names: Vec<String> = units.iter().map(|(n, _)| regex::escape(n)).collect();
+        // longest first, so `days` isn't taken for `d` and `ays`
+        names.sort_by_key(|n| std::cmp::Reverse(n.len()));
+        let pattern = Regex::new(&format!(
+            r"(?P<count>\d+(?:_\d+)*(?:\.\d+(?:_\d+)*)?)\s*(?P<unit>{})\b",
+            names.join("|")
+        ))
+        .map_err(|e| e.to_string())?;
+
+        Ok(CustomUnits { units, pattern })
+    }
+
+    fn get(&self, name: &str) -> Option<Duration> {
+        self.units.iter().find(|(n, _)| n == name).map(|(_, d)| *d)
+    }
+}
+
+/// Whether `name` is a unit of the configuration file.
+fn is_custom_unit(name: &str) -> bool {
+    CUSTOM_UNITS
+        .read()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|units| units.get(name).is_some())
+}
+
+/// A token as it is added or subtracted, see `evaluate`.
+pub struct Step<'a> {
+    /// Index of the signed composite the token belongs to
+    pub group: usize,
+    pub sign: &'a str,
+    pub token: &'a str,
+    /// `None` for a token that cannot be converted and is skipped
+    pub value: Option<Duration>,
+    /// Result so far, including this token
+    pub sum: Duration,
+}
+
+/// Which value of a range like `2h..3h` is taken, see `expand_range`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    Low,
+    Middle,
+    High,
+}
+
+/// Puts a `+` in front of a line without sign, drops the comment and replaces the special
+/// terms like `until 17:00`, so only signs and tokens are left. Ranges are replaced by `bound`.
+pub fn expand(input: &str, now: NaiveDateTime, bound: Bound) -> Option<String> {
+    // ugh...
+    let line = match input.chars().next() {
+        Some('+') | Some('-') => input.to_owned(),
+        _ => "+".to_owned() + input,
+    };
+
+    let line = line.split('#').next().unwrap();
+    let line = expand_bare(line, BARE_UNIT.read().unwrap().as_deref());
+    let line = expand_functions(&line)?;
+    let line = expand_units(&line, CUSTOM_UNITS.read().unwrap().as_ref())?;
+    let line = strip_buffers(&line);
+    let line = expand_range(&line, bound)?;
+    let line = expand_pert(&line)?;
+    let line = expand_recurring(&line, now)?;
+    let line = expand_percent(&line)?;
+    let line = expand_headcount(&line)?;
+    expand_until(&line, now)
+}
+
+/// Evaluates a line for `Duration::from_str_at`, calling `step` for every token.
+pub fn evaluate(
+    input: &str,
+    now: NaiveDateTime,
+    bound: Bound,
+    step: &mut dyn FnMut(Step),
+) -> Option<Duration> {
+    lazy_static! {
+        static ref LINE_PATTERN: Regex =
+            Regex::new(&format!(r"^(?:\s*[+-]\s*(?:{}\s*)+)+$", TOKEN)).unwrap();
+        static ref DURATION_COMPOSITE_PATTERN: Regex = Regex::new(&format!(
+            r"(?P<sign>[+-])\s*(?P<duration>\s*(?:{}\s*)+)",
+            TOKEN
+        ))
+        .unwrap();
+        static ref DURATION_PATTERN: Regex = Regex::new(TOKEN_CAPTURES).unwrap();
+    }
+
+    let mut duration = Duration::zero();
+
+    if input.is_empty() {
+        return Some(duration);
+    }
+    check_limits(input).ok()?;
+
+    let line = expand(input, now, bound)?;
+    let line = line.as_str();
+    debug!("{:?} expands to {:?}", input, line);
+
+    if !LINE_PATTERN.is_match(line) {
+        debug!("{:?} is no sequence of signed tokens", line);
+        return None;
+    }
+
+    for (group, caps) in DURATION_COMPOSITE_PATTERN.captures_iter(line).enumerate() {
+        let operator_function = match &caps["sign"] {
+            "+" => Duration::checked_add,
+            "-" => Duration::checked_sub,
+            _ => unreachable!(),
+        };
+        for inner_caps in DURATION_PATTERN.captures_iter(&caps["duration"]) {
+            if inner_caps
+                .name("unit")
+                .is_some_and(|u| is_rejected_calendar_unit(u.as_str()))
+            {
+                return None;
+            }
+            let value = token_from_captures(&inner_caps);
+            duration = match value {
+                Some(d) => match operator_function(&duration, &d) {
+                    Some(dd) => dd,
+                    None => d,
+                },
+                None => duration,
+            };
+
+            debug!(
+                "{} {:?} is {}, sum {} ms",
+                &caps["sign"],
+                &inner_caps[0],
+                value.map_or("skipped".to_owned(), |d| format!(
+                    "{} ms",
+                    d.num_milliseconds()
+                )),
+                duration.num_milliseconds()
+            );
+            step(Step {
+                group,
+                sign: &caps["sign"],
+                token: inner_caps.get(0).unwrap().as_str().trim(),
+                value,
+                sum: duration,
+            });
+        }
+    }
+
+    Some(duration)
+}
+
+/// A single token of the line grammar: a count with unit, the count possibly with fraction, or
+/// `h:mm:ss` with optional fraction. Units are case-insensitive, digits of counts may be grouped
+/// with underscores like `86_400s` and have an exponent like `1.5e3s`.
+const TOKEN: &str = r"(?:\d+(?:_\d+)*:\d{2}:\d{2}(?:\.\d+)?|\d+(?:_\d+)*(?:\.\d+(?:_\d+)*)?(?:[eE][+-]?\d+)?\s*(?i:ms|µs|us|y|w|d|h|min|m|s))";
+
+/// Captures of a single token, see `token_from_captures`.
+const TOKEN_CAPTURES: &str = r"(?P<h>\d+(?:_\d+)*):(?P<m>\d{2}):(?P<s>\d{2}(?:\.\d+)?)|(?P<count>\d+(?:_\d+)*(?:\.\d+(?:_\d+)*)?(?:[eE][+-]?\d+)?)\s*(?P<unit>(?i:ms|µs|us|y|w|d|h|min|m|s))";
+
+/// Converts the captures of `TOKEN_CAPTURES` (or `m:ss.fff` with `m2` and `s2` in `--scan`) to a
+/// `Duration`.
+fn token_from_captures(caps: &regex::Captures) -> Option<Duration> {
+    if let Some(count) = caps.name("count") {
+        return Duration::fractional_token_to_duration(count.as_str(), &caps["unit"]);
+    }
+
+    let (h, m, s) = match (caps.name("h"), caps.name("m2")) {
+        (Some(h), _) => (h.as_str(), &caps["m"], &caps["s"]),
+        (_, Some(m)) => ("0", m.as_str(), &caps["s2"]),
+        _ => return None,
+    };
+
+    Duration::fractional_token_to_duration(h, "h")?
+        .checked_add(&Duration::fractional_token_to_duration(m, "m")?)?
+        .checked_add(&Duration::fractional_token_to_duration(s, "s")?)
+}
+
+impl DurationParse for Duration {
+    fn from_str(input: &str) -> Option<Duration> {
+        Self::from_str_at(input, Local::now().naive_local())
+    }
+
+    fn from_str_at(input: &str, now: NaiveDateTime) -> Option<Duration> {
+        evaluate(input, now, Bound::Middle, &mut |_| {})
+    }
+
+    fn scan(input: &str) -> Duration {
+        lazy_static! {
+            static ref SCAN_PATTERN: Regex = Regex::new(&format!(
+                r"\b(?:{}|(?P<m2>\d+):(?P<s2>\d{{2}}\.\d+))\b",
+                TOKEN_CAPTURES
+            ))
+            .unwrap();
+        }
+
+        SCAN_PATTERN
+            .captures_iter(input)
+            .filter(|caps| {
+                !caps
+                    .name("unit")
+                    .is_some_and(|u| is_rejected_calendar_unit(u.as_str()))
+            })
+            .filter_map(|caps| {
+                debug!("scanned {:?}", &caps[0]);
+                token_from_captures(&caps)
+            })
+            .fold(Duration::zero(), |d, t| d.saturated_add(&t))
+    }
+
+    fn token_to_duration(count: i64, unit: &str) -> Option<Duration> {
+        unit_duration(count, unit, months())
+    }
+
+    fn fractional_token_to_duration(count: &str, unit: &str) -> Option<Duration> {
+        let count = shift_exponent(&count.replace('_', ""))?;
+        let (whole, fraction) = count.split_once('.').unwrap_or((&count, ""));
+        let whole = Self::token_to_duration(i64::from_str(whole).ok()?, unit)?;
+        if fraction.is_empty() {
+            return Some(whole);
+        }
+
+        let fraction = &fraction[..fraction.len().min(9)];
+        let unit_nanos = Self::token_to_duration(1, unit)?.num_nanoseconds()? as i128;
+        let nanos = unit_nanos * i128::from_str(fraction).ok()? / 10i128.pow(fraction.len() as u32);
+
+        whole.checked_add(&Duration::nanoseconds(nanos as i64))
+    }
+}
+
+/// `count` times `unit`, where an uppercase `M` is 30 days with `months`, see `--month-unit`.
+fn unit_duration(count: i64, unit: &str, months: bool) -> Option<Duration> {
+    if unit == "M" && months {
+        return Duration::try_days(count.checked_mul(30)?);
+    }
+
+    let unit = unit.to_lowercase();
+    let (_, convert) = UNITS.iter().find(|(u, _)| *u == unit)?;
+    convert(count)
+}
+
+/// Appends `unit` to a line that is just a signed number like `+1500`, e.g. a column cut out of
+/// a CSV file. Other lines, and all lines without `unit`, are returned as they are.
+fn expand_bare(line: &str, unit: Option<&str>) -> String {
+    lazy_static! {
+        static ref BARE_PATTERN: Regex =
+            Regex::new(r"^\s*([+-])\s*(\d+(?:_\d+)*(?:\.\d+(?:_\d+)*)?(?:[eE][+-]?\d+)?)\s*$")
+                .unwrap();
+    }
+
+    match (unit, BARE_PATTERN.captures(line)) {
+        (Some(unit), Some(caps)) => format!("{}{}{}", &caps[1], &caps[2], unit),
+        _ => line.to_owned(),
+    }
+}
+
+/// Moves the decimal point of a count like `1.5e3` by its exponent, giving `1500`, so it can be
+/// converted as precisely as any other count. Counts without exponent are returned as they are,
+/// `None` if the exponent is way beyond the range of durations.
+fn shift_exponent(count: &str) -> Option<String> {
+    let (mantissa, exponent) = match count.split_once(['e', 'E']) {
+        Some((m, e)) => (m, i32::from_str(e).ok()?),
+        None => return Some(count.to_owned()),
+    };
+    if exponent > 30 {
+        return None;
+    }
+    // digits beyond nanoseconds of a year are lost anyway
+    let exponent = exponent.max(-30);
+
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", whole, fraction);
+    let point = whole.len() as i32 + exponent;
+
+    let shifted = if point <= 0 {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    } else if point as usize >= digits.len() {
+        format!("{}{}", digits, "0".repeat(point as usize - digits.len()))
+    } else {
+        format!(
+            "{}.{}",
+            &digits[..point as usize],
+            &digits[point as usize..]
+        )
+    };
+    Some(shifted)
+}
+
+/// What a function computes from its arguments, `None` if it can't.
+pub type Function = fn(&[Duration]) -> Option<Duration>;
+
+/// Built-in functions, their number of arguments (any but none without) and what they compute.
+pub const FUNCTIONS: [(&str, Option<usize>, Function); 5] = [
+    ("max", None, |args| args.iter().max().copied()),
+    ("min", None, |args| args.iter().min().copied()),
+    ("abs", Some(1), |args| Some(args[0].abs())),
+    ("neg", Some(1), |args| Some(-args[0])),
+    ("clamp", Some(3), |args| {
+        (args[1] <= args[2]).then(|| args[0].clamp(args[1], args[2]))
+    }),
+];
+
+/// Replaces function calls like `max(0s, 40h - week)` with their result in milliseconds, the
+/// innermost first. The arguments are expressions of their own. Returns `None` for unknown
+/// functions, a wrong number of arguments or arguments that cannot be parsed.
+fn expand_functions(line: &str) -> Option<String> {
+    lazy_static! {
+        static ref CALL_PATTERN: Regex = Regex::new(
+            r"(?P<sign>[+-]?)(?P<space>\s*)\b(?P<name>[\pL_]\w*)\s*\((?P<args>[^()]*)\)"
+        )
+        .unwrap();
+    }
+
+    let mut line = line.to_owned();
+    while let Some(caps) = CALL_PATTERN.captures(&line) {
+        let (_, arity, function) = FUNCTIONS.iter().find(|(n, _, _)| *n == &caps["name"])?;
+        let args = caps["args"]
+            .split(',')
+            .map(|a| Duration::from_str(a.trim()).filter(|_| !a.trim().is_empty()))
+            .collect::<Option<Vec<_>>>()?;
+        if arity.is_some_and(|n| n != args.len()) {
+            return None;
+        }
+
+        let result = signed_ms(&caps["sign"], &caps["space"], function(&args)?);
+        let m = caps.get(0).unwrap();
+        line = format!("{}{}{}", &line[..m.start()], result, &line[m.end()..]);
+    }
+
+    Some(line)
+}
+
+/// Replaces counts of custom units like `1.5 sprint` with their duration in milliseconds.
+/// Returns `None` on overflow.
+fn expand_units(line: &str, units: Option<&CustomUnits>) -> Option<String> {
+    let Some(units) = units else {
+        return Some(line.to_owned());
+    };
+
+    let mut result = String::with_capacity(line.len());
+    let mut last = 0;
+
+    for caps in units.pattern.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        let d = scale(units.get(&caps["unit"])?, &caps["count"].replace('_', ""))?;
+
+        result.push_str(&line[last..m.start()]);
+        result.push_str(&format!("{}ms ", d.num_milliseconds()));
+        last = m.end();
+    }
+    result.push_str(&line[last..]);
+
+    Some(result)
+}
+
+/// Replaces ranges like `2h..3h` with a bound in milliseconds: the midpoint for
+/// `Bound::Middle`, otherwise the bound giving the lowest or highest total, i.e. the upper bound
+/// of a subtracted range for `Bound::Low`. Returns `None` on overflow.
+fn expand_range(line: &str, bound: Bound) -> Option<String> {
+    lazy_static! {
+        static ref RANGE_PATTERN: Regex = Regex::new(&format!(
+            r"(?P<sign>[+-])\s*(?P<low>(?:{token}\s*)+?)\s*\.\.\s*(?P<high>(?:{token}\s*)+)",
+            token = TOKEN
+        ))
+        .unwrap();
+    }
+
+    let mut result = String::with_capacity(line.len());
+    let mut last = 0;
+
+    for caps in RANGE_PATTERN.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        let a = Duration::from_str(&caps["low"])?.num_milliseconds();
+        let b = Duration::from_str(&caps["high"])?.num_milliseconds();
+        let (low, high) = (a.min(b), a.max(b));
+        let value = match (bound, &caps["sign"]) {
+            (Bound::Middle, _) => low + (high - low) / 2,
+            (Bound::Low, "+") | (Bound::High, "-") => low,
+            _ => high,
+        };
+
+        result.push_str(&line[last..m.start()]);
+        result.push_str(&format!("{}{}ms ", &caps["sign"], value));
+        last = m.end();
+    }
+    result.push_str(&line[last..]);
+
+    Some(result)
+}
+
+/// Uncertainty buffers, either explicit like `±30m` or a quarter of a rough term marked like
+/// `2h~`, see `strip_buffers` and `buffer`.
+fn buffer_pattern() -> &'static Regex {
+    lazy_static! {
+        static ref BUFFER_PATTERN: Regex = Regex::new(&format!(
+            r"±\s*(?P<buffer>(?:{token}\s*)+)|(?P<rough>(?:{token}\s*)+?)\s*~",
+            token = TOKEN
+        ))
+        .unwrap();
+    }
+
+    &BUFFER_PATTERN
+}
+
+/// Drops the buffers from a line, they are not part of the total: `2h ±30m` and `2h~` are `2h`.
+fn strip_buffers(line: &str) -> String {
+    buffer_pattern()
+        .replace_all(line, |caps: &regex::Captures| {
+            caps.name("rough")
+                .map_or(String::new(), |r| format!("{} ", r.as_str()))
+        })
+        .into_owned()
+}
+
+/// The sum of the uncertainty buffers in a line like `2h ±30m + 4h~`, here 1h 30m, `None` for
+/// lines without any. Buffers always add up, whatever the sign of their term. Dates and tags
+/// are skipped.
+pub fn buffer(line: &str) -> Option<Duration> {
+    let rest = expression(line);
+    let rest = rest.split('#').next().unwrap();
+
+    buffer_pattern()
+        .captures_iter(rest)
+        .map(|caps| match (caps.name("buffer"), caps.name("rough")) {
+            (Some(b), _) => Duration::from_str(b.as_str()).map(|d| d.abs()),
+            (_, Some(r)) => Duration::from_str(r.as_str()).map(|d| d.abs() / 4),
+            _ => None,
+        })
+        .reduce(|a, b| a?.checked_add(&b?))
+        .flatten()
+}
+
+/// Three-point estimates like `1h/2h/4h` (optimistic, likely and pessimistic), see
+/// `expand_pert` and `pert_variance`.
+fn pert_pattern() -> &'static Regex {
+    lazy_static! {
+        static ref PERT_PATTERN: Regex = Regex::new(&format!(
+            r"(?P<sign>[+-])\s*(?P<o>(?:{token}\s*)+?)\s*/\s*(?P<m>(?:{token}\s*)+?)\s*/\s*(?P<p>(?:{token}\s*)+)",
+            token = TOKEN
+        ))
+        .unwrap();
+    }
+
+    &PERT_PATTERN
+}
+
+/// Optimistic, likely and pessimistic value of a three-point estimate in milliseconds.
+fn pert_points(caps: &regex::Captures) -> Option<(i64, i64, i64)> {
+    let ms = |name| Duration::from_str(&caps[name]).map(|d| d.num_milliseconds());
+    Some((ms("o")?, ms("m")?, ms("p")?))
+}
+
+/// Replaces three-point estimates like `1h/2h/4h` with their PERT expected value in
+/// milliseconds, `(o + 4m + p) / 6`, here `+7800000ms`. Returns `None` on overflow.
+fn expand_pert(line: &str) -> Option<String> {
+    let mut result = String::with_capacity(line.len());
+    let mut last = 0;
+
+    for caps in pert_pattern().captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        let (o, likely, p) = pert_points(&caps)?;
+        let expected = o.checked_add(likely.checked_mul(4)?)?.checked_add(p)? / 6;
+
+        result.push_str(&line[last..m.start()]);
+        result.push_str(&format!("{}{}ms ", &caps["sign"], expected));
+        last = m.end();
+    }
+    result.push_str(&line[last..]);
+
+    Some(result)
+}
+
+/// The sum of the PERT variances, `((p - o) / 6)²` in milliseconds squared, of the
+/// three-point estimates in a line like `1h/2h/4h`, `None` for lines without any. Dates and
+/// tags are skipped.
+pub fn pert_variance(line: &str) -> Option<f64> {
+    let rest = expression(line);
+    let rest = format!("+{}", rest.split('#').next().unwrap());
+
+    pert_pattern()
+        .captures_iter(&rest)
+        .map(|caps| {
+            let (o, _, p) = pert_points(&caps)?;
+            let deviation = (p as f64 - o as f64) / 6.0;
+            Some(deviation * deviation)
+        })
+        .reduce(|a, b| Some(a? + b?))
+        .flatten()
+}
+
+/// The decimal factor of a percentage like `12.5` (for `12.5%`), i.e. `0.125`, for `scale`.
+fn percent_factor(percent: &str) -> String {
+    let (whole, fraction) = percent.split_once('.').unwrap_or((percent, ""));
+    let whole = format!("{:0>3}", whole);
+    let (whole, hundredths) = whole.split_at(whole.len() - 2);
+    format!("{}.{}{}", whole, hundredths, fraction)
+}
+
+/// Replaces percentages of an amount like `10% * 8h`, `10% of 8h` or `8h * 110%` with the result
+/// in milliseconds, here `+48m` for the first two. Returns `None` on overflow.
+fn expand_percent(line: &str) -> Option<String> {
+    lazy_static! {
+        static ref PERCENT_PATTERN: Regex = Regex::new(&format!(
+            r"(?P<sign>[+-])\s*(?:(?P<p1>\d+(?:\.\d+)?)\s*%\s*(?:\*|of\b)\s*(?P<a1>(?:{token}\s*)+)|(?P<a2>(?:{token}\s*)+)\*\s*(?P<p2>\d+(?:\.\d+)?)\s*%)",
+            token = TOKEN
+        ))
+        .unwrap();
+    }
+
+    let mut result = String::with_capacity(line.len());
+    let mut last = 0;
+
+    for caps in PERCENT_PATTERN.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        let amount = caps.name("a1").or(caps.name("a2")).unwrap().as_str();
+        let percent = caps.name("p1").or(caps.name("p2")).unwrap().as_str();
+        let d = scale(Duration::from_str(amount)?, &percent_factor(percent))?;
+
+        result.push_str(&line[last..m.start()]);
+        result.push_str(&format!("{}{}ms ", &caps["sign"], d.num_milliseconds()));
+        last = m.end();
+    }
+    result.push_str(&line[last..]);
+
+    Some(result)
+}
+
+/// Replaces terms with a headcount like `2h @5` (a meeting of five people) with the effort in
+/// milliseconds, i.e. the amount times the headcount, here `+600m`. Returns `None` on overflow.
+fn expand_headcount(line: &str) -> Option<String> {
+    lazy_static! {
+        static ref HEADCOUNT_PATTERN: Regex = Regex::new(&format!(
+            r"(?P<sign>[+-])\s*(?P<amount>(?:{token}\s*)+)@\s*(?P<headcount>\d+)",
+            token = TOKEN
+        ))
+        .unwrap();
+    }
+
+    let mut result = String::with_capacity(line.len());
+    let mut last = 0;
+
+    for caps in HEADCOUNT_PATTERN.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        let amount = Duration::from_str(&caps["amount"])?;
+        let headcount: i64 = caps["headcount"].parse().ok()?;
+
+        result.push_str(&line[last..m.start()]);
+        result.push_str(&format!(
+            "{}{}ms ",
+            &caps["sign"],
+            amount.num_milliseconds().checked_mul(headcount)?
+        ));
+        last = m.end();
+    }
+    result.push_str(&line[last..]);
+
+    Some(result)
+}
+
+/// Replaces recurring terms like `30m daily for 2w` with their total in milliseconds, i.e. the
+/// amount times the number of occurrences within the span, here `+420m`. Occurrences are
+/// `hourly`, `daily`, `weekly`, or on `weekdays` (Mon-Fri, counted from the day of `now`).
+/// Returns `None` on overflow.
+fn expand_recurring(line: &str, now: NaiveDateTime) -> Option<String> {
+    lazy_static! {
+        static ref RECURRING_PATTERN: Regex = Regex::new(&format!(
+            r"(?P<sign>[+-])\s*(?P<amount>(?:{token}\s*)+?)\s*(?P<every>hourly|daily|weekly|weekdays)\s+for\s+(?P<span>(?:{token}\s*)+)",
+            token = TOKEN
+        ))
+        .unwrap();
+    }
+
+    let mut result = String::with_capacity(line.len());
+    let mut last = 0;
+
+    for caps in RECURRING_PATTERN.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        let amount = Duration::from_str_at(&caps["amount"], now)?;
+        let span = Duration::from_str_at(&caps["span"], now)?;
+        let occurrences = match &caps["every"] {
+            "hourly" => span.num_hours(),
+            "daily" => span.num_days(),
+            "weekly" => span.num_weeks(),
+            "weekdays" => now
+                .date()
+                .iter_days()
+                .take(span.num_days().try_into().ok()?)
+                .filter(|d| d.weekday().num_days_from_monday() < 5)
+                .count() as i64,
+            _ => unreachable!(),
+        };
+        debug!("recurring {:?}: {} times", &caps["amount"], occurrences);
+
+        result.push_str(&line[last..m.start()]);
+        result.push_str(&format!(
+            "{}{}ms ",
+            &caps["sign"],
+            amount.num_milliseconds().checked_mul(occurrences)?
+        ));
+        last = m.end();
+    }
+    result.push_str(&line[last..]);
+
+    Some(result)
+}
+
+/// Replaces `until <target>` terms with the number of seconds from `now` to the target, e.g.
+/// `+until 17:00 - 45m` becomes `+27000s - 45m` at 09:30. The term takes the place of a whole
+/// composite, so a target in the past flips the preceding sign instead of producing `+-5s`.
+/// Returns `None` if a target cannot be resolved.
+fn expand_until(line: &str, now: NaiveDateTime) -> Option<String> {
+    lazy_static! {
+        static ref UNTIL_PATTERN: Regex = Regex::new(
+            r"(?P<sign>[+-])\s*until\s+(?P<target>\d{4}-\d{2}-\d{2}(?:[T ]\d{1,2}:\d{2}(?::\d{2})?)?|\d{1,2}:\d{2}(?::\d{2})?|[A-Za-z]+)"
+        )
+        .unwrap();
+    }
+
+    let mut result = String::with_capacity(line.len());
+    let mut last = 0;
+
+    for caps in UNTIL_PATTERN.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        let target = resolve_until(&caps["target"], now)?;
+        debug!(
+            "until {:?}: {} ms",
+            &caps["target"],
+            target.num_milliseconds()
+        );
+
+        let negative = (&caps["sign"] == "-") != (target < Duration::zero());
+        result.push_str(&line[last..m.start()]);
+        result.push_str(&format!(
+            "{}{}s ",
+            if negative { '-' } else { '+' },
+            target.num_seconds().abs()
+        ));
+        last = m.end();
+    }
+    result.push_str(&line[last..]);
+
+    Some(result)
+}
+
+/// Resolves the target of an `until` term to the duration from `now`.
+///
+/// * `17:00` - the next occurrence of that time of day, i.e. tomorrow if it has already passed
+/// * `friday`, `fri` - the next midnight starting that weekday, never today
+/// * `2025-01-01`, `2025-01-01T17:00` - that point in time, which may lie in the past
+fn resolve_until(target: &str, now: NaiveDateTime) -> Option<Duration> {
+    if let Ok(t) = NaiveTime::parse_from_str(target, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(target, "%H:%M"))
+    {
+        let mut at = now.date().and_time(t);
+        if at <= now {
+            at += Duration::days(1);
+        }
+        return Some(clock::elapsed(now, at));
+    }
+
+    if let Ok(weekday) = Weekday::from_str(target) {
+        let days_ahead = (7 + weekday.num_days_from_monday() as i64
+            - now.weekday().num_days_from_monday() as i64)
+            % 7;
+        let days_ahead = if days_ahead == 0 { 7 } else { days_ahead };
+        let at = (now.date() + Duration::days(days_ahead)).and_time(NaiveTime::MIN);
+        return Some(clock::elapsed(now, at));
+    }
+
+    parse_datetime(target, now).map(|at| clock::elapsed(now, at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_displayable_duration() {
+        let display = |d, compact, compact_style| {
+            let style = Style {
+                compact,
+                compact_style,
+                ..Style::default()
+            };
+            DisplayableDuration(d, style).to_string()
+        };
+        let d = Duration::seconds(3725);
+        let short = -Duration::seconds(65);
+
+        assert_eq!(display(d, false, CompactStyle::Colon), "1h 02m 05s");
+        assert_eq!(display(d, true, CompactStyle::Units), "1h02m05s");
+        assert_eq!(display(d, true, CompactStyle::Colon), "1:02:05");
+        assert_eq!(display(short, true, CompactStyle::Colon), "-0:01:05");
+        assert_eq!(display(d, true, CompactStyle::Underscore), "1h_02m_05s");
+        assert_eq!(display(d, true, CompactStyle::Narrow), "1h02m05s");
+        assert_eq!(display(short, true, CompactStyle::Narrow), "-1m05s");
+
+        let style = Style {
+            hour_width: 3,
+            unpadded: true,
+            ..Style::default()
+        };
+        assert_eq!(DisplayableDuration(d, style).to_string(), "001h 2m 5s");
+        let style = Style {
+            compact: true,
+            compact_style: CompactStyle::Narrow,
+            ..style
+        };
+        assert_eq!(DisplayableDuration(short, style).to_string(), "-1m5s");
+
+        let style = Style {
+            negative_style: NegativeStyle::Parentheses,
+            ..Style::default()
+        };
+        assert_eq!(
+            DisplayableDuration(short, style).to_string(),
+            "(0h 01m 05s)"
+        );
+        assert_eq!(DisplayableDuration(d, style).to_string(), "1h 02m 05s");
+        let style = Style {
+            negative_style: NegativeStyle::Trailing,
+            ..Style::default()
+        };
+        assert_eq!(DisplayableDuration(short, style).to_string(), "0h 01m 05s-");
+    }
+
+    #[test]
+    fn test_hms() {
+        let mut buf = [0; HMS_LENGTH];
+        for n in [0, 59, 3725, 36_000, i64::MAX / 1000] {
+            for hour_width in [0, 1, 3, 20] {
+                let style = Style {
+                    hour_width,
+                    ..Style::default()
+                };
+                let expected = DisplayableDuration::unsigned(n, style);
+                assert_eq!(hms(n as u64, hour_width, &mut buf), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_tags() {
+        let (tags, rest) = split_tags("+billable 2h 30m +clientA - 5m +1h # fix +bug");
+        assert_eq!(tags, vec!["billable", "clientA"]);
+        assert_eq!(Duration::from_str(&rest), Some(Duration::minutes(205)));
+        assert!(rest.ends_with("# fix +bug"));
+
+        let (tags, rest) = split_tags("+30m");
+        assert!(tags.is_empty());
+        assert_eq!(rest, "+30m");
+    }
+
+    #[test]
+    fn test_extract_durations() {
+        let pattern = Regex::new(r"elapsed=(-?\w+)").unwrap();
+        assert_eq!(
+            extract_durations(
+                &pattern,
+                "job=1 retries=3 elapsed=2m30s, job=2 elapsed=-15s"
+            ),
+            Ok(Duration::minutes(2) + Duration::seconds(15))
+        );
+        assert_eq!(
+            extract_durations(&pattern, "retries=3 took 5m"),
+            Ok(Duration::zero())
+        );
+        assert_eq!(
+            extract_durations(&pattern, "elapsed=soon"),
+            Err("cannot parse \"soon\" extracted from \"elapsed=soon\" as duration".to_owned())
+        );
+
+        let pattern = Regex::new(r"\d+h").unwrap();
+        assert_eq!(
+            extract_durations(&pattern, "1h and 2h"),
+            Ok(Duration::hours(3))
+        );
+    }
+
+    #[test]
+    fn test_token_to_duration() {
+        let cases = vec![
+            (5, "y", Duration::days(365 * 5)),
+            (2, "w", Duration::days(14)),
+            (2, "d", Duration::days(2)),
+            (3, "h", Duration::hours(3)),
+            (30, "m", Duration::minutes(30)),
+            (10, "s", Duration::seconds(10)),
+            (0, "y", Duration::zero()),
+            (250, "ms", Duration::milliseconds(250)),
+        ];
+
+        for (count, unit, expected) in cases {
+            let result = Duration::token_to_duration(count, unit);
+            assert_eq!(result, Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_from_str() {
+        let cases = vec![
+            ("", Duration::zero()),
+            (
+                "3d 20h 10m 15s",
+                Duration::days(3)
+                    + Duration::hours(20)
+                    + Duration::minutes(10)
+                    + Duration::seconds(15),
+            ),
+            ("+2d 5h", Duration::days(2) + Duration::hours(5)),
+            (
+                "-1y 3h + 40m",
+                Duration::days(-365) - Duration::hours(3) + Duration::minutes(40),
+            ),
+            ("+3h-2m", Duration::hours(3) - Duration::minutes(2)),
+            ("2d 5h # Comment", Duration::days(2) + Duration::hours(5)),
+            ("-2d 5h # Comment", -Duration::days(2) - Duration::hours(5)),
+            (
+                "1.5h + 75.3s",
+                Duration::minutes(90) + Duration::milliseconds(75_300),
+            ),
+            (
+                "1:02:03.5 - 250ms",
+                Duration::seconds(3723) + Duration::milliseconds(250),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let result = Duration::from_str(input).unwrap();
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    fn test_from_str_underscores() {
+        let cases = vec![
+            ("86_400s", Some(Duration::days(1))),
+            (
+                "1_000h - 1_0m",
+                Some(Duration::hours(1000) - Duration::minutes(10)),
+            ),
+            (
+                "1_000:00:00.5",
+                Some(Duration::hours(1000) + Duration::milliseconds(500)),
+            ),
+            ("0.000_001s", Some(Duration::microseconds(1))),
+            ("1__0s", None),
+            ("_10s", None),
+            ("10_s", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Duration::from_str(input), expected, "{}", input);
+        }
+        assert_eq!(Duration::scan("took 1_500ms"), Duration::milliseconds(1500));
+    }
+
+    #[test]
+    fn test_expand_bare() {
+        assert_eq!(expand_bare("+1500", Some("ms")), "+1500ms");
+        assert_eq!(expand_bare("- 2.5 ", Some("s")), "-2.5s");
+        assert_eq!(expand_bare("+1e3", Some("s")), "+1e3s");
+        assert_eq!(expand_bare("+1500", None), "+1500");
+        assert_eq!(expand_bare("+15m", Some("s")), "+15m");
+        assert_eq!(expand_bare("+1 2", Some("s")), "+1 2");
+    }
+
+    #[test]
+    fn test_shift_exponent() {
+        let cases = vec![
+            ("1e3", Some("1000")),
+            ("1.5e3", Some("1500")),
+            ("1.2345E2", Some("123.45")),
+            ("2.5e-3", Some("0.0025")),
+            ("25e-1", Some("2.5")),
+            ("7", Some("7")),
+            ("1e31", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(shift_exponent(input).as_deref(), expected, "{}", input);
+        }
+
+        assert_eq!(Duration::from_str("1.5e3 s"), Some(Duration::seconds(1500)));
+        assert_eq!(
+            Duration::from_str("2.5e-3s"),
+            Some(Duration::microseconds(2500))
+        );
+        assert_eq!(Duration::scan("mean 1.2e2ms"), Duration::milliseconds(120));
+    }
+
+    #[test]
+    fn test_from_str_uppercase() {
+        let cases = vec![
+            ("2H 30M", Duration::minutes(150)),
+            ("10S", Duration::seconds(10)),
+            ("1D 5Min", Duration::minutes(24 * 60 + 5)),
+            ("250MS", Duration::milliseconds(250)),
+        ];
+        for (input, expected) in &cases {
+            assert_eq!(Duration::from_str(input), Some(*expected), "{}", input);
+        }
+
+        assert_eq!(Duration::from_str("1M 2m"), Some(Duration::minutes(3)));
+        assert_eq!(unit_duration(2, "M", false), Some(Duration::minutes(2)));
+        assert_eq!(unit_duration(2, "M", true), Some(Duration::days(60)));
+        assert_eq!(unit_duration(2, "m", true), Some(Duration::minutes(2)));
+    }
+
+    #[test]
+    fn test_duplicate_units() {
+        assert_eq!(
+            validate::duplicate_units("3h 2h + 1h 30min 5m - 2h"),
+            vec![(4, "h".to_owned()), (18, "m".to_owned())]
+        );
+        assert_eq!(validate::duplicate_units("1h 30m +h2 # 1h 1h"), vec![]);
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent_factor("10"), "0.10");
+        assert_eq!(percent_factor("110"), "1.10");
+        assert_eq!(percent_factor("5"), "0.05");
+        assert_eq!(percent_factor("12.5"), "0.125");
+
+        let cases = vec![
+            ("10% * 8h", Duration::minutes(48)),
+            ("10 % of 8h", Duration::minutes(48)),
+            ("8h * 110%", Duration::minutes(528)),
+            ("1h - 12.5% * 1h 20m", Duration::minutes(50)),
+            ("2h * 50% + 30m * 200%", Duration::hours(2)),
+            ("max(0s, 40h - 110% * 35h)", Duration::minutes(90)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Duration::from_str(input), Some(expected), "{}", input);
+        }
+
+        assert_eq!(Duration::from_str("10% * 8"), None);
+        assert_eq!(Duration::from_str("8h * 1.1"), None);
+    }
+
+    #[test]
+    fn test_expand_history() {
+        let history = [Some(Duration::hours(2)), None, Some(Duration::minutes(-30))];
+        assert_eq!(
+            expand_history("$prev * 200%", &history),
+            Ok("-1800000ms * 200%".to_owned())
+        );
+        assert_eq!(
+            expand_history("1h - $3", &history),
+            Ok("1h + 1800000ms".to_owned())
+        );
+        assert_eq!(
+            expand_history("$1 + 10m # was $2", &history),
+            Ok("7200000ms + 10m # was $2".to_owned())
+        );
+        assert_eq!(
+            expand_history("$2 + 1h", &history),
+            Err("no result for $2".to_owned())
+        );
+        assert_eq!(
+            expand_history("$0", &history),
+            Err("no result for $0".to_owned())
+        );
+        assert_eq!(
+            expand_history("$prev", &[]),
+            Err("no result for $prev".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let terms = "+1s ".repeat(MAX_TERMS);
+        assert_eq!(check_limits(&terms), Ok(()));
+        assert_eq!(Duration::from_str(&terms), Some(Duration::seconds(256)));
+
+        let terms = terms + "-1s";
+        assert_eq!(check_limits(&terms), Err("more than 256 terms".to_owned()));
+        assert_eq!(Duration::from_str(&terms), None);
+        assert!(line_duration(&terms).is_err());
+
+        let long = format!("1h {}", "0".repeat(MAX_LINE_LENGTH));
+        assert_eq!(
+            check_limits(&long),
+            Err("line longer than 4096 bytes".to_owned())
+        );
+        assert_eq!(
+            check_limits(&format!("1h #{}", "-".repeat(MAX_LINE_LENGTH))),
+            Ok(())
+        );
+        assert_eq!(
+            check_limits(&"1:00:00 ".repeat(400)),
+            Err("more than 1024 numbers".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        let cases = vec![
+            ("max(0s, 40h - 42h)", Duration::zero()),
+            ("2h - min(30m, 1h, 45m)", Duration::minutes(90)),
+            ("clamp(50h, 0s, 40h) + 1h", Duration::hours(41)),
+            ("1h - max(-2h, min(-1h, 3h))", Duration::hours(2)),
+            ("max(1h 30m..2h)", Duration::minutes(105)),
+            ("abs(2h - 3h 30m)", Duration::minutes(90)),
+            ("1h + neg(abs(-20m))", Duration::minutes(40)),
+            ("-neg(1h)", Duration::hours(1)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Duration::from_str(input), Some(expected), "{}", input);
+        }
+
+        let invalid = [
+            "clamp(1h, 2h)",
+            "clamp(1h, 3h, 2h)",
+            "avg(1h, 2h)",
+            "max()",
+            "max(1h,)",
+            "abs(1h, 2h)",
+        ];
+        for input in invalid {
+            assert_eq!(Duration::from_str(input), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_is_calendar_unit() {
+        for unit in ["y", "W", "d"] {
+            assert!(is_calendar_unit(unit, false), "{}", unit);
+        }
+        for unit in ["h", "m", "min", "s", "ms", "M"] {
+            assert!(!is_calendar_unit(unit, false), "{}", unit);
+        }
+        assert!(is_calendar_unit("M", true));
+        assert!(!is_calendar_unit("m", true));
+    }
+
+    #[test]
+    fn test_from_str_at_until() {
+        // a Wednesday
+        let now = NaiveDate::from_ymd_opt(2025, 1, 8)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+
+        let cases = vec![
+            ("until 17:00", Duration::hours(7) + Duration::minutes(30)),
+            (
+                "until 17:00 - 45m",
+                Duration::hours(6) + Duration::minutes(45),
+            ),
+            ("until 08:00", Duration::hours(22) + Duration::minutes(30)),
+            (
+                "until friday",
+                Duration::days(1) + Duration::hours(14) + Duration::minutes(30),
+            ),
+            (
+                "until wed",
+                Duration::days(6) + Duration::hours(14) + Duration::minutes(30),
+            ),
+            (
+                "until 2025-01-09",
+                Duration::hours(14) + Duration::minutes(30),
+            ),
+            (
+                "1h - until 2025-01-08 09:00",
+                Duration::hours(1) + Duration::minutes(30),
+            ),
+            ("-until 2025-01-08T10:00 # Comment", -Duration::minutes(30)),
+        ];
+
+        for (input, expected) in cases {
+            let result = Duration::from_str_at(input, now).unwrap();
+            assert_eq!(result, expected, "{}", input);
+        }
+
+        assert_eq!(Duration::from_str_at("until someday", now), None);
+    }
+
+    #[test]
+    fn test_from_str_headcount() {
+        let cases = vec![
+            ("2h @5", Duration::hours(10)),
+            ("1h 30m @ 2 - 15m @4", Duration::hours(2)),
+            ("1h + 2h@3", Duration::hours(7)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Duration::from_str(input), Some(expected), "{}", input);
+        }
+
+        assert_eq!(Duration::from_str("@5"), None);
+        assert_eq!(Duration::from_str("100000y @100000"), None);
+    }
+
+    #[test]
+    fn test_expand_units() {
+        let units = CustomUnits::new(vec![
+            ("sprint".to_owned(), Duration::weeks(2)),
+            ("shift".to_owned(), Duration::minutes(7 * 60 + 30)),
+            ("day".to_owned(), Duration::minutes(7 * 60 + 42)),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            expand_units("+1.5 sprint 2d - 2shift", Some(&units)),
+            Some("+1814400000ms  2d - 54000000ms ".to_owned())
+        );
+        assert_eq!(
+            expand_units("+2days 3 day", Some(&units)),
+            Some("+2days 83160000ms ".to_owned())
+        );
+        assert_eq!(expand_units("+2h", None), Some("+2h".to_owned()));
+
+        assert!(CustomUnits::new(vec![("H".to_owned(), Duration::hours(1))]).is_err());
+        assert!(CustomUnits::new(vec![("man-day".to_owned(), Duration::hours(8))]).is_err());
+    }
+
+    #[test]
+    fn test_range_spread() {
+        let now = NaiveDate::from_ymd_opt(2025, 1, 8)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+
+        assert_eq!(Duration::from_str("2h..3h"), Some(Duration::minutes(150)));
+        assert_eq!(
+            Duration::from_str("1h 30m..2h + 30m"),
+            Some(Duration::minutes(135))
+        );
+        assert_eq!(
+            range_spread("2025-01-06 8h..10h - 30m..1h +dev # estimate", now),
+            Some((Duration::minutes(-75), Duration::minutes(75)))
+        );
+        assert_eq!(
+            range_spread("3h..2h", now),
+            Some((Duration::minutes(-30), Duration::minutes(30)))
+        );
+        assert_eq!(range_spread("2h # 1..2", now), None);
+        assert_eq!(Duration::from_str("2h.."), None);
+    }
+
+    #[test]
+    fn test_buffer() {
+        assert_eq!(
+            Duration::from_str("2h ±30m + 4h~"),
+            Some(Duration::hours(6))
+        );
+        assert_eq!(Duration::from_str("3h - 1h~"), Some(Duration::hours(2)));
+
+        assert_eq!(buffer("2h ±30m + 4h~"), Some(Duration::minutes(90)));
+        assert_eq!(
+            buffer("2025-01-06 3h - 1h 20m~ +dev # rough"),
+            Some(Duration::minutes(20))
+        );
+        assert_eq!(buffer("2h # ±30m"), None);
+    }
+
+    #[test]
+    fn test_pert() {
+        assert_eq!(Duration::from_str("1h/2h/4h"), Some(Duration::minutes(130)));
+        assert_eq!(
+            Duration::from_str("30m + 1h/1h 30m/3h - 15m"),
+            Some(Duration::minutes(115))
+        );
+
+        assert_eq!(pert_variance("1h/2h/4h"), Some(1_800_000.0 * 1_800_000.0));
+        assert_eq!(
+            pert_variance("2025-01-06 1h/2h/4h + 2h/2h/2h +dev # estimate"),
+            Some(1_800_000.0 * 1_800_000.0)
+        );
+        assert_eq!(pert_variance("2h # 1h/2h/4h"), None);
+        assert_eq!(Duration::from_str("1h/2h"), None);
+    }
+
+    #[test]
+    fn test_from_str_at_recurring() {
+        // a Wednesday
+        let now = NaiveDate::from_ymd_opt(2025, 1, 8)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+
+        let cases = vec![
+            ("30m daily for 2w", Duration::hours(7)),
+            ("1h 30m weekly for 4w", Duration::hours(6)),
+            ("2h - 5m hourly for 8h", Duration::minutes(80)),
+            ("15m weekdays for 2w", Duration::minutes(150)),
+            ("15m weekdays for 3d", Duration::minutes(45)),
+            ("1.5s daily for 1w 10h", Duration::milliseconds(10500)),
+        ];
+
+        for (input, expected) in cases {
+            let result = Duration::from_str_at(input, now).unwrap();
+            assert_eq!(result, expected, "{}", input);
+        }
+
+        assert_eq!(
+            Duration::from_str_at("30m daily for 1w @3", now),
+            Some(Duration::minutes(630))
+        );
+        assert_eq!(Duration::from_str_at("30m daily", now), None);
+        assert_eq!(
+            Duration::from_str_at("100000y hourly for 100000y", now),
+            None
+        );
+    }
+
+    #[test]
+    fn test_scan() {
+        let cases = vec![
+            ("", Duration::zero()),
+            (
+                "Build took 3m 42s",
+                Duration::minutes(3) + Duration::seconds(42),
+            ),
+            (
+                "step 1: 10 s, step 2: 5min",
+                Duration::seconds(10) + Duration::minutes(5),
+            ),
+            ("-2h # the sign is ignored", Duration::hours(2)),
+            ("12 steps, see v1.2", Duration::zero()),
+            (
+                "Time (mean ± σ): 75.3 ms, pytest: 2 passed in 1.50s (0:00:01)",
+                Duration::microseconds(75_300)
+                    + Duration::milliseconds(1500)
+                    + Duration::seconds(1),
+            ),
+            (
+                "elapsed 1:02:03.456, lap 2:05.5, at 17:00",
+                Duration::hours(1)
+                    + Duration::minutes(4)
+                    + Duration::seconds(8)
+                    + Duration::milliseconds(956),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(Duration::scan(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_lenient_duration() {
+        let cases = vec![
+            (
+                "Spent roughly 2h 15m on the migration",
+                Some(Duration::minutes(135)),
+            ),
+            ("2h - 15m", Some(Duration::minutes(105))),
+            ("review -30m, as agreed", Some(Duration::minutes(-30))),
+            ("about 1:30:00 in total # call", Some(Duration::minutes(90))),
+            ("Discussed the roadmap", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(lenient_duration(input), Ok(expected), "{}", input);
+        }
+        assert!(lenient_duration("2h on the migration, 1h on review").is_err());
+        assert!(lenient_duration("meeting 1h then 30m").is_err());
+        assert_eq!(
+            lenient_duration("took 5 minutes"),
+            Err("no duration found in \"took 5 minutes\"".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_saturated_add_and_sub() {
+        let cases = vec![
+            (
+                Duration::days(5),
+                Duration::days(3),
+                Duration::days(8),
+                Duration::days(2),
+            ),
+            (
+                Duration::hours(5),
+                Duration::hours(3),
+                Duration::hours(8),
+                Duration::hours(2),
+            ),
+            (
+                Duration::minutes(30),
+                Duration::minutes(20),
+                Duration::minutes(50),
+                Duration::minutes(10),
+            ),
+        ];
+
+        for (a, b, expected_add, expected_sub) in cases {
+            let result_add = a.saturated_add(&b);
+            let result_sub = a.saturated_sub(&b);
+            assert_eq!(result_add, expected_add);
+            assert_eq!(result_sub, expected_sub);
+        }
+    }
+
+    #[test]
+    fn test_scale() {
+        let d = Duration::hours(10);
+        assert_eq!(scale(d, "1.2"), Some(Duration::hours(12)));
+        assert_eq!(scale(d, "0.5"), Some(Duration::hours(5)));
+        assert_eq!(scale(-d, "1.15"), Some(-Duration::minutes(690)));
+        assert_eq!(
+            scale(Duration::milliseconds(1), "0.5"),
+            Some(Duration::milliseconds(1))
+        );
+        assert_eq!(scale(d, "-1"), None);
+        assert_eq!(scale(d, "1,2"), None);
+        assert_eq!(scale(Duration::MAX, "2"), None);
+    }
+}
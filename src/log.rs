@@ -0,0 +1,82 @@
+//! Diagnostics on stderr, enabled at runtime: `--verbose` logs what is read and how long it
+//! takes, `--debug` also how each line is parsed.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Instant;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    #[default]
+    Off,
+    Verbose,
+    Debug,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Off as u8);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Whether messages of `level` are logged.
+pub fn enabled(level: Level) -> bool {
+    LEVEL.load(Ordering::Relaxed) >= level as u8
+}
+
+/// Logs like `eprintln!` with `--verbose` or `--debug`.
+#[macro_export]
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Verbose) {
+            eprintln!("verbose: {}", format_args!($($arg)*));
+        }
+    };
+}
+
+/// Logs like `eprintln!` with `--debug`.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Debug) {
+            eprintln!("debug: {}", format_args!($($arg)*));
+        }
+    };
+}
+
+pub use crate::{debug, verbose};
+
+/// Logs how long something took when dropped, with `--verbose`.
+pub struct Timer {
+    what: String,
+    start: Instant,
+}
+
+impl Timer {
+    pub fn start(what: impl Into<String>) -> Timer {
+        Timer {
+            what: what.into(),
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        verbose!("{} took {:.1?}", self.what, self.start.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enabled() {
+        assert!(!enabled(Level::Verbose));
+        set_level(Level::Debug);
+        assert!(enabled(Level::Verbose) && enabled(Level::Debug));
+        set_level(Level::Verbose);
+        assert!(enabled(Level::Verbose) && !enabled(Level::Debug));
+        set_level(Level::Off);
+    }
+}
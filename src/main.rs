@@ -24,262 +24,1658 @@
 /// today 0h01m00s
 /// total -0h01m00s
 /// ```
-use std::cmp::Ordering;
 use std::env;
-use std::fmt;
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, IsTerminal, Read};
+use std::path::PathBuf;
 use std::str::FromStr;
 
-use chrono::Duration;
-use debug_print::debug_println;
-use lazy_static::lazy_static;
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
 use regex::Regex;
 
+mod annotate;
+mod billing;
+mod burndown;
+#[cfg(feature = "clipboard")]
+mod clipboard;
+mod config;
+mod csv;
+mod diff;
+mod editor;
+mod examples;
+mod glob;
+mod http;
+mod ics;
+mod json;
+mod labelmap;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod notify;
+mod options;
+mod org;
+mod output;
+mod pairs;
+mod report;
+mod server;
+mod state;
+mod stats;
+mod summary;
+mod timew;
+mod trace;
+#[cfg(feature = "tui")]
+mod tui;
+mod workhours;
+
+use duration_calculator_rs::{
+    buffer, check_limits, check_typos, clock, custom_unit_durations, evaluate, expand,
+    expand_history, expand_keys, expression, extract_durations, lenient_duration, line_duration,
+    log, parse_datetime, pert_variance, range_spread, scale, set_bare_unit, set_custom_units,
+    set_no_calendar_units, set_policies, set_uppercase_months, split_date, split_key, split_tags,
+    validate, Bound, CustomUnits, DisplayableDuration, DurationCalculate, DurationParse, Step,
+    FUNCTIONS, UNITS,
+};
+
+use billing::{Billing, DisplayableBilling, Rounding, RoundingReport};
+use burndown::Rate;
+use config::Config;
+use diff::{Diff, DisplayableDelta, DisplayableDiff};
+use labelmap::LabelMap;
+use log::{debug, verbose};
+use options::{print_usage_and_exit, Format, Options, Style};
+use output::Output;
+use report::{
+    Aggregate, DisplayableReport, Entry, HtmlReport, JsonReport, MarkdownReport, Period,
+    PrometheusReport, Report, Rollup, ShellReport, Sparkline, UNDATED,
+};
+use state::{split_variable, State};
+use stats::{DisplayableStats, Stats};
+use workhours::Schedule;
+
 fn main() {
     let exe = env::args().next().unwrap_or_default();
-    let args: Vec<String> = env::args().skip(1).collect();
-    let mut compact: bool = false;
-    let mut compactset :bool = false;
-
-    let mut stdin_total_prefix_open: bool = false;
-    let mut stdin_total_prefix = String::new();
-    let mut total_prefix_open :bool = false;
-    let mut total_prefix = String::new();
-
-    let mut args_duration = Vec::new();
-
-    for a in args {
-        match a.as_str() {
-            "-c" | "--compact" if ! compactset => {
-                compact = true;
-                compactset = true;
-            },
-            "-t" | "--total-prefix" if total_prefix.is_empty() => {
-                total_prefix_open = true;
-            },
-            "-s" | "--stdin-sum-prefix" if stdin_total_prefix.is_empty() => {
-                stdin_total_prefix_open = true;
-            },
-            "-c" | "--compact" | "-t" | "--total-prefix" | "-s" | "--stdin-sum-prefix" => {
-                eprintln!("{} provided more than once",a);
-                eprintln!();
-                print_usage_and_exit(&exe, 1);
-            },
-            _ if (total_prefix_open || stdin_total_prefix_open) && a.starts_with('-') => {
-                eprintln!("ambiguous prefix {}",a);
+    let options = Options::parse(&exe, env::args().skip(1).collect());
+    log::set_level(options.log_level);
+    if let Some(zone) = &options.tz {
+        clock::set_time_zone(zone).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(49);
+        });
+    }
+    let _summary = options.summary.then(summary::start);
+    let style = options.style;
+    let mut out =
+        Output::open(&options.outputs, options.format, options.append).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(24);
+        });
+
+    if options.version {
+        return out.println_all(format_args!("{} {}", PACKAGE, VERSION));
+    }
+
+    load_config(&exe, &options);
+    if options.capabilities {
+        return out.println_all(capabilities());
+    }
+
+    // with the default grammar rather than the one of the options
+    match options.free.first().map(String::as_str) {
+        Some("examples") => return examples::print(&mut out, style),
+        Some("selftest") if !examples::selftest(&mut out, style) => {
+            out.flush();
+            std::process::exit(41);
+        }
+        Some("selftest") => return,
+        Some("alias") => return alias(&exe, &options, &mut out),
+        _ => {}
+    }
+
+    set_no_calendar_units(options.no_calendar_units);
+    clock::set_wall_clock(options.wall_clock);
+    set_policies(options.dup_units, options.sane);
+
+    match options.free.first().map(String::as_str) {
+        Some("work-hours") => return work_hours(&exe, &options, &mut out),
+        Some("diff") => return diff(&exe, &options, &mut out),
+        Some("countdown") => return countdown(&exe, &options, &mut out),
+        Some("state") => return state(&exe, &options, &mut out),
+        _ => {}
+    }
+
+    if let Some(path) = &options.serve {
+        if let Err(e) = server::serve(path, style) {
+            eprintln!("error serving on {}: {}", path, e);
+            std::process::exit(18);
+        }
+        return;
+    }
+
+    if options.lsp_like {
+        let mut stdout = io::stdout().lock();
+        if let Err(e) = editor::run(&mut io::stdin().lock(), &mut stdout, style) {
+            eprintln!("error in editor protocol: {}", e);
+            std::process::exit(35);
+        }
+        return;
+    }
+
+    if let Some(address) = options.http.clone() {
+        if let Err(e) = http::serve(&address, options) {
+            eprintln!("error serving on {}: {}", address, e);
+            std::process::exit(18);
+        }
+        return;
+    }
+
+    match options.month_unit.as_deref() {
+        Some("M") => set_uppercase_months(true),
+        Some("none") | None => {}
+        Some(unit) => {
+            eprintln!("invalid month unit {:?}, expected M or none", unit);
+            eprintln!();
+            print_usage_and_exit(&exe, 25);
+        }
+    }
+
+    match options.bare_unit.as_deref() {
+        Some(unit @ ("s" | "ms")) => set_bare_unit(Some(unit.to_owned())),
+        None => {}
+        Some(unit) => {
+            eprintln!("invalid bare unit {:?}, expected s or ms", unit);
+            eprintln!();
+            print_usage_and_exit(&exe, 26);
+        }
+    }
+
+    let mut arg_str = options.free.join(" ");
+    if options.clipboard_in {
+        arg_str = format!("{} {}", arg_str, clipboard_expression());
+    }
+
+    if options.trace_json {
+        trace_json(&arg_str, &options, &mut out);
+        return;
+    }
+
+    if options.check {
+        check(&arg_str, &options, &mut out);
+        return;
+    }
+
+    if let Some(path) = &options.batch {
+        batch(path, &options, &mut out);
+        return;
+    }
+
+    if let Some(path) = &options.pairs {
+        pairs(&exe, path, &options, &mut out);
+        return;
+    }
+
+    if options.annotate {
+        let start = std::time::Instant::now();
+        let mut content = Vec::new();
+        io::stdin()
+            .read_to_end(&mut content)
+            .unwrap_or_else(|_| panic!("IO error reading stdin"));
+        let content = decode(content, "stdin", options.strict_encoding);
+        let annotated = annotate::annotate(&content, style);
+        summary::add_parsing_time(start.elapsed());
+        out.print_all(annotated);
+        return;
+    }
+
+    let range = date_range(&exe, &options);
+    let timer = log::Timer::start("importing");
+    let (mut entries, mut files) = imported_entries(&exe, &options, range);
+    drop(timer);
+    if let (true, Some(path)) = (options.group, &options.label_map) {
+        let map = LabelMap::parse(&read_file(path, options.strict_encoding)).unwrap_or_else(|e| {
+            eprintln!("cannot read label map {}: {}", path, e);
+            std::process::exit(30);
+        });
+        for entry in &mut entries {
+            entry.label = map.map(&entry.label).to_owned();
+        }
+    }
+    let aggregate = options.aggregate.as_ref().map_or(Aggregate::Sum, |a| {
+        Aggregate::from_str(a).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            eprintln!();
+            print_usage_and_exit(&exe, 13);
+        })
+    });
+    let mut report = Report::aggregated(&entries, aggregate);
+    if options.stable {
+        report.sort();
+        files.sort();
+    }
+    let burn_rate = options.burn_rate.as_ref().map(|r| {
+        Rate::from_str(r).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            eprintln!();
+            print_usage_and_exit(&exe, 15);
+        })
+    });
+    let billing = options.rate.as_ref().map(|r| billing(&exe, r, &options));
+    let by = options.by.as_ref().map(|p| {
+        Period::from_str(p).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            eprintln!();
+            print_usage_and_exit(&exe, 9);
+        })
+    });
+
+    // read stdin only if there is a redirect
+    let stdin_label = label_or(&options.stdin_total_prefix, "stdin");
+    let stdin_entries = (!io::stdin().is_terminal() && !options.timew).then(|| {
+        let _timer = log::Timer::start("reading stdin");
+        let mut stdin_entries =
+            read_lines(io::stdin().lock(), "stdin", Some(stdin_label), &options);
+        stdin_entries.retain(|e| {
+            e.in_date_range(range.0, range.1)
+                && options.tag.as_ref().is_none_or(|t| e.tags.contains(t))
+        });
+        stdin_entries
+    });
+    // keyed lines go to their accumulator rather than the sum, after those of `--state`
+    let mut state = load_state(&options);
+    for (key, d) in accumulators(stdin_entries.iter().flatten()) {
+        state.add(&key, d);
+    }
+    save_state(&state, &options);
+    let stdin_entries = stdin_entries.map(|mut entries| {
+        entries.retain(|e| split_key(&e.description).0.is_none());
+        entries
+    });
+    let d_stdin = stdin_entries
+        .as_ref()
+        .map(|e| Report::aggregated(e, aggregate).total());
+
+    if options.free.first().is_some_and(|a| a == "tui") {
+        let all: Vec<Entry> = entries
+            .into_iter()
+            .chain(stdin_entries.into_iter().flatten())
+            .collect();
+        return tui(&all, style);
+    }
+
+    check_typos(&arg_str, "arguments").unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(38);
+    });
+    // `total` is the sum of the imports and stdin so far, unless a variable or an accumulator
+    // is named so
+    let mut names = state.names();
+    names.push((
+        "total".to_owned(),
+        report
+            .total()
+            .saturated_add(&d_stdin.unwrap_or_else(Duration::zero)),
+    ));
+    let d_args = Duration::from_str(&expand_keys(&arg_str, &names)).unwrap_or_else(|| {
+        eprintln!("cannot parse {:?} from arguments as duration", &arg_str);
+        std::process::exit(48);
+    });
+    let d_args = match &options.repeat {
+        Some(r) => r
+            .parse::<i32>()
+            .ok()
+            .and_then(|r| d_args.checked_mul(r))
+            .unwrap_or_else(|| {
+                eprintln!("invalid repeat count {:?}", r);
                 eprintln!();
-                print_usage_and_exit(&exe, 2);
-            },
-            _ if total_prefix_open => {
-                total_prefix_open = false;
-                total_prefix = a + " ";
-            },
-            _ if stdin_total_prefix_open => {
-                stdin_total_prefix_open = false;
-                stdin_total_prefix = a + " ";
-            },
-            _ => {
-                args_duration.push(a);
-            }
+                print_usage_and_exit(&exe, 16);
+            }),
+        None => d_args,
+    };
+
+    if options.stats || options.histogram.is_some() || options.percentiles.is_some() {
+        let histogram = options.histogram.as_ref().map(|w| {
+            Duration::from_str(w)
+                .filter(|w| *w > Duration::zero())
+                .unwrap_or_else(|| {
+                    eprintln!("invalid histogram bucket width {:?}", w);
+                    eprintln!();
+                    print_usage_and_exit(&exe, 12);
+                })
+        });
+        let percentiles: Vec<f64> = options
+            .percentiles
+            .iter()
+            .flat_map(|p| p.split(','))
+            .map(|p| {
+                p.trim()
+                    .parse()
+                    .ok()
+                    .filter(|p| *p > 0.0 && *p <= 100.0)
+                    .unwrap_or_else(|| {
+                        eprintln!("invalid percentile {:?}", p);
+                        eprintln!();
+                        print_usage_and_exit(&exe, 12);
+                    })
+            })
+            .collect();
+        let stats = Stats::new(
+            entries
+                .iter()
+                .chain(stdin_entries.iter().flatten())
+                .map(|e| e.duration)
+                .collect(),
+        );
+        out.print_all(DisplayableStats(&stats, style, histogram, &percentiles));
+        return;
+    }
+
+    if let Some(start) = &options.timeline {
+        let start = parse_datetime(start, Local::now().naive_local()).unwrap_or_else(|| {
+            eprintln!("cannot parse {:?} as timestamp", start);
+            eprintln!();
+            print_usage_and_exit(&exe, 14);
+        });
+        let tasks = entries.iter().chain(stdin_entries.iter().flatten());
+        for (start, end, e) in timeline(start, tasks) {
+            out.println_all(format_args!(
+                "{} - {}  {}",
+                start.format("%Y-%m-%d %H:%M"),
+                end.format("%Y-%m-%d %H:%M"),
+                e.description.trim()
+            ));
+        }
+        return;
+    }
+
+    let formats = out.report_formats();
+    if !formats.is_empty() {
+        let mut dated: Vec<&Entry> = entries.iter().collect();
+        if d_stdin.is_some_and(|d| !d.is_zero()) {
+            dated.extend(stdin_entries.iter().flatten());
+        }
+
+        let mut report = match by {
+            Some(period) => Report::by_period(dated, period, aggregate),
+            None if options.by_tag => Report::by_tag(dated, aggregate),
+            None => Report::aggregated(dated, aggregate),
         };
+        if options.stable {
+            report.sort();
+        }
+        if !d_args.is_zero() {
+            report.add("arguments", d_args);
+        }
+
+        let total_label = label_or(&options.total_prefix, "Total");
+        for format in formats {
+            match format {
+                Format::Markdown => {
+                    out.print_format(format, MarkdownReport(&report, total_label, style))
+                }
+                Format::Html => out.print_format(
+                    format,
+                    HtmlReport(&report, total_label, style, options.chart),
+                ),
+                Format::Json => out.print_format(format, JsonReport(&report, total_label, style)),
+                Format::Shell => {
+                    let name = options.print_var.as_deref().unwrap_or("RESULT");
+                    out.print_format(format, ShellReport(&report, name, style))
+                }
+                Format::Prometheus => {
+                    let name = options.prom_name.as_deref().unwrap_or("duration_seconds");
+                    if !report::is_prometheus_name(name, true) {
+                        eprintln!("invalid metric name {:?}", name);
+                        eprintln!();
+                        print_usage_and_exit(&exe, 19);
+                    }
+                    let labels = options.prom_labels.as_deref().unwrap_or("");
+                    let labels = report::prometheus_labels(labels).unwrap_or_else(|e| {
+                        eprintln!("{}", e);
+                        eprintln!();
+                        print_usage_and_exit(&exe, 19);
+                    });
+                    out.print_format(format, PrometheusReport(&report, name, &labels));
+                }
+                Format::Text => unreachable!(),
+            }
+        }
+
+        // the rest is plain text only
+        if !out.has_text() {
+            return;
+        }
     }
 
-    let arg_str = args_duration.join(" ");
+    if options.group && !entries.is_empty() && options.rollup {
+        out.print(DisplayableReport(
+            &Rollup::new(&report).flatten(),
+            style,
+            options.chart,
+        ));
+    } else if options.group && !entries.is_empty() {
+        out.print(DisplayableReport(&report, style, options.chart));
+    } else if files.groups.len() > 1 {
+        out.print(DisplayableReport(&files, style, options.chart));
+    }
 
-    if total_prefix_open {
-        eprintln!("error parsing total summary prefix");
-        eprintln!();
-        print_usage_and_exit(&exe, 3);
+    if let Some(period) = by {
+        let dated = entries.iter().chain(stdin_entries.iter().flatten());
+        let periods = Report::by_period(dated, period, aggregate);
+        out.print(DisplayableReport(&periods, style, options.chart));
+        if options.sparkline {
+            let totals: Vec<Duration> = periods
+                .groups
+                .iter()
+                .filter(|(label, _)| label != UNDATED)
+                .map(|(_, d)| *d)
+                .collect();
+            out.println(Sparkline(&totals));
+        }
     }
 
-    if stdin_total_prefix_open {
-        eprintln!("error parsing stdin_total summary prefix");
-        eprintln!();
-        print_usage_and_exit(&exe, 4);
+    if let Some(window) = &options.window {
+        let days = Duration::from_str(window)
+            .filter(|w| *w > Duration::zero() && Duration::days(w.num_days()) == *w)
+            .map(|w| w.num_days())
+            .unwrap_or_else(|| {
+                eprintln!("invalid window {:?}, expected whole days like 7d", window);
+                eprintln!();
+                print_usage_and_exit(&exe, 43);
+            });
+        let dated = entries.iter().chain(stdin_entries.iter().flatten());
+        let totals = Report::rolling(dated, days);
+        out.print(DisplayableReport(&totals, style, options.chart));
+        if options.sparkline {
+            let totals: Vec<Duration> = totals.groups.iter().map(|(_, d)| *d).collect();
+            out.println(Sparkline(&totals));
+        }
+    }
+
+    if options.by_tag {
+        let tagged = entries.iter().chain(stdin_entries.iter().flatten());
+        let mut tags = Report::by_tag(tagged, aggregate);
+        if options.stable {
+            tags.sort();
+        }
+        out.print(DisplayableReport(&tags, style, options.chart));
     }
 
     let mut d = Duration::zero();
     let mut printed: bool = false;
 
-    // read stdin only if there is a redirect
-    if atty::isnt(atty::Stream::Stdin) {
-        for line in io::stdin().lock().lines() {
-            let ls = line.unwrap_or_else(|_| panic!("IO error reading stdin"));
-            let d_line =
-                Duration::from_str(&ls).unwrap_or_else(|| panic!("cannot parse {:?}", &ls));
-            d = d.saturated_add(&d_line);
-        }
-
+    if let Some(d_stdin) = d_stdin {
+        d = d_stdin;
         printed = true;
-        println!("{}{}", stdin_total_prefix, DisplayableDuration(d, compact));
+        out.println(format_args!(
+            "{}{}",
+            options.stdin_total_prefix,
+            DisplayableDuration(d, style)
+        ));
     }
 
-    let d_from_args = Duration::from_str(&arg_str)
-        .unwrap_or_else(|| panic!("cannot parse {:?} from arguments as duration", &arg_str));
+    let d_from_args = d_args.saturated_add(&report.total());
 
     // don't print 0 if there is already a result from stdin
     if d_from_args != Duration::zero() || !printed {
-        d = d.saturated_add(&d_from_args);
-        println!("{}{}", total_prefix, DisplayableDuration(d, compact));
+        d = match (printed, report.groups.is_empty()) {
+            // arguments are always added, the aggregate is over stdin and imports only
+            (true, false) => aggregate.combine(d, report.total()).saturated_add(&d_args),
+            _ => d.saturated_add(&d_from_args),
+        };
+        out.println(format_args!(
+            "{}{}",
+            options.total_prefix,
+            DisplayableDuration(d, style)
+        ));
+    }
+
+    let now = Local::now().naive_local();
+    let spreads: Vec<(Duration, Duration)> = stdin_entries
+        .iter()
+        .flatten()
+        .map(|e| e.description.as_str())
+        .chain([arg_str.as_str()])
+        .filter_map(|l| range_spread(l, now))
+        .collect();
+    if !spreads.is_empty() {
+        let (low, high) = spreads.iter().fold((d, d), |(low, high), (l, h)| {
+            (low.saturated_add(l), high.saturated_add(h))
+        });
+        out.println(format_args!(
+            "range {} – {}",
+            DisplayableDuration(low, style),
+            DisplayableDuration(high, style)
+        ));
+    }
+
+    let variances: Vec<f64> = stdin_entries
+        .iter()
+        .flatten()
+        .map(|e| e.description.as_str())
+        .chain([arg_str.as_str()])
+        .filter_map(pert_variance)
+        .collect();
+    if !variances.is_empty() {
+        let deviation = variances.iter().sum::<f64>().sqrt().round() as i64;
+        out.println(format_args!(
+            "expected {} ± {}",
+            DisplayableDuration(d, style),
+            DisplayableDuration(Duration::milliseconds(deviation), style)
+        ));
+    }
+
+    let buffers: Vec<Duration> = stdin_entries
+        .iter()
+        .flatten()
+        .map(|e| e.description.as_str())
+        .chain([arg_str.as_str()])
+        .filter_map(buffer)
+        .collect();
+    if !buffers.is_empty() {
+        let buffer = buffers
+            .iter()
+            .fold(Duration::zero(), |sum, b| sum.saturated_add(b));
+        out.println(format_args!(
+            "buffer ± {}",
+            DisplayableDuration(buffer, style)
+        ));
+    }
+
+    if let Some(cap) = &options.cap_per_line {
+        let cap = Duration::from_str(cap)
+            .filter(|c| *c > Duration::zero())
+            .unwrap_or_else(|| {
+                eprintln!("invalid cap per line {:?}", cap);
+                eprintln!();
+                print_usage_and_exit(&exe, 42);
+            });
+        let lines = entries.iter().chain(stdin_entries.iter().flatten());
+        let over = overage(lines.map(|e| e.duration), cap);
+        out.println(format_args!(
+            "capped {}",
+            DisplayableDuration(d.saturated_sub(&over), style)
+        ));
+        out.println(format_args!(
+            "over cap {}",
+            DisplayableDuration(over, style)
+        ));
+    }
+
+    if let Some(factor) = &options.scale {
+        let scaled = scale(d, factor).unwrap_or_else(|| {
+            eprintln!("invalid scale factor {:?}", factor);
+            eprintln!();
+            print_usage_and_exit(&exe, 34);
+        });
+        out.println(format_args!(
+            "scaled by {} {}",
+            factor,
+            DisplayableDuration(scaled, style)
+        ));
+    }
+
+    if let Some(headcount) = &options.headcount {
+        let people = headcount
+            .parse::<i32>()
+            .ok()
+            .filter(|n| *n > 0)
+            .unwrap_or_else(|| {
+                eprintln!("invalid headcount {:?}", headcount);
+                eprintln!();
+                print_usage_and_exit(&exe, 33);
+            });
+        out.println(format_args!(
+            "{} people {}",
+            people,
+            DisplayableDuration(d / people, style)
+        ));
+    }
+
+    if let Some(billing) = &billing {
+        let mut groups = Report::default();
+        if options.group && !entries.is_empty() {
+            groups.groups = report.groups.clone();
+        }
+        let total_label = label_or(&options.total_prefix, "total");
+        out.print(DisplayableBilling(&groups, billing, d, total_label, style));
+    }
+
+    if options.rounding_report {
+        let lines = entries.iter().chain(stdin_entries.iter().flatten());
+        let mut lines: Vec<Duration> = lines.map(|e| e.duration).collect();
+        // the arguments are one line
+        if !d_args.is_zero() {
+            lines.push(d_args);
+        }
+        out.print(RoundingReport(&lines, billing.as_ref(), style));
+    }
+
+    if let Some(command) = &options.exec {
+        let value = if options.seconds {
+            d.num_seconds().to_string()
+        } else {
+            DisplayableDuration(d, style).to_string()
+        };
+        // output of the command comes after ours
+        out.flush();
+        exec(command, &value);
+    }
+
+    if options.clipboard_out {
+        copy_to_clipboard(&DisplayableDuration(d, style).to_string());
+    }
+
+    if let Some(path) = &options.baseline {
+        baseline(&exe, path, d, &options, &mut out);
+    }
+
+    if let Some(rate) = burn_rate {
+        let schedule = options.business_days.then(|| schedule(&exe, &options));
+        let today = Local::now().date_naive();
+        match burndown::completion(d, &rate, today, schedule.as_ref()) {
+            Some(date) => out.println(format_args!("done by {}", date.format("%Y-%m-%d (%a)"))),
+            None => out.println("done never"),
+        }
     }
 }
 
-fn print_usage_and_exit(exe: &str, errorlevel: i32) {
-    print_usage(exe);
-    std::process::exit(errorlevel);
+/// Prints the result of every line of `path` (`-` for stdin) on its own line, after the line
+/// and a tab with `--echo`. Blank and comment lines give an empty result, so do lines that cannot
+/// be parsed, which are reported on stderr and make it exit with 40 at the end.
+fn batch(path: &str, options: &Options, out: &mut Output) {
+    let start = std::time::Instant::now();
+    let content = read_file_or_stdin(path, options);
+    let mut parsing = start.elapsed();
+
+    let mut failed = false;
+    let mut state = load_state(options);
+    for (i, line) in content.lines().enumerate() {
+        let start = std::time::Instant::now();
+        let d = batch_line(line, &mut state);
+        parsing += start.elapsed();
+        state.history.push(d.clone().ok().flatten());
+        let result = match d {
+            Ok(Some(d)) => {
+                summary::count_line(false);
+                DisplayableDuration(d, options.style).to_string()
+            }
+            Ok(None) => {
+                summary::count_line(true);
+                String::new()
+            }
+            Err(e) => {
+                eprintln!("line {}: {}", i + 1, e);
+                summary::count_failed();
+                failed = true;
+                String::new()
+            }
+        };
+        if options.echo {
+            out.println_all(format_args!("{}\t{}", line, result));
+        } else {
+            out.println_all(result);
+        }
+    }
+    summary::add_parsing_time(parsing);
+    save_state(&state, options);
+
+    if failed {
+        out.flush();
+        summary::print();
+        std::process::exit(40);
+    }
 }
 
-fn print_usage(exe: &str) {
-    println!("Usage:");
-    println!();
-    println!("{} [Options] [Duration String]", exe);
-    println!();
-    println!("where Options:");
-    println!("-c|--compact\tCompact output");
-    println!("-t|--total-prefix <prefix>\tPrefix the end sum with <prefix>");
-    println!("-s|--stdin-sum-prefix <prefix>\tPrefix the stdin sum with <prefix>");
+/// Evaluates a line of `--batch` with the results, variables and accumulators of `state`. A line
+/// like `rest := 40h - mon` also sets a variable, and one like `mon=8h` adds to the accumulator
+/// and results in its new sum.
+fn batch_line(line: &str, state: &mut State) -> Result<Option<Duration>, String> {
+    let line = expand_history(line, &state.history)?;
+    let (variable, expression) = split_variable(&line);
+    let (key, expression) = match variable {
+        Some(_) => (None, expression),
+        None => split_key(expression),
+    };
+    let d = line_duration(&expand_keys(expression, &state.names()))?;
+
+    match (variable, key, d) {
+        (Some(name), _, Some(d)) => state.set(name, d),
+        (_, Some(key), Some(d)) => return Ok(Some(state.add(key, d))),
+        _ => {}
+    }
+    Ok(d)
 }
 
-pub struct DisplayableDuration(
-    pub Duration,
-    pub bool
-);
+/// The session of `--state`, empty without it.
+fn load_state(options: &Options) -> State {
+    let Some(path) = &options.state else {
+        return State::default();
+    };
+    State::load(path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(50);
+    })
+}
 
-impl fmt::Display for DisplayableDuration {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let sgn = match Duration::zero().cmp(&self.0) {
-            /* so, er, if 0 is greater than the duration, the sign is negative. I'm
-            deleting this from working memory and hopefully never have to look again.
-            */
-            Ordering::Greater => -1,
-            _ => 1,
-        };
+/// Saves the session to the file of `--state`, if any.
+fn save_state(state: &State, options: &Options) {
+    if let Some(path) = &options.state {
+        state.save(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(50);
+        });
+    }
+}
+
+/// `state export` prints the session of `--state` as JSON, `state import` replaces it with such
+/// an export, given as argument or on stdin, e.g. to move a session to another machine.
+fn state(exe: &str, options: &Options, out: &mut Output) {
+    if options.state.is_none() {
+        eprintln!("state requires --state <file>");
+        eprintln!();
+        print_usage_and_exit(exe, 50);
+    }
+
+    match (options.free.get(1).map(String::as_str), options.free.len()) {
+        (Some("export"), 2) => out.println_all(load_state(options).to_json()),
+        (Some("import"), 2 | 3) => {
+            let json = match options.free.get(2) {
+                Some(json) => json.clone(),
+                None => read_file_or_stdin("-", options),
+            };
+            let state = State::parse(&json).unwrap_or_else(|e| {
+                eprintln!("cannot import state: {}", e);
+                std::process::exit(50);
+            });
+            save_state(&state, options);
+        }
+        _ => {
+            eprintln!("state requires export or import [<json>]");
+            eprintln!();
+            print_usage_and_exit(exe, 50);
+        }
+    }
+}
+
+/// The content of `path`, or of stdin for `-`.
+fn read_file_or_stdin(path: &str, options: &Options) -> String {
+    if path == "-" {
+        let mut content = Vec::new();
+        io::stdin()
+            .read_to_end(&mut content)
+            .unwrap_or_else(|_| panic!("IO error reading stdin"));
+        decode(content, "stdin", options.strict_encoding)
+    } else {
+        read_file(path, options.strict_encoding)
+    }
+}
 
-        /*  if the duration is negative, display sign prefixing the whole duration,
-           but keep the portions positive. -2h-05m-20s looks odd, doesn't it?
-        */
-        let n = sgn * self.0.num_seconds();
-        let hours = n / 3600;
-        let minutes = (n % 3600) / 60;
-        let seconds = n % 60;
+/// Prints both durations of every line of `path` (`-` for stdin) and the result of `--pairs-op`,
+/// separated by tabs, and the totals of the three columns at the end. Like `batch`, blank and
+/// comment lines give an empty line, and lines that cannot be parsed are reported on stderr and
+/// make it exit with 45 at the end.
+fn pairs(exe: &str, path: &str, options: &Options, out: &mut Output) {
+    let op = options
+        .pairs_op
+        .as_ref()
+        .map_or(Ok(pairs::Op::default()), |o| pairs::Op::from_str(o))
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            eprintln!();
+            print_usage_and_exit(exe, 45);
+        });
+    let delimiter = options.delimiter.as_deref().unwrap_or("\t");
+    let start = std::time::Instant::now();
+    let content = read_file_or_stdin(path, options);
+    let mut parsing = start.elapsed();
 
-        if self.1 {
-            write!(
-                f,
-                "{}{}h{:02}m{:02}s",
-                if sgn < 0 { "-" } else { "" },
-                hours,
-                minutes,
-                seconds
-            )
-        } 
-        else {
-            write!(
-                f,
-                "{}{}h {:02}m {:02}s",
-                if sgn < 0 { "-" } else { "" },
-                hours,
-                minutes,
-                seconds
-            )
+    let mut failed = false;
+    let mut totals = [Duration::zero(); 3];
+    for (i, line) in content.lines().enumerate() {
+        let start = std::time::Instant::now();
+        let pair = pairs::parse(line, delimiter);
+        parsing += start.elapsed();
+        match pair {
+            Ok(Some((a, b))) => {
+                summary::count_line(false);
+                let row = [a, b, op.apply(a, b)];
+                for (total, d) in totals.iter_mut().zip(row) {
+                    *total = total.saturated_add(&d);
+                }
+                out.println_all(format_args!(
+                    "{}\t{}\t{}",
+                    DisplayableDuration(row[0], options.style),
+                    DisplayableDuration(row[1], options.style),
+                    DisplayableDuration(row[2], options.style)
+                ));
+            }
+            Ok(None) => {
+                summary::count_line(true);
+                out.println_all("");
+            }
+            Err(e) => {
+                eprintln!("line {}: {}", i + 1, e);
+                summary::count_failed();
+                failed = true;
+                out.println_all("");
+            }
         }
     }
+    summary::add_parsing_time(parsing);
+    out.println_all(format_args!(
+        "{}\t{}\t{}",
+        DisplayableDuration(totals[0], options.style),
+        DisplayableDuration(totals[1], options.style),
+        DisplayableDuration(totals[2], options.style)
+    ));
+
+    if failed {
+        out.flush();
+        summary::print();
+        std::process::exit(45);
+    }
 }
 
-/// A trait for performing arithmetic operations on durations not already covered in the standard
-trait DurationCalculate {
-    /// Adds two durations and returns the result or maximum value for overflow
-    fn saturated_add(&self, rhs: &Self) -> Self;
+/// Prints the diagnostics of each stdin line and of the arguments like `stdin:3:5: error: ...`,
+/// with lines and columns counted from 1. Exits with 36 if there are errors.
+fn check(arg_str: &str, options: &Options, out: &mut Output) {
+    let mut lines = Vec::new();
+    if !io::stdin().is_terminal() {
+        for (i, line) in io::stdin().lock().split(b'\n').enumerate() {
+            let line = line.unwrap_or_else(|_| panic!("IO error reading stdin"));
+            let line = decode(
+                line,
+                &format!("line {} of stdin", i + 1),
+                options.strict_encoding,
+            );
+            lines.push(("stdin", i + 1, line.trim_end_matches('\r').to_owned()));
+        }
+    }
+    if !arg_str.trim().is_empty() {
+        lines.push(("arguments", 1, arg_str.to_owned()));
+    }
+
+    let mut errors = false;
+    for (source, number, line) in &lines {
+        for diagnostic in validate::validate(line) {
+            errors |= diagnostic.severity == validate::Severity::Error;
+            out.println(format_args!(
+                "{}:{}:{}: {}",
+                source,
+                number,
+                diagnostic.column + 1,
+                diagnostic
+            ));
+        }
+    }
 
-    /// Adds two durations and returns the result or minimum value for overflow
-    fn saturated_sub(&self, rhs: &Self) -> Self;
+    if errors {
+        out.flush();
+        std::process::exit(36);
+    }
 }
 
-impl DurationCalculate for Duration {
-    fn saturated_add(&self, rhs: &Duration) -> Duration {
-        self.checked_add(rhs).unwrap_or(Duration::max_value())
+/// Prints the trace of each stdin line, if there is a redirect, and of the arguments as JSON,
+/// one object per line.
+fn trace_json(arg_str: &str, options: &Options, out: &mut Output) {
+    let now = Local::now().naive_local();
+
+    if !io::stdin().is_terminal() {
+        for (i, line) in io::stdin().lock().split(b'\n').enumerate() {
+            let line = line.unwrap_or_else(|_| panic!("IO error reading stdin"));
+            let line = decode(
+                line,
+                &format!("line {} of stdin", i + 1),
+                options.strict_encoding,
+            );
+            let line = line.trim_end_matches('\r');
+            out.println_all(trace::trace(line, now, options.style));
+        }
     }
 
-    fn saturated_sub(&self, rhs: &Duration) -> Duration {
-        self.checked_sub(rhs).unwrap_or(Duration::min_value())
+    if !arg_str.trim().is_empty() {
+        out.println_all(trace::trace(arg_str, now, options.style));
     }
 }
 
-/// A trait for parsing duration strings.
-trait DurationParse {
-    /// Parses a "line" of a duration string and returns a `Duration` or `None` if the input is invalid.
-    fn from_str(input: &str) -> Option<Duration>;
+/// Sets the custom units of the configuration file, `--config` or the default one if it exists.
+fn load_config(exe: &str, options: &Options) {
+    let path = match &options.config {
+        Some(path) => path.clone(),
+        None => match config::default_path().filter(|p| p.exists()) {
+            Some(path) => path.to_string_lossy().into_owned(),
+            None => return,
+        },
+    };
+    let fail = |e: String| -> ! {
+        eprintln!("invalid config {}: {}", path, e);
+        eprintln!();
+        print_usage_and_exit(exe, 37);
+    };
+
+    let config =
+        Config::parse(&read_file(&path, options.strict_encoding)).unwrap_or_else(|e| fail(e));
+    let units = custom_units(&config.units).unwrap_or_else(|e| fail(e));
+    verbose!("read {} custom units from {}", config.units.len(), path);
+    set_custom_units(units);
+}
 
-    /// Converts the smallest token (e.g. "5m", "4s") to a `Duration` object or `None` for invalid input.
-    fn token_to_duration(count: i64, unit: &str) -> Option<Duration>;
+/// The custom units of a configuration file, failing for invalid names and durations.
+fn custom_units(units: &[(String, String)]) -> Result<CustomUnits, String> {
+    units
+        .iter()
+        .map(|(name, value)| match Duration::from_str(value) {
+            Some(d) => Ok((name.clone(), d)),
+            None => Err(format!(
+                "cannot parse {:?} of unit {} as duration",
+                value, name
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .and_then(CustomUnits::new)
 }
 
-impl DurationParse for Duration {
-    fn from_str(input: &str) -> Option<Duration> {
-        lazy_static! {
-            static ref LINE_PATTERN: Regex =
-                Regex::new(r"^(?:\s*[+-]\s*(?:\d+\s*(?:y|d|h|m|s)\s*)+)+$").unwrap();
-            static ref DURATION_COMPOSITE_PATTERN: Regex =
-                Regex::new(r"(?P<sign>[+-])\s*(?P<duration>\s*(?:\d+\s*(?:y|d|h|m|s)\s*)+)")
-                    .unwrap();
-            static ref DURATION_PATTERN: Regex =
-                Regex::new(r"(?P<count>\d+)\s*(?P<unit>y|d|h|m|min|s)").unwrap();
+/// Adds, lists or removes the custom units of the configuration file, for `alias`.
+fn alias(exe: &str, options: &Options, out: &mut Output) {
+    let fail = |e: String| -> ! {
+        eprintln!("{}", e);
+        eprintln!();
+        print_usage_and_exit(exe, 44);
+    };
+
+    let path = match &options.config {
+        Some(path) => PathBuf::from(path),
+        None => config::default_path().unwrap_or_else(|| {
+            fail("no config file, neither HOME nor XDG_CONFIG_HOME is set".to_owned())
+        }),
+    };
+    let input = match path.exists() {
+        true => read_file(&path.to_string_lossy(), options.strict_encoding),
+        false => String::new(),
+    };
+    // load_config has already checked an existing file
+    let config = Config::parse(&input).unwrap_or_else(|e| fail(e));
+
+    let args: Vec<&str> = options.free[1..].iter().map(String::as_str).collect();
+    let output = match args[..] {
+        ["list"] => {
+            for (name, value) in &config.units {
+                out.println_all(format_args!("{} = {}", name, value));
+            }
+            return;
+        }
+        ["add", definition] => {
+            let (name, value) = definition
+                .split_once('=')
+                .map(|(n, v)| (n.trim(), v.trim()))
+                .filter(|(n, v)| !n.is_empty() && !v.is_empty())
+                .unwrap_or_else(|| {
+                    fail(format!(
+                        "invalid alias {:?}, expected <name>=<duration>",
+                        definition
+                    ))
+                });
+            let mut units = config.units.clone();
+            units.retain(|(n, _)| n != name);
+            units.push((name.to_owned(), value.to_owned()));
+            custom_units(&units).unwrap_or_else(|e| fail(e));
+            config::set_unit(&input, name, value)
         }
+        ["rm", name] => config::remove_unit(&input, name)
+            .unwrap_or_else(|| fail(format!("no alias {} in {}", name, path.display()))),
+        _ => fail("alias requires add <name>=<duration>, list or rm <name>".to_owned()),
+    };
 
-        let mut duration = Duration::zero();
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, output));
+    if let Err(e) = written {
+        eprintln!("error writing {}: {}", path.display(), e);
+        std::process::exit(44);
+    }
+}
 
-        if input.is_empty() {
-            return Some(duration);
+/// The sum of what `durations` exceed `cap` by, for `--cap-per-line`.
+fn overage(durations: impl Iterator<Item = Duration>, cap: Duration) -> Duration {
+    durations
+        .filter(|d| *d > cap)
+        .fold(Duration::zero(), |sum, d| sum.saturated_add(&(d - cap)))
+}
+
+/// Parses `--rate` and the options of the billing increment.
+fn billing(exe: &str, rate: &str, options: &Options) -> Billing {
+    let fail = |e: String| -> ! {
+        eprintln!("{}", e);
+        eprintln!();
+        print_usage_and_exit(exe, 32);
+    };
+
+    let increment = options.increment.as_ref().map(|i| {
+        Duration::from_str(i)
+            .filter(|i| *i > Duration::zero())
+            .unwrap_or_else(|| fail(format!("invalid billing increment {:?}", i)))
+    });
+    let rounding = options
+        .rounding
+        .as_ref()
+        .map_or(Ok(Rounding::Up), |r| Rounding::from_str(r))
+        .unwrap_or_else(|e| fail(e));
+
+    Billing {
+        rate: billing::parse_rate(rate).unwrap_or_else(|e| fail(e)),
+        currency: options.currency.clone(),
+        increment,
+        rounding,
+    }
+}
+
+/// Runs `command` with a shell, each `{}` replaced by `value` in single quotes. Output goes
+/// straight to ours, a failing command fails us with its exit code.
+fn exec(command: &str, value: &str) {
+    let command = command.replace("{}", &shell_quote(value));
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .status()
+        .unwrap_or_else(|e| {
+            eprintln!("error running {:?}: {}", command, e);
+            std::process::exit(23);
+        });
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(23));
+    }
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+#[cfg(feature = "clipboard")]
+fn clipboard_expression() -> String {
+    let text = clipboard::read().unwrap_or_else(|e| {
+        eprintln!("error reading clipboard: {}", e);
+        std::process::exit(21);
+    });
+    clipboard::expression(&text)
+}
+
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(text: &str) {
+    if let Err(e) = clipboard::write(text) {
+        eprintln!("error writing clipboard: {}", e);
+        std::process::exit(21);
+    }
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn clipboard_expression() -> String {
+    eprintln!("clipboard is not available, build with --features clipboard");
+    std::process::exit(21);
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn copy_to_clipboard(_text: &str) {
+    eprintln!("clipboard is not available, build with --features clipboard");
+    std::process::exit(21);
+}
+
+#[cfg(feature = "tui")]
+fn tui(entries: &[Entry], style: Style) {
+    if let Err(e) = tui::run(entries, style) {
+        eprintln!("error running tui: {}", e);
+        std::process::exit(20);
+    }
+}
+
+#[cfg(not(feature = "tui"))]
+fn tui(_entries: &[Entry], _style: Style) {
+    eprintln!("tui is not available, build with --features tui");
+    std::process::exit(20);
+}
+
+/// Prints the change of the total `d` against the one stored in the baseline file at `path`,
+/// and stores `d` instead unless `--no-update`. A missing file is created.
+fn baseline(exe: &str, path: &str, d: Duration, options: &Options, out: &mut Output) {
+    let previous = match std::fs::read(path) {
+        Ok(content) => {
+            let content = decode(content, path, options.strict_encoding);
+            let previous = content.trim();
+            Some(Duration::from_str(previous).unwrap_or_else(|| {
+                eprintln!("cannot parse baseline {:?} in {}", previous, path);
+                eprintln!();
+                print_usage_and_exit(exe, 17);
+            }))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => {
+            eprintln!("error reading baseline {}: {}", path, e);
+            std::process::exit(17);
         }
+    };
+
+    match previous {
+        Some(previous) => out.println(format_args!(
+            "baseline {}, {}",
+            DisplayableDuration(previous, options.style),
+            DisplayableDelta(previous, d, options.style)
+        )),
+        None => out.println("baseline none"),
+    }
 
-        // ugh...
-        let line = match input.chars().next() {
-            Some('+') | Some('-') => input.to_owned(),
-            _ => "+".to_owned() + input,
+    if !options.no_update {
+        let content = format!("{}\n", DisplayableDuration(d, Style::default()));
+        std::fs::write(path, content).unwrap_or_else(|e| {
+            eprintln!("error writing baseline {}: {}", path, e);
+            std::process::exit(17);
+        });
+    }
+}
+
+/// Reads duration lines as entries labelled `label`, or with their comment if there is no label.
+/// Lines may start with a date like `2025-01-06 2h 30m`, which becomes the start of the entry.
+/// A UTF-8 byte order mark and carriage returns left over from Windows line endings are ignored.
+/// Invalid UTF-8 is replaced with a warning, unless `--strict-encoding`. With `--scan` or
+/// `--extract`, durations are picked from free-form text instead. `source` is only used for
+/// messages.
+fn read_lines(
+    reader: impl BufRead,
+    source: &str,
+    label: Option<&str>,
+    options: &Options,
+) -> Vec<Entry> {
+    let start = std::time::Instant::now();
+    let mut entries = Vec::new();
+    let extract = options.extract.as_ref().map(|e| {
+        Regex::new(e).unwrap_or_else(|e| {
+            eprintln!("error parsing extract pattern: {}", e);
+            std::process::exit(11);
+        })
+    });
+
+    for (i, line) in reader.split(b'\n').enumerate() {
+        let line = line.unwrap_or_else(|_| panic!("IO error reading {}", source));
+        let ls = decode(
+            line,
+            &format!("line {} of {}", i + 1, source),
+            options.strict_encoding,
+        );
+        let ls = if i == 0 {
+            ls.trim_start_matches('\u{feff}')
+        } else {
+            &ls
+        };
+        let ls = ls.trim_end_matches('\r').to_owned();
+        let (_, duration) = split_key(&ls);
+        let (date, duration) = split_date(duration);
+        let (tags, duration) = split_tags(duration);
+        let duration = duration.as_str();
+        // nothing but a comment, these would only skew statistics
+        if duration.split('#').next().unwrap().trim().is_empty() && date.is_none() {
+            summary::count_line(true);
+            continue;
+        }
+        let d_line = if let Some(extract) = &extract {
+            extract_durations(extract, duration).unwrap_or_else(|e| {
+                eprintln!("cannot parse line {} of {}: {}", i + 1, source, e);
+                std::process::exit(47);
+            })
+        } else if options.scan {
+            Duration::scan(duration)
+        } else if options.lenient {
+            match lenient_duration(duration) {
+                Ok(Some(d)) => d,
+                // just words
+                Ok(None) => {
+                    summary::count_line(true);
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("warning: skipped line {} of {}: {}", i + 1, source, e);
+                    summary::count_line(true);
+                    continue;
+                }
+            }
+        } else {
+            check_typos(duration, &format!("line {} of {}", i + 1, source)).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(38);
+            });
+            if let Err(e) = check_limits(duration) {
+                eprintln!("cannot parse line {} of {}: {}", i + 1, source, e);
+                std::process::exit(46);
+            }
+            Duration::from_str(duration).unwrap_or_else(|| panic!("cannot parse {:?}", &ls))
         };
+        let label = match (label, annotate::strip(&ls).unwrap_or("").split_once('#')) {
+            (Some(label), _) => label.to_owned(),
+            (None, Some((_, comment))) if !comment.trim().is_empty() => comment.trim().to_owned(),
+            _ => "(no label)".to_owned(),
+        };
+        entries.push(Entry {
+            label,
+            description: ls.clone(),
+            start: date.map(|d| d.and_time(NaiveTime::MIN)),
+            duration: d_line,
+            tags,
+        });
+        summary::count_line(false);
+    }
 
-        let line = line.split('#').next().unwrap();
+    summary::add_parsing_time(start.elapsed());
+    verbose!("entries from {}: {}", source, entries.len());
+    entries
+}
 
-        if !LINE_PATTERN.is_match(line) {
-            return None;
+/// Decodes UTF-8, replacing invalid sequences with a warning unless `strict`. `source` is only
+/// used for messages.
+fn decode(bytes: Vec<u8>, source: &str, strict: bool) -> String {
+    String::from_utf8(bytes).unwrap_or_else(|e| {
+        if strict {
+            panic!("invalid UTF-8 in {}", source);
         }
+        eprintln!("warning: invalid UTF-8 in {} replaced", source);
+        String::from_utf8_lossy(e.as_bytes()).into_owned()
+    })
+}
 
-        for caps in DURATION_COMPOSITE_PATTERN.captures_iter(line) {
-            let operator_function = match &caps["sign"] {
-                "+" => Duration::checked_add,
-                "-" => Duration::checked_sub,
-                _ => unreachable!(),
-            };
-            debug_println!("outer: {:?}", &caps);
-
-            for inner_caps in DURATION_PATTERN.captures_iter(&caps["duration"]) {
-                debug_println!("inner: {:?}", &inner_caps);
-                let count = i64::from_str(&inner_caps["count"]).unwrap();
-                duration = match Self::token_to_duration(count, &inner_caps["unit"]) {
-                    Some(d) => match operator_function(&duration, &d) {
-                        Some(dd) => dd,
-                        None => d,
-                    },
-                    None => duration,
-                };
-
-                debug_println!(" {:#?} duration", duration);
+/// Reads a file, see `decode`.
+fn read_file(path: &str, strict: bool) -> String {
+    let bytes = std::fs::read(path).unwrap_or_else(|e| {
+        eprintln!("cannot read {}: {}", path, e);
+        std::process::exit(7);
+    });
+
+    decode(bytes, path, strict)
+}
+
+/// Reads the entries of a file, depending on its extension an iCalendar (`.ics`), time tracker
+/// CSV (`.csv`) or org (`.org`) file, or duration lines labelled with their comment otherwise.
+fn file_entries(path: &str, options: &Options) -> Vec<Entry> {
+    let _timer = log::Timer::start(format!("reading {}", path));
+    let read = || read_file(path, options.strict_encoding);
+    let extension = std::path::Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase());
+
+    match extension.as_deref() {
+        Some("ics") => ics::events(&read()),
+        Some("csv") => csv::entries(&read()).unwrap_or_else(|e| {
+            eprintln!("cannot import {}: {}", path, e);
+            std::process::exit(7);
+        }),
+        Some("org") => org::entries(&read(), Local::now().naive_local()),
+        _ => {
+            #[cfg(feature = "mmap")]
+            if let Some(map) = map_large_file(path) {
+                return read_lines(map.bytes(), path, None, options);
             }
+            read_lines(read().as_bytes(), path, None, options)
+        }
+    }
+}
+
+/// Maps files of duration lines from `mmap::THRESHOLD` on, which are then decoded line by line
+/// rather than read and decoded as a whole.
+#[cfg(feature = "mmap")]
+fn map_large_file(path: &str) -> Option<mmap::Map> {
+    let map = mmap::Map::open_large(path, mmap::THRESHOLD).unwrap_or_else(|e| {
+        eprintln!("cannot read {}: {}", path, e);
+        std::process::exit(7);
+    });
+    if map.is_some() {
+        verbose!("mapped {} into memory", path);
+    }
+    map
+}
+
+/// Prints the difference between the two files following `diff`, in total or per group.
+fn diff(exe: &str, options: &Options, out: &mut Output) {
+    if options.free.len() != 3 {
+        eprintln!("diff requires two files");
+        eprintln!();
+        print_usage_and_exit(exe, 10);
+    }
+
+    let (from, to) = date_range(exe, options);
+    let report = |path: &String| {
+        file_entries(path, options)
+            .iter()
+            .filter(|e| e.in_date_range(from, to))
+            .collect::<Report>()
+    };
+    let (a, b) = (report(&options.free[1]), report(&options.free[2]));
+    let names = (options.free[1].clone(), options.free[2].clone());
+
+    let mut diff = if options.group {
+        Diff::groups(names, &a, &b)
+    } else {
+        Diff::totals(names, &a, &b)
+    };
+    if options.stable {
+        diff.sort();
+    }
+    out.print_all(DisplayableDiff(&diff, options.style));
+}
+
+/// Waits for the duration of the remaining arguments, showing the time left on a terminal.
+/// Notifies and rings the bell when done if asked to.
+fn countdown(exe: &str, options: &Options, out: &mut Output) {
+    let expression = options.free[1..].join(" ");
+    let d = Duration::from_str(&expression).unwrap_or_else(|| {
+        eprintln!("cannot parse {:?} as duration", expression);
+        eprintln!();
+        print_usage_and_exit(exe, 22);
+    });
+    let end = std::time::Instant::now() + d.to_std().unwrap_or_default();
+    let terminal = io::stdout().is_terminal();
+
+    loop {
+        let left = end.saturating_duration_since(std::time::Instant::now());
+        if left.is_zero() {
+            break;
         }
+        if terminal {
+            // round up, so the last second shows 1s rather than 0s
+            let seconds = left.as_secs() as i64 + i64::from(left.subsec_nanos() > 0);
+            let shown = Duration::seconds(seconds);
+            print!("\r\x1b[K{}", DisplayableDuration(shown, options.style));
+            io::Write::flush(&mut io::stdout()).unwrap();
+        }
+        std::thread::sleep(left.min(std::time::Duration::from_secs(1)));
+    }
 
-        Some(duration)
+    let done = format!(
+        "{}{}",
+        options.total_prefix,
+        DisplayableDuration(d, options.style)
+    );
+    if terminal {
+        print!("\r\x1b[K");
     }
+    out.println_all(done);
+    out.flush();
+
+    if options.bell {
+        print!("\x07");
+        io::Write::flush(&mut io::stdout()).unwrap();
+    }
+    if options.notify {
+        let body = format!(
+            "Countdown of {} finished",
+            DisplayableDuration(d, options.style)
+        );
+        if let Err(e) = notify::notify("duration-calculator-rs", &body) {
+            eprintln!("error sending notification: {}", e);
+        }
+    }
+}
 
-    fn token_to_duration(count: i64, unit: &str) -> Option<Duration> {
-        match unit {
-            "y" => Some(Duration::days(365 * count)),
-            "d" => Some(Duration::days(count)),
-            "h" => Some(Duration::hours(count)),
-            "m" => Some(Duration::minutes(count)),
-            "s" => Some(Duration::seconds(count)),
-            _ => None,
+/// The sums of keyed accumulator lines like `mon=8h 15m` per key, in order of appearance.
+fn accumulators<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Vec<(String, Duration)> {
+    let mut sums: Vec<(String, Duration)> = Vec::new();
+    for e in entries {
+        let Some(key) = split_key(&e.description).0 else {
+            continue;
+        };
+        match sums.iter_mut().find(|(k, _)| k == key) {
+            Some((_, sum)) => *sum = sum.saturated_add(&e.duration),
+            None => sums.push((key.to_owned(), e.duration)),
         }
     }
+    sums
+}
+
+/// Turns a prefix like `"total "` into a label for reports, or returns `default` if it is empty.
+fn label_or<'a>(prefix: &'a str, default: &'a str) -> &'a str {
+    match prefix.trim() {
+        "" => default,
+        label => label,
+    }
+}
+
+/// Reads the entries of the `--ics`, `--csv`, `--org` and `--file` files and the `--timew` export
+/// on stdin, honouring the `--ics-summary` and date filters. Also returns the subtotal of each
+/// `--file`.
+fn imported_entries(
+    exe: &str,
+    options: &Options,
+    (from, to): (Option<NaiveDate>, Option<NaiveDate>),
+) -> (Vec<Entry>, Report) {
+    let summary = options.ics_summary.as_ref().map(|s| {
+        Regex::new(s).unwrap_or_else(|e| {
+            eprintln!("error parsing summary pattern: {}", e);
+            eprintln!();
+            print_usage_and_exit(exe, 7);
+        })
+    });
+    let filter = |e: &Entry| {
+        summary.as_ref().is_none_or(|r| r.is_match(&e.description))
+            && e.in_date_range(from, to)
+            && options.tag.as_ref().is_none_or(|t| e.tags.contains(t))
+    };
+
+    let read = |path: &String| read_file(path, options.strict_encoding);
+
+    let mut entries = Vec::new();
+
+    if let Some(path) = &options.ics {
+        entries.extend(ics::events(&read(path)));
+    }
+
+    if let Some(path) = &options.csv {
+        entries.extend(csv::entries(&read(path)).unwrap_or_else(|e| {
+            eprintln!("cannot import {}: {}", path, e);
+            std::process::exit(7);
+        }));
+    }
+
+    if let Some(path) = &options.org {
+        entries.extend(org::entries(&read(path), Local::now().naive_local()));
+    }
+
+    if options.timew {
+        let mut content = Vec::new();
+        io::stdin()
+            .read_to_end(&mut content)
+            .unwrap_or_else(|_| panic!("IO error reading stdin"));
+        let content = decode(content, "stdin", options.strict_encoding);
+        entries.extend(
+            timew::entries(&content, chrono::Utc::now().naive_utc()).unwrap_or_else(|e| {
+                eprintln!("cannot import timewarrior export: {}", e);
+                std::process::exit(7);
+            }),
+        );
+    }
+
+    let mut files = Report::default();
+
+    let paths = options.files.iter().flat_map(|pattern| {
+        glob::expand(pattern).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(7);
+        })
+    });
+
+    for path in paths {
+        let mut file = file_entries(&path, options);
+        file.retain(filter);
+        files.add(&path, file.iter().collect::<Report>().total());
+        entries.extend(file);
+    }
+
+    entries.retain(filter);
+    debug!("entries: {:#?}", entries);
+
+    (entries, files)
+}
+
+/// Parses `--from-date` and `--to-date` or a shortcut like `--last-week`.
+fn date_range(exe: &str, options: &Options) -> (Option<NaiveDate>, Option<NaiveDate>) {
+    if let Some(shortcut) = &options.date_shortcut {
+        if options.from_date.is_some() || options.to_date.is_some() {
+            eprintln!(
+                "--{} cannot be combined with --from-date or --to-date",
+                shortcut
+            );
+            eprintln!();
+            print_usage_and_exit(exe, 8);
+        }
+
+        let (from, to) = shortcut_range(shortcut, Local::now().date_naive()).unwrap();
+        return (Some(from), Some(to));
+    }
+
+    let parse = |date: &Option<String>| {
+        date.as_ref().map(|d| {
+            NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap_or_else(|_| {
+                eprintln!("cannot parse {:?} as date", d);
+                eprintln!();
+                print_usage_and_exit(exe, 8);
+            })
+        })
+    };
+
+    (parse(&options.from_date), parse(&options.to_date))
+}
+
+/// Returns the first and last day of a date range shortcut, relative to `today`. Weeks start on
+/// Monday.
+fn shortcut_range(shortcut: &str, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let first_of_month = today.with_day(1)?;
+    let first_of_last_month = (first_of_month - Duration::days(1)).with_day(1)?;
+
+    match shortcut {
+        "today" => Some((today, today)),
+        "yesterday" => Some((today - Duration::days(1), today - Duration::days(1))),
+        "this-week" => Some((monday, monday + Duration::days(6))),
+        "last-week" => Some((monday - Duration::days(7), monday - Duration::days(1))),
+        "this-month" => Some((
+            first_of_month,
+            first_of_month.checked_add_months(chrono::Months::new(1))? - Duration::days(1),
+        )),
+        "last-month" => Some((first_of_last_month, first_of_month - Duration::days(1))),
+        _ => None,
+    }
+}
+
+/// Prints the working time between the two timestamps following `work-hours`.
+fn work_hours(exe: &str, options: &Options, out: &mut Output) {
+    let schedule = schedule(exe, options);
+
+    let now = Local::now().naive_local();
+    let timestamps: Vec<NaiveDateTime> = options.free[1..]
+        .iter()
+        .map(|a| {
+            parse_datetime(a, now).unwrap_or_else(|| {
+                eprintln!("cannot parse {:?} as timestamp", a);
+                eprintln!();
+                print_usage_and_exit(exe, 6);
+            })
+        })
+        .collect();
+
+    if timestamps.len() != 2 {
+        eprintln!("work-hours requires a start and an end timestamp");
+        eprintln!();
+        print_usage_and_exit(exe, 6);
+    }
+
+    let d = schedule.working_time(timestamps[0], timestamps[1]);
+    out.println_all(format_args!(
+        "{}{}",
+        options.total_prefix,
+        DisplayableDuration(d, options.style)
+    ));
+}
+
+/// Schedules `tasks` one after another from `start`, returning start and end of each. Ends
+/// saturate at the latest representable timestamp.
+fn timeline<'a, I: IntoIterator<Item = &'a Entry>>(
+    start: NaiveDateTime,
+    tasks: I,
+) -> Vec<(NaiveDateTime, NaiveDateTime, &'a Entry)> {
+    let mut at = start;
+    tasks
+        .into_iter()
+        .map(|e| {
+            let end = at
+                .checked_add_signed(e.duration)
+                .unwrap_or(NaiveDateTime::MAX);
+            let task = (at, end, e);
+            at = end;
+            task
+        })
+        .collect()
+}
+
+/// The `--schedule` option, Mon-Fri 09:00-17:00 by default.
+fn schedule(exe: &str, options: &Options) -> Schedule {
+    match &options.schedule {
+        Some(s) => Schedule::from_str(s).unwrap_or_else(|e| {
+            eprintln!("error parsing work schedule: {}", e);
+            eprintln!();
+            print_usage_and_exit(exe, 5);
+        }),
+        None => Schedule::default(),
+    }
+}
+
+const PACKAGE: &str = env!("CARGO_PKG_NAME");
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// What this build supports, for wrapper tools to check instead of parsing the usage: the
+/// version, units including the custom ones, functions, output formats, subcommands, compiled
+/// in features and the long names of the options.
+fn capabilities() -> json::Value {
+    use json::Value;
+
+    let strings =
+        |names: &[&str]| Value::Array(names.iter().map(|n| Value::String(n.to_string())).collect());
+    let unit = |(name, d): &(String, Duration)| {
+        Value::Object(vec![
+            ("name".to_owned(), Value::String(name.clone())),
+            (
+                "seconds".to_owned(),
+                Value::Number(d.num_milliseconds() as f64 / 1000.0),
+            ),
+        ])
+    };
+    let custom_units: Vec<Value> = custom_unit_durations().iter().map(unit).collect();
+    let mut subcommands = vec![
+        "work-hours",
+        "diff",
+        "countdown",
+        "state",
+        "examples",
+        "selftest",
+        "alias",
+    ];
+    let mut features = Vec::new();
+    if cfg!(feature = "tui") {
+        subcommands.push("tui");
+        features.push("tui");
+    }
+    if cfg!(feature = "clipboard") {
+        features.push("clipboard");
+    }
+    if cfg!(feature = "mmap") {
+        features.push("mmap");
+    }
+
+    Value::Object(vec![
+        ("name".to_owned(), Value::String(PACKAGE.to_owned())),
+        ("version".to_owned(), Value::String(VERSION.to_owned())),
+        ("units".to_owned(), strings(&UNITS.map(|(name, _)| name))),
+        ("month_units".to_owned(), strings(&["M"])),
+        ("custom_units".to_owned(), Value::Array(custom_units)),
+        (
+            "functions".to_owned(),
+            strings(&FUNCTIONS.map(|(name, _, _)| name)),
+        ),
+        ("formats".to_owned(), strings(&Format::NAMES)),
+        ("subcommands".to_owned(), strings(&subcommands)),
+        ("features".to_owned(), strings(&features)),
+        ("options".to_owned(), strings(&options::long_names())),
+    ])
 }
 
 #[cfg(test)]
@@ -287,77 +1683,156 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_token_to_duration() {
-        let cases = vec![
-            (5, "y", Duration::days(365 * 5)),
-            (2, "d", Duration::days(2)),
-            (3, "h", Duration::hours(3)),
-            (30, "m", Duration::minutes(30)),
-            (10, "s", Duration::seconds(10)),
-            (0, "y", Duration::zero()),
-        ];
+    fn test_read_lines() {
+        let input = "\u{feff}2025-01-06 2h\r\n\r\n# lunch\n0m\n30m # lunch\r\r\n-5m\n";
+        let strict = Options {
+            strict_encoding: true,
+            ..Default::default()
+        };
+        let entries = read_lines(input.as_bytes(), "test", None, &strict);
 
-        for (count, unit, expected) in cases {
-            let result = Duration::token_to_duration(count, unit);
-            assert_eq!(result, Some(expected));
-        }
+        assert_eq!(entries.len(), 4);
+        assert_eq!(
+            entries[0].start,
+            NaiveDate::from_ymd_opt(2025, 1, 6)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+        );
+        assert_eq!(entries[0].duration, Duration::hours(2));
+        assert_eq!(entries[1].label, "(no label)");
+        assert_eq!(entries[1].duration, Duration::zero());
+        assert_eq!(entries[2].label, "lunch");
+        assert_eq!(entries[2].duration, Duration::minutes(30));
+        assert_eq!(entries[3].label, "(no label)");
+        assert_eq!(entries[3].duration, -Duration::minutes(5));
+
+        let input = "2h # dev # => 2h 00m 00s\n1h # => 1h 00m 00s\n# => total 3h 00m 00s\n";
+        let entries = read_lines(input.as_bytes(), "test", None, &Options::default());
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].label, "dev");
+        assert_eq!(entries[1].label, "(no label)");
+
+        let input = b"1h # caf\xe9\n";
+        let entries = read_lines(&input[..], "test", None, &Options::default());
+        assert_eq!(entries[0].label, "caf\u{fffd}");
+        assert_eq!(entries[0].duration, Duration::hours(1));
+    }
+
+    #[test]
+    fn test_timeline() {
+        let input = "2h # write\n45m # review\n";
+        let tasks = read_lines(input.as_bytes(), "test", None, &Options::default());
+        let start = NaiveDate::from_ymd_opt(2025, 1, 6)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let timeline = timeline(start, &tasks);
+
+        assert_eq!(timeline.len(), 2);
+        assert_eq!(timeline[0].1, start + Duration::hours(2));
+        assert_eq!(timeline[1].0, timeline[0].1);
+        assert_eq!(timeline[1].1, start + Duration::minutes(165));
+        assert_eq!(timeline[1].2.label, "review");
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("1h 30m 00s"), "'1h 30m 00s'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
     }
 
     #[test]
-    fn test_from_str() {
+    fn test_shortcut_range() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        // a Wednesday
+        let today = date(2025, 1, 8);
+
         let cases = vec![
-            ("", Duration::zero()),
-            (
-                "3d 20h 10m 15s",
-                Duration::days(3)
-                    + Duration::hours(20)
-                    + Duration::minutes(10)
-                    + Duration::seconds(15),
-            ),
-            ("+2d 5h", Duration::days(2) + Duration::hours(5)),
-            (
-                "-1y 3h + 40m",
-                Duration::days(-365) - Duration::hours(3) + Duration::minutes(40),
-            ),
-            ("+3h-2m", Duration::hours(3) - Duration::minutes(2)),
-            ("2d 5h # Comment", Duration::days(2) + Duration::hours(5)),
-            ("-2d 5h # Comment", -Duration::days(2) - Duration::hours(5)),
+            ("today", (date(2025, 1, 8), date(2025, 1, 8))),
+            ("yesterday", (date(2025, 1, 7), date(2025, 1, 7))),
+            ("this-week", (date(2025, 1, 6), date(2025, 1, 12))),
+            ("last-week", (date(2024, 12, 30), date(2025, 1, 5))),
+            ("this-month", (date(2025, 1, 1), date(2025, 1, 31))),
+            ("last-month", (date(2024, 12, 1), date(2024, 12, 31))),
         ];
 
-        for (input, expected) in cases {
-            let result = Duration::from_str(input).unwrap();
-            assert_eq!(result, expected);
+        for (shortcut, expected) in cases {
+            assert_eq!(
+                shortcut_range(shortcut, today),
+                Some(expected),
+                "{}",
+                shortcut
+            );
         }
+        assert_eq!(shortcut_range("next-year", today), None);
     }
 
     #[test]
-    fn test_saturated_add_and_sub() {
-        let cases = vec![
-            (
-                Duration::days(5),
-                Duration::days(3),
-                Duration::days(8),
-                Duration::days(2),
-            ),
-            (
-                Duration::hours(5),
-                Duration::hours(3),
-                Duration::hours(8),
-                Duration::hours(2),
-            ),
-            (
-                Duration::minutes(30),
-                Duration::minutes(20),
-                Duration::minutes(50),
-                Duration::minutes(10),
-            ),
+    fn test_accumulators() {
+        let entry = |line: &str| Entry {
+            label: "stdin".to_owned(),
+            description: line.to_owned(),
+            start: None,
+            duration: Duration::from_str(&expression(line)).unwrap(),
+            tags: Vec::new(),
+        };
+        let entries = [
+            entry("mon=8h 15m"),
+            entry("tue = 7h"),
+            entry("mon=-30m"),
+            entry("2h"),
         ];
+        let sums = accumulators(&entries);
+        assert_eq!(
+            sums,
+            vec![
+                ("mon".to_owned(), Duration::minutes(465)),
+                ("tue".to_owned(), Duration::hours(7)),
+            ]
+        );
+
+        assert_eq!(
+            expand_keys("mon + tue - 16h", &sums),
+            "27900000ms + 25200000ms - 16h"
+        );
+        let negative = [("over".to_owned(), Duration::minutes(-30))];
+        assert_eq!(
+            Duration::from_str(&expand_keys("2h - over + over", &negative)),
+            Some(Duration::hours(2))
+        );
+        assert_eq!(expand_keys("2h daily for 1w", &sums), "2h daily for 1w");
+    }
+
+    #[test]
+    fn test_overage() {
+        let durations = [11, 8, 10, 13, -12].map(Duration::hours);
+        assert_eq!(
+            overage(durations.into_iter(), Duration::hours(10)),
+            Duration::hours(4)
+        );
+        assert_eq!(
+            overage(durations.into_iter(), Duration::hours(20)),
+            Duration::zero()
+        );
+    }
+
+    #[test]
+    fn test_capabilities_features() {
+        let capabilities = capabilities();
+        let features: Vec<&str> = capabilities
+            .get("features")
+            .and_then(|f| f.as_array())
+            .unwrap()
+            .iter()
+            .filter_map(|f| f.as_str())
+            .collect();
 
-        for (a, b, expected_add, expected_sub) in cases {
-            let result_add = a.saturated_add(&b);
-            let result_sub = a.saturated_sub(&b);
-            assert_eq!(result_add, expected_add);
-            assert_eq!(result_sub, expected_sub);
+        for (feature, enabled) in [
+            ("tui", cfg!(feature = "tui")),
+            ("clipboard", cfg!(feature = "clipboard")),
+            ("mmap", cfg!(feature = "mmap")),
+        ] {
+            assert_eq!(features.contains(&feature), enabled, "{}", feature);
         }
     }
 }
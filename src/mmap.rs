@@ -0,0 +1,88 @@
+//! Read-only memory maps, so `--file` reads the lines of multi-gigabyte files straight from the
+//! page cache instead of copying the whole file into memory first. Only built with the `mmap`
+//! feature.
+
+use std::fs::File;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::{ptr, slice};
+
+/// Files of at least this size are mapped rather than read.
+pub const THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// A file mapped into memory, unmapped when dropped. The file must not be truncated meanwhile.
+pub struct Map {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+impl Map {
+    /// Maps `path` if it is a regular file of at least `min_len` (and at least one) bytes, `None`
+    /// otherwise.
+    pub fn open_large(path: &str, min_len: u64) -> io::Result<Option<Map>> {
+        let file = File::open(path)?;
+        let metadata = file.metadata()?;
+        if !metadata.is_file() || metadata.len() < min_len.max(1) {
+            return Ok(None);
+        }
+        let len = usize::try_from(metadata.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "file too large to map"))?;
+
+        // SAFETY: a private read-only mapping of an open file, len is its non-zero size
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: only a hint about the mapping created above, failing is harmless
+        unsafe {
+            libc::madvise(ptr, len, libc::MADV_SEQUENTIAL);
+        }
+
+        Ok(Some(Map { ptr, len }))
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        // SAFETY: the mapping is readable and len bytes long until dropped
+        unsafe { slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+impl Drop for Map {
+    fn drop(&mut self) {
+        // SAFETY: unmaps the mapping created in open_large, which is no longer borrowed
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_large() {
+        let path = std::env::temp_dir().join(format!("dc-mmap-{}.txt", std::process::id()));
+        let content = "30m # review\n".repeat(100);
+        std::fs::write(&path, &content).unwrap();
+        let path_str = path.to_str().unwrap();
+        assert!(Map::open_large(path_str, 1301).unwrap().is_none());
+        let map = Map::open_large(path_str, 1300).unwrap().unwrap();
+        assert_eq!(map.bytes(), content.as_bytes());
+        drop(map);
+
+        std::fs::write(&path, "").unwrap();
+        assert!(Map::open_large(path_str, 0).unwrap().is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
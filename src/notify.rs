@@ -0,0 +1,48 @@
+//! Desktop notifications through `notify-send` or, on macOS, `osascript`.
+
+use std::io;
+use std::process::Command;
+
+/// Shows a notification with `summary` and `body` using the first tool found.
+pub fn notify(summary: &str, body: &str) -> io::Result<()> {
+    let script = format!(
+        "display notification {} with title {}",
+        apple_script_string(body),
+        apple_script_string(summary)
+    );
+    let commands: [(&str, Vec<&str>); 2] = [
+        ("notify-send", vec![summary, body]),
+        ("osascript", vec!["-e", &script]),
+    ];
+
+    for (tool, args) in commands {
+        match Command::new(tool).args(args).status() {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => return Err(io::Error::other(format!("{} failed: {}", tool, status))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "neither notify-send nor osascript found",
+    ))
+}
+
+fn apple_script_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apple_script_string() {
+        assert_eq!(
+            apple_script_string(r#"say "hi" \o/"#),
+            r#""say \"hi\" \\o/""#
+        );
+    }
+}
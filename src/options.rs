@@ -0,0 +1,720 @@
+//! Command line option parsing shared by all modes.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::log::Level;
+pub use duration_calculator_rs::style::{CompactStyle, NegativeStyle, Style};
+pub use duration_calculator_rs::Policy;
+
+/// Options taking a value: short name (may be empty), long name, description for error messages
+/// and the errorlevel used when the value is missing.
+const VALUE_OPTIONS: &[(&str, &str, &str, i32)] = &[
+    ("-t", "--total-prefix", "total summary prefix", 3),
+    ("-s", "--stdin-sum-prefix", "stdin_total summary prefix", 4),
+    ("-w", "--schedule", "work schedule", 5),
+    ("", "--ics", "iCalendar file", 7),
+    ("", "--csv", "CSV file", 7),
+    ("", "--org", "org file", 7),
+    ("-f", "--file", "file", 7),
+    ("", "--ics-summary", "summary pattern", 7),
+    ("", "--from-date", "from date", 8),
+    ("", "--to-date", "to date", 8),
+    ("-b", "--by", "period", 9),
+    ("-x", "--extract", "extract pattern", 11),
+    ("", "--histogram", "histogram bucket width", 12),
+    ("", "--percentiles", "percentiles", 12),
+    ("-a", "--aggregate", "aggregate", 13),
+    ("", "--timeline", "timeline start", 14),
+    ("", "--burn-rate", "burn rate", 15),
+    ("", "--repeat", "repeat count", 16),
+    ("", "--baseline", "baseline file", 17),
+    ("", "--serve", "socket path", 18),
+    ("", "--exec", "command", 23),
+    ("-o", "--output", "output file", 24),
+    ("", "--month-unit", "month unit", 25),
+    ("", "--bare-unit", "bare unit", 26),
+    ("", "--compact-style", "compact style", 27),
+    ("", "--hour-width", "hour width", 28),
+    ("", "--negative-style", "negative style", 29),
+    ("", "--label-map", "label map file", 30),
+    ("", "--tag", "tag", 31),
+    ("", "--rate", "hourly rate", 32),
+    ("", "--currency", "currency", 32),
+    ("", "--increment", "billing increment", 32),
+    ("", "--rounding", "rounding", 32),
+    ("", "--headcount", "headcount", 33),
+    ("", "--scale", "scale factor", 34),
+    ("", "--cap-per-line", "cap per line", 42),
+    ("", "--window", "window", 43),
+    ("", "--config", "config file", 37),
+    ("", "--dup-units", "duplicate unit policy", 38),
+    ("", "--sane", "sanity policy", 38),
+    ("", "--http", "listen address", 18),
+    ("", "--prom-name", "metric name", 19),
+    ("", "--print-var", "variable name", 39),
+    ("", "--batch", "batch file", 40),
+    ("", "--pairs", "pairs file", 45),
+    ("", "--pairs-op", "pairs op", 45),
+    ("", "--delimiter", "delimiter", 45),
+    ("", "--prom-labels", "metric labels", 19),
+    ("", "--tz", "time zone", 49),
+    ("", "--state", "state file", 50),
+];
+
+/// Options without a value: short name (may be empty) and long name.
+const FLAG_OPTIONS: &[(&str, &str)] = &[
+    ("-c", "--compact"),
+    ("-g", "--group"),
+    ("", "--timew"),
+    ("", "--markdown"),
+    ("", "--html"),
+    ("", "--chart"),
+    ("", "--rounding-report"),
+    ("", "--sparkline"),
+    ("", "--today"),
+    ("", "--yesterday"),
+    ("", "--this-week"),
+    ("", "--last-week"),
+    ("", "--this-month"),
+    ("", "--last-month"),
+    ("", "--strict-encoding"),
+    ("", "--scan"),
+    ("", "--prom"),
+    ("", "--json"),
+    ("", "--stats"),
+    ("", "--business-days"),
+    ("", "--no-update"),
+    ("", "--clipboard-in"),
+    ("", "--clipboard-out"),
+    ("", "--notify"),
+    ("", "--bell"),
+    ("", "--seconds"),
+    ("", "--append"),
+    ("", "--no-padding"),
+    ("", "--rollup"),
+    ("", "--by-tag"),
+    ("", "--trace-json"),
+    ("", "--lsp-like"),
+    ("", "--annotate"),
+    ("", "--check"),
+    ("", "--no-calendar-units"),
+    ("", "--lenient"),
+    ("", "--wall-clock"),
+    ("", "--echo"),
+    ("", "--stable"),
+    ("-v", "--verbose"),
+    ("", "--debug"),
+    ("", "--summary"),
+    ("", "--version"),
+    ("", "--capabilities"),
+];
+
+/// The long names of all options, for `--capabilities`.
+pub fn long_names() -> Vec<&'static str> {
+    let values = VALUE_OPTIONS.iter().map(|(_, long, _, _)| *long);
+    values
+        .chain(FLAG_OPTIONS.iter().map(|(_, long)| *long))
+        .collect()
+}
+
+/// How the results are printed.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// The sums line by line, as prefixed plain text
+    #[default]
+    Text,
+    /// A GitHub flavored Markdown table with the groups, stdin, arguments and total
+    Markdown,
+    /// An HTML page with the same table as `Markdown`
+    Html,
+    /// Prometheus exposition format gauges of the total and the same rows as `Markdown`
+    Prometheus,
+    /// Shell variable assignments of the total, see `--print-var`
+    Shell,
+    /// A JSON object with the same rows as `Markdown`
+    Json,
+}
+
+impl Format {
+    /// The names of the formats as `format=` of `--output` takes them
+    pub const NAMES: [&'static str; 6] = ["text", "markdown", "html", "prom", "json", "shell"];
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "markdown" => Ok(Format::Markdown),
+            "html" => Ok(Format::Html),
+            "prom" => Ok(Format::Prometheus),
+            "json" => Ok(Format::Json),
+            "shell" => Ok(Format::Shell),
+            _ => Err(format!(
+                "invalid format {:?}, expected text, markdown, html, prom, json or shell",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub style: Style,
+    /// Print subtotals per label of the imported entries
+    pub group: bool,
+    /// Print subtotals per tag like `+billable` of dated stdin lines and imported entries
+    pub by_tag: bool,
+    /// Only count stdin lines and imported entries with this tag
+    pub tag: Option<String>,
+    /// Nest the groups by the `/`-separated parts of their labels, implies `group`
+    pub rollup: bool,
+    /// File mapping the labels to names for `group`, see `LabelMap`
+    pub label_map: Option<String>,
+    pub format: Format,
+    /// Print subtotals per `day`, `week` or `month` of dated stdin lines and imported entries
+    pub by: Option<String>,
+    /// Add a bar chart to the report
+    pub chart: bool,
+    /// Add a sparkline of the period totals to `by` and `window`
+    pub sparkline: bool,
+    pub total_prefix: String,
+    pub stdin_total_prefix: String,
+    /// Work schedule for the `work-hours` mode, see `Schedule::from_str`
+    pub schedule: Option<String>,
+    /// iCalendar file whose event durations are added to the arguments
+    pub ics: Option<String>,
+    /// Time tracker CSV export whose durations are added to the arguments
+    pub csv: Option<String>,
+    /// org file whose clocked time is added to the arguments
+    pub org: Option<String>,
+    /// Files or glob patterns whose entries are added to the arguments, see `file_entries`. May be
+    /// given more than once.
+    pub files: Vec<String>,
+    /// Read a `timew export` from stdin instead of durations
+    pub timew: bool,
+    /// Only count imported entries whose summary or description matches this regular expression
+    pub ics_summary: Option<String>,
+    /// Only count dated lines and imported entries on or after this date
+    pub from_date: Option<String>,
+    /// Only count dated lines and imported entries on or before this date
+    pub to_date: Option<String>,
+    /// Start timestamp to schedule the lines and entries one after another from
+    pub timeline: Option<String>,
+    /// Metric name for `Format::Prometheus`
+    pub prom_name: Option<String>,
+    /// Constant labels like `job=timesheet,team=a` for `Format::Prometheus`
+    pub prom_labels: Option<String>,
+    /// Variable name prefix for `Format::Shell`
+    pub print_var: Option<String>,
+    /// File whose lines are evaluated one by one instead of summed up, `-` for stdin
+    pub batch: Option<String>,
+    /// File keeping accumulators, variables and `batch` results across runs, see `state`
+    pub state: Option<String>,
+    /// Put each line of `batch` in front of its result
+    pub echo: bool,
+    /// File with two durations per line combined by `pairs_op`, `-` for stdin, see `pairs`
+    pub pairs: Option<String>,
+    /// How the durations of `pairs` are combined: diff (default), sum, min or max
+    pub pairs_op: Option<String>,
+    /// What separates the durations of `pairs`, a tab by default
+    pub delimiter: Option<String>,
+    /// Add the expression in the clipboard to the arguments
+    pub clipboard_in: bool,
+    /// Copy the total to the clipboard
+    pub clipboard_out: bool,
+    /// `M` if an uppercase `M` unit means months instead of minutes
+    pub month_unit: Option<String>,
+    /// `s` or `ms` if lines that are just a number count in this unit
+    pub bare_unit: Option<String>,
+    /// Files to write the results to instead of stdout, or specs like `format=json,path=a.json`,
+    /// see `Output::open`
+    pub outputs: Vec<String>,
+    /// Append to the `outputs` rather than replacing them
+    pub append: bool,
+    /// Shell command to run with `{}` replaced by the total
+    pub exec: Option<String>,
+    /// Substitute the total in whole seconds for `exec`
+    pub seconds: bool,
+    /// Desktop notification when a countdown finishes
+    pub notify: bool,
+    /// Ring the terminal bell when a countdown finishes
+    pub bell: bool,
+    /// Unix domain socket to answer expressions on instead of calculating once
+    pub serve: Option<String>,
+    /// Address like `127.0.0.1:8080` to answer HTTP requests on instead of calculating once
+    pub http: Option<String>,
+    /// File with the total of the previous run to compare against
+    pub baseline: Option<String>,
+    /// Only compare against the baseline, don't store the new total
+    pub no_update: bool,
+    /// How many times the arguments count
+    pub repeat: Option<String>,
+    /// Factor like `1.2` to print the total scaled by as well, e.g. for contingency
+    pub scale: Option<String>,
+    /// Number of days like `7d` to print rolling totals of dated lines and entries for
+    pub window: Option<String>,
+    /// Most a single line or entry counts, the excess is reported separately
+    pub cap_per_line: Option<String>,
+    /// Configuration file with custom units, instead of the default one, see `config`
+    pub config: Option<String>,
+    /// Team size to divide the total, as effort, into calendar time by
+    pub headcount: Option<String>,
+    /// Hourly rate like `95.50` to bill the total at
+    pub rate: Option<String>,
+    /// Currency like `EUR` of `rate`
+    pub currency: Option<String>,
+    /// Duration like `15m` the billed durations are rounded to
+    pub increment: Option<String>,
+    /// How the billed durations are rounded: up (default), nearest or down
+    pub rounding: Option<String>,
+    /// Print the total with each line rounded by common billing policies, see `RoundingReport`
+    pub rounding_report: bool,
+    /// Rate like `6h/day` to project the completion of the total at
+    pub burn_rate: Option<String>,
+    /// Only work on the days of `schedule` for the burn rate
+    pub business_days: bool,
+    /// How lines and entries are reduced: sum (default), min, max or last
+    pub aggregate: Option<String>,
+    /// Print statistics over the single lines and entries instead of sums
+    pub stats: bool,
+    /// Bucket width of a histogram added to the statistics, implies `stats`
+    pub histogram: Option<String>,
+    /// Comma separated percentiles added to the statistics, implies `stats`
+    pub percentiles: Option<String>,
+    /// Echo stdin with the duration of each line appended as comment, and the total
+    pub annotate: bool,
+    /// Answer parse requests of editor plugins on stdin, see `editor`
+    pub lsp_like: bool,
+    /// Print how each line and the arguments are evaluated as JSON instead of the sums
+    pub trace_json: bool,
+    /// What happens to units used twice within a composite
+    pub dup_units: Policy,
+    /// What happens to components beyond their natural bound like `75m`
+    pub sane: Policy,
+    /// Reject `y`, `w`, `d` and months, whose real length varies
+    pub no_calendar_units: bool,
+    /// Subtract local timestamps as wall clock times, ignoring daylight saving time, see `clock`
+    pub wall_clock: bool,
+    /// Time zone of local timestamps instead of the one of the system, see `clock::set_time_zone`
+    pub tz: Option<String>,
+    /// Print diagnostics for the lines and the arguments instead of the sums, see `validate`
+    pub check: bool,
+    /// Print the version instead of the sums
+    pub version: bool,
+    /// Sort groups by label rather than by first appearance
+    pub stable: bool,
+    /// What is logged to stderr, see `log`
+    pub log_level: Level,
+    /// Print how many lines were read, parsed and skipped to stderr on exit, see `summary`
+    pub summary: bool,
+    /// Print the supported units, formats, subcommands and options as JSON instead of the sums
+    pub capabilities: bool,
+    /// Pick durations from free-form text lines instead of parsing whole lines
+    pub scan: bool,
+    /// Ignore the words before the first and after the last duration of a line, see
+    /// `lenient_duration`
+    pub lenient: bool,
+    /// Regular expression whose first capture group is parsed as duration, like `scan`
+    pub extract: Option<String>,
+    /// Fail on invalid UTF-8 instead of replacing it
+    pub strict_encoding: bool,
+    /// Date range shortcut instead of `from_date` and `to_date`, e.g. `last-week`
+    pub date_shortcut: Option<String>,
+    /// Everything that is not an option, i.e. the duration string or a mode and its operands
+    pub free: Vec<String>,
+}
+
+impl Options {
+    /// Parses the arguments (without the executable) into `Options`. Prints the usage and exits
+    /// for invalid arguments.
+    pub fn parse(exe: &str, args: Vec<String>) -> Options {
+        let mut options = Options::default();
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut open: Option<&(&str, &str, &str, i32)> = None;
+
+        for a in args {
+            if let Some(&(_, long, _, _)) = open {
+                if a.starts_with('-') && a != "-" {
+                    eprintln!("ambiguous value {} for {}", a, long);
+                    eprintln!();
+                    print_usage_and_exit(exe, 2);
+                }
+                open = None;
+                match long {
+                    "--total-prefix" => options.total_prefix = a + " ",
+                    "--stdin-sum-prefix" => options.stdin_total_prefix = a + " ",
+                    "--schedule" => options.schedule = Some(a),
+                    "--ics" => options.ics = Some(a),
+                    "--csv" => options.csv = Some(a),
+                    "--org" => options.org = Some(a),
+                    "--file" => options.files.push(a),
+                    "--ics-summary" => options.ics_summary = Some(a),
+                    "--from-date" => options.from_date = Some(a),
+                    "--to-date" => options.to_date = Some(a),
+                    "--by" => options.by = Some(a),
+                    "--extract" => options.extract = Some(a),
+                    "--histogram" => options.histogram = Some(a),
+                    "--aggregate" => options.aggregate = Some(a),
+                    "--timeline" => options.timeline = Some(a),
+                    "--burn-rate" => options.burn_rate = Some(a),
+                    "--repeat" => options.repeat = Some(a),
+                    "--baseline" => options.baseline = Some(a),
+                    "--serve" => options.serve = Some(a),
+                    "--exec" => options.exec = Some(a),
+                    "--output" => options.outputs.push(a),
+                    "--month-unit" => options.month_unit = Some(a),
+                    "--bare-unit" => options.bare_unit = Some(a),
+                    "--label-map" => options.label_map = Some(a),
+                    "--rate" => options.rate = Some(a),
+                    "--currency" => options.currency = Some(a),
+                    "--increment" => options.increment = Some(a),
+                    "--rounding" => options.rounding = Some(a),
+                    "--headcount" => options.headcount = Some(a),
+                    "--scale" => options.scale = Some(a),
+                    "--cap-per-line" => options.cap_per_line = Some(a),
+                    "--window" => options.window = Some(a),
+                    "--tz" => options.tz = Some(a),
+                    "--config" => options.config = Some(a),
+                    "--tag" => options.tag = Some(a.trim_start_matches('+').to_owned()),
+                    "--compact-style" => match CompactStyle::from_str(&a) {
+                        Ok(style) => {
+                            options.style.compact = true;
+                            options.style.compact_style = style;
+                        }
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            eprintln!();
+                            print_usage_and_exit(exe, 27);
+                        }
+                    },
+                    "--dup-units" | "--sane" => match Policy::from_str(&a) {
+                        Ok(policy) if long == "--sane" => options.sane = policy,
+                        Ok(policy) => options.dup_units = policy,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            eprintln!();
+                            print_usage_and_exit(exe, 38);
+                        }
+                    },
+                    "--negative-style" => match NegativeStyle::from_str(&a) {
+                        Ok(style) => options.style.negative_style = style,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            eprintln!();
+                            print_usage_and_exit(exe, 29);
+                        }
+                    },
+                    "--hour-width" => match u8::from_str(&a) {
+                        Ok(width) => options.style.hour_width = width,
+                        Err(_) => {
+                            eprintln!("invalid hour width {:?}", a);
+                            eprintln!();
+                            print_usage_and_exit(exe, 28);
+                        }
+                    },
+                    "--http" => options.http = Some(a),
+                    "--prom-name" => options.prom_name = Some(a),
+                    "--batch" => options.batch = Some(a),
+                    "--state" => options.state = Some(a),
+                    "--pairs" => options.pairs = Some(a),
+                    "--pairs-op" => options.pairs_op = Some(a),
+                    "--delimiter" => options.delimiter = Some(a),
+                    "--print-var" => {
+                        let valid = a.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                            && a.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                        if !valid {
+                            eprintln!("invalid variable name {:?}", a);
+                            eprintln!();
+                            print_usage_and_exit(exe, 39);
+                        }
+                        options.format = Format::Shell;
+                        options.print_var = Some(a);
+                    }
+                    "--prom-labels" => options.prom_labels = Some(a),
+                    "--percentiles" => options.percentiles = Some(a),
+                    _ => unreachable!(),
+                }
+                continue;
+            }
+
+            let value_option = VALUE_OPTIONS
+                .iter()
+                .find(|(short, long, _, _)| a == *long || (!short.is_empty() && a == *short));
+            let flag_option = FLAG_OPTIONS
+                .iter()
+                .find(|(short, long)| a == *long || (!short.is_empty() && a == *short));
+
+            let long = match (value_option, flag_option) {
+                (Some(o), _) => o.1,
+                (_, Some(o)) => o.1,
+                _ => {
+                    options.free.push(a);
+                    continue;
+                }
+            };
+
+            if !seen.insert(long) && long != "--file" && long != "--output" {
+                eprintln!("{} provided more than once", a);
+                eprintln!();
+                print_usage_and_exit(exe, 1);
+            }
+
+            match long {
+                "--compact" => options.style.compact = true,
+                "--group" => options.group = true,
+                "--timew" => options.timew = true,
+                "--markdown" => options.format = Format::Markdown,
+                "--html" => options.format = Format::Html,
+                "--prom" => options.format = Format::Prometheus,
+                "--json" => options.format = Format::Json,
+                "--chart" => options.chart = true,
+                "--rounding-report" => options.rounding_report = true,
+                "--sparkline" => options.sparkline = true,
+                "--strict-encoding" => options.strict_encoding = true,
+                "--scan" => options.scan = true,
+                "--stats" => options.stats = true,
+                "--business-days" => options.business_days = true,
+                "--no-update" => options.no_update = true,
+                "--clipboard-in" => options.clipboard_in = true,
+                "--clipboard-out" => options.clipboard_out = true,
+                "--notify" => options.notify = true,
+                "--bell" => options.bell = true,
+                "--seconds" => options.seconds = true,
+                "--append" => options.append = true,
+                "--no-padding" => options.style.unpadded = true,
+                "--by-tag" => options.by_tag = true,
+                "--trace-json" => options.trace_json = true,
+                "--lsp-like" => options.lsp_like = true,
+                "--annotate" => options.annotate = true,
+                "--check" => options.check = true,
+                "--echo" => options.echo = true,
+                "--version" => options.version = true,
+                "--stable" => options.stable = true,
+                "--verbose" => options.log_level = options.log_level.max(Level::Verbose),
+                "--debug" => options.log_level = Level::Debug,
+                "--summary" => options.summary = true,
+                "--capabilities" => options.capabilities = true,
+                "--no-calendar-units" => options.no_calendar_units = true,
+                "--lenient" => options.lenient = true,
+                "--wall-clock" => options.wall_clock = true,
+                "--rollup" => {
+                    options.group = true;
+                    options.rollup = true;
+                }
+                "--today" | "--yesterday" | "--this-week" | "--last-week" | "--this-month"
+                | "--last-month" => {
+                    if options.date_shortcut.is_some() {
+                        eprintln!("more than one date range provided");
+                        eprintln!();
+                        print_usage_and_exit(exe, 8);
+                    }
+                    options.date_shortcut = Some(long[2..].to_owned());
+                }
+                _ => open = value_option,
+            }
+        }
+
+        if let Some(&(_, _, description, errorlevel)) = open {
+            eprintln!("error parsing {}", description);
+            eprintln!();
+            print_usage_and_exit(exe, errorlevel);
+        }
+
+        if options.scan && options.extract.is_some() {
+            eprintln!("--scan and --extract cannot be combined");
+            eprintln!();
+            print_usage_and_exit(exe, 11);
+        }
+
+        options
+    }
+}
+
+pub fn print_usage_and_exit(exe: &str, errorlevel: i32) -> ! {
+    print_usage(exe);
+    std::process::exit(errorlevel);
+}
+
+fn print_usage(exe: &str) {
+    println!("Usage:");
+    println!();
+    println!("{} [Options] [Duration String]", exe);
+    println!("{} [Options] work-hours <Start> <End>", exe);
+    println!("{} [Options] diff <File A> <File B>", exe);
+    println!("{} [Options] countdown <Duration String>", exe);
+    println!(
+        "{} [Options] --state <File> state export|import [<JSON>]",
+        exe
+    );
+    println!("{} [Options] tui", exe);
+    println!("{} [Options] examples|selftest", exe);
+    println!(
+        "{} [Options] alias add <Name>=<Duration String>|list|rm <Name>",
+        exe
+    );
+    println!();
+    println!("where Options:");
+    println!("-c|--compact\tCompact output");
+    println!("--compact-style <units|colon|underscore|narrow>");
+    println!("\tCompact output like 0h01m00s (default), 0:01:00, 0h_01m_00s or 1m00s");
+    println!("--hour-width <n>\tPad the hours with zeros to <n> digits, e.g. 002h");
+    println!("--no-padding\tMinutes and seconds without leading zero, e.g. 1h 5m 0s");
+    println!("--negative-style <minus|parentheses|trailing>");
+    println!("\tNegative durations like -2h 05m 00s (default), (2h 05m 00s) or 2h 05m 00s-");
+    println!("-t|--total-prefix <prefix>\tPrefix the end sum with <prefix>");
+    println!("-s|--stdin-sum-prefix <prefix>\tPrefix the stdin sum with <prefix>");
+    println!("-w|--schedule <schedule>\tWork schedule for work-hours and --business-days, default \"Mon-Fri 09:00-17:00\"");
+    println!(
+        "-g|--group\tPrint subtotals per label of imported entries and files, or diff per label"
+    );
+    println!(
+        "--by-tag\tPrint subtotals per tag like +billable, lines count toward each of their tags"
+    );
+    println!("--tag <tag>\tOnly count lines and entries with this tag");
+    println!("--rollup\tLike --group, nested by /-separated labels like client/project/task");
+    println!("--label-map <file>\tMap labels to names for --group, lines like PROJ-* = Project");
+    println!("-b|--by <day|week|month>\tPrint subtotals per period of dated lines and entries");
+    println!("--window <days>\tPrint the rolling total of the last <days>, e.g. 7d, for each day of dated lines and entries");
+    println!("--sparkline\tAdd a sparkline of the totals per period to --by or --window");
+    println!("--clipboard-in\tAdd the lines in the clipboard to the arguments (feature clipboard)");
+    println!("--clipboard-out\tCopy the total to the clipboard (feature clipboard)");
+    println!("-o|--output <file>\tWrite the results to <file> instead of stdout, may be repeated");
+    println!("-o|--output <spec>\tWrite in another format, e.g. format=json,path=out.json, stdout without path");
+    println!("--append\tAppend to the --output file instead of replacing it");
+    println!("--exec <command>\tRun <command> with {{}} replaced by the total");
+    println!("--seconds\tReplace {{}} with the total in whole seconds for --exec");
+    println!("--notify\tSend a desktop notification when a countdown finishes");
+    println!("--bell\tRing the terminal bell when a countdown finishes");
+    println!("--serve <socket>\tStay resident and answer expressions, one per line, on a Unix domain socket");
+    println!(
+        "--http <address>\tStay resident and answer POST /sum and GET /parse?q=... on <address>"
+    );
+    println!("--baseline <file>\tPrint the change against the total stored in <file>, then store the new one");
+    println!("--no-update\tDon't store the new total in the --baseline file");
+    println!("--repeat <n>\tMultiply the arguments by <n>, e.g. for a recurring amount");
+    println!("--timeline <start>\tPrint start and end of each line as tasks done one after another from <start>");
+    println!(
+        "--burn-rate <rate>\tProject the day the total is done at <rate>, e.g. 6h/day or 30h/week"
+    );
+    println!("--business-days\tOnly count the days of the --schedule (Mon-Fri by default) for --burn-rate");
+    println!("--config <file>\tRead custom units from <file>, default ~/.config/duration-calculator-rs/config.toml");
+    println!("--scale <factor>\tPrint the total multiplied by <factor> as well, e.g. 1.2 for 20% contingency");
+    println!("--cap-per-line <duration>\tPrint the total with each line capped at <duration>, and the excess");
+    println!("--headcount <n>\tPrint the calendar time of the total as effort of <n> people");
+    println!(
+        "--rate <amount>\tPrint the amount of the total (and groups) at an hourly rate, e.g. 95.50"
+    );
+    println!("--currency <code>\tCurrency of --rate, e.g. EUR");
+    println!("--increment <duration>\tRound billed durations to multiples of <duration>, e.g. 15m");
+    println!("--rounding <up|nearest|down>\tHow --increment rounds, default up");
+    println!("--rounding-report\tPrint the total with each line rounded exactly, to the nearest 6m or 15m and up to 15m");
+    println!("-a|--aggregate <mode>\tReduce lines and groups by sum (default), min, max or last");
+    println!("--stats\tPrint count, sum, mean, minimum, maximum, standard deviation and variance of the lines and entries");
+    println!("--histogram <width>\tAdd a histogram with buckets of <width> to --stats");
+    println!("--percentiles <p,...>\tAdd percentiles, e.g. 50,90,99, to --stats");
+    println!("--markdown\tPrint a Markdown table of groups, stdin, arguments and total");
+    println!("--html\tPrint an HTML page with the same table as --markdown");
+    println!("--chart\tAdd a bar chart to --group or --html");
+    println!("--json\tPrint the rows of --markdown as JSON object");
+    println!("--prom\tPrint the total and the rows of --markdown as Prometheus gauges in seconds");
+    println!("--print-var <name>\tPrint <name>_SECONDS and <name>_HUMAN assignments of the total for eval");
+    println!("--prom-name <name>\tMetric name for --prom, default duration_seconds");
+    println!(
+        "--prom-labels <labels>\tLabels added to every --prom sample, e.g. job=timesheet,team=a"
+    );
+    println!("--ics <file>\tAdd the durations of the events in an iCalendar file");
+    println!("--csv <file>\tAdd the durations of a Toggl/Clockify CSV export");
+    println!("--org <file>\tAdd the CLOCK lines of an org file, grouped by headline");
+    println!("-f|--file <file>\tAdd the durations of a file or glob pattern, subtotals per file");
+    println!("--timew\tRead a timewarrior export from stdin, grouped by tags");
+    println!("--ics-summary <regex>\tOnly import entries with a matching summary or description");
+    println!("--from-date <date>\tOnly count dated lines and entries on or after <date>");
+    println!("--to-date <date>\tOnly count dated lines and entries on or before <date>");
+    println!(
+        "--month-unit <M|none>\tWhether an uppercase M means months (30 days), default minutes"
+    );
+    println!("--dup-units <error|warn|allow>\tWhat happens to units used twice in a composite like 3h 2h, default allow");
+    println!("--sane <error|warn|allow>\tWhat happens to components beyond natural bounds like 75m or 36h, default allow");
+    println!("--no-calendar-units\tReject y, w, d and months, for exact h/m/s quantities only");
+    println!("--wall-clock\tIgnore daylight saving time between timestamps, e.g. count 22:00 to 06:00 as 8h");
+    println!("--tz <zone>\tTime zone of timestamps like Europe/Berlin, default the one of the system or TZ");
+    println!("--bare-unit <s|ms>\tCount lines that are just a number in seconds or milliseconds");
+    println!(
+        "--annotate\tEcho stdin with the duration of each line as comment and the total at the end"
+    );
+    println!(
+        "--lsp-like\tAnswer Content-Length framed JSON parse requests on stdin, for editor plugins"
+    );
+    println!("--batch <file>\tPrint the result of each line of <file> (- for stdin) on its own instead of the sum");
+    println!("--echo\tPut each line in front of its result with --batch, separated by a tab");
+    println!(
+        "--state <file>\tKeep accumulators, variables and --batch results in <file> across runs"
+    );
+    println!("--pairs <file>\tPrint two durations per line of <file> (- for stdin) and their difference, and the column totals");
+    println!("--pairs-op <diff|sum|min|max>\tHow --pairs combines the durations, default diff (second minus first)");
+    println!("--delimiter <text>\tWhat separates the durations of --pairs, default a tab");
+    println!("--check\tReport unknown units, out of range components and unparsable lines, exit 36 on errors");
+    println!(
+        "--trace-json\tPrint how each line is understood as JSON, e.g. for editor integrations"
+    );
+    println!("--scan\tSum up every duration found in the input lines, e.g. \"took 3m 42s\"");
+    println!("--lenient\tIgnore words around the duration of a line, e.g. \"spent 2h 15m on it\", and lines without any. Unclear lines are skipped with a warning");
+    println!("-x|--extract <regex>\tSum up the first capture group of every match as duration");
+    println!("--strict-encoding\tFail on invalid UTF-8 instead of replacing it with a warning");
+    println!("--today|--yesterday|--this-week|--last-week|--this-month|--last-month");
+    println!("\tOnly count dated lines and entries in this range");
+    println!(
+        "--stable\tSort groups by label for output independent of the input order, see README"
+    );
+    println!("-v|--verbose\tLog what is read and how long it takes to stderr");
+    println!("--debug\tLog how each line is parsed to stderr as well");
+    println!("--summary\tPrint how many lines were read, parsed, skipped and failed and how long it took to stderr,");
+    println!("\tnot on errors other than failed lines of --batch and --pairs");
+    println!("--version\tPrint the version");
+    println!("--capabilities\tPrint the supported units, formats, subcommands and options as JSON");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(a: &[&str]) -> Vec<String> {
+        a.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let o = Options::parse("", args(&["-c", "1h", "-t", "total", "-", "5m"]));
+        assert!(o.style.compact);
+        assert_eq!(o.total_prefix, "total ");
+        assert_eq!(o.stdin_total_prefix, "");
+        assert_eq!(o.free, args(&["1h", "-", "5m"]));
+
+        let o = Options::parse("", args(&["-f", "a.txt", "--file", "b.txt"]));
+        assert_eq!(o.files, args(&["a.txt", "b.txt"]));
+
+        let o = Options::parse(
+            "",
+            args(&["work-hours", "--schedule", "Mon 08:00-12:00", "a", "b"]),
+        );
+        assert_eq!(o.schedule.as_deref(), Some("Mon 08:00-12:00"));
+        assert_eq!(o.free, args(&["work-hours", "a", "b"]));
+
+        let o = Options::parse("", args(&["--batch", "-", "--echo"]));
+        assert_eq!(o.batch.as_deref(), Some("-"));
+        assert!(o.echo);
+    }
+
+    #[test]
+    fn test_names() {
+        for name in Format::NAMES {
+            assert!(Format::from_str(name).is_ok(), "{}", name);
+        }
+
+        let names = long_names();
+        assert!(names.contains(&"--output") && names.contains(&"--capabilities"));
+    }
+}
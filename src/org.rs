@@ -0,0 +1,96 @@
+//! Import of org-mode `CLOCK:` lines.
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::clock;
+use crate::report::Entry;
+
+/// Returns the clocked time of an org file, labelled with the headline the `CLOCK:` line belongs
+/// to. A running clock lasts until `now`.
+pub fn entries(content: &str, now: NaiveDateTime) -> Vec<Entry> {
+    lazy_static! {
+        static ref HEADLINE_PATTERN: Regex =
+            Regex::new(r"^\*+\s+(?P<title>.*?)(?:\s+:[\w@#%:]+:)?\s*$").unwrap();
+        static ref CLOCK_PATTERN: Regex = Regex::new(
+            r"^\s*CLOCK:\s*\[(?P<start_date>\d{4}-\d{2}-\d{2})[^\]]*?(?P<start_time>\d{1,2}:\d{2})\](?:--\[(?P<end_date>\d{4}-\d{2}-\d{2})[^\]]*?(?P<end_time>\d{1,2}:\d{2})\])?"
+        )
+        .unwrap();
+    }
+
+    let mut entries = Vec::new();
+    let mut headline = "(no headline)".to_owned();
+
+    for line in content.lines() {
+        if let Some(caps) = HEADLINE_PATTERN.captures(line) {
+            headline = caps["title"].to_owned();
+            continue;
+        }
+
+        let caps = match CLOCK_PATTERN.captures(line) {
+            Some(caps) => caps,
+            None => continue,
+        };
+        let date_time = |date: &str, time: &str| {
+            Some(
+                NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .ok()?
+                    .and_time(NaiveTime::parse_from_str(time, "%H:%M").ok()?),
+            )
+        };
+
+        let start = match date_time(&caps["start_date"], &caps["start_time"]) {
+            Some(s) => s,
+            None => continue,
+        };
+        let end = match (caps.name("end_date"), caps.name("end_time")) {
+            (Some(d), Some(t)) => match date_time(d.as_str(), t.as_str()) {
+                Some(e) => e,
+                None => continue,
+            },
+            _ => now,
+        };
+
+        entries.push(Entry {
+            label: headline.clone(),
+            description: headline.clone(),
+            start: Some(start),
+            duration: clock::elapsed(start, end),
+            tags: Vec::new(),
+        });
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_entries() {
+        let now = NaiveDate::from_ymd_opt(2025, 1, 6)
+            .unwrap()
+            .and_hms_opt(15, 0, 0)
+            .unwrap();
+        let org = "#+TITLE: Work
+* TODO Write report                                             :acme:
+  :LOGBOOK:
+  CLOCK: [2025-01-05 Sun 09:00]--[2025-01-05 Sun 11:30] =>  2:30
+  CLOCK: [2025-01-05 So 23:30]--[2025-01-06 Mo 00:15] =>  0:45
+  :END:
+** Review
+   CLOCK: [2025-01-06 Mon 14:20]
+";
+        let entries = entries(org, now);
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].label, "TODO Write report");
+        assert_eq!(entries[0].duration, Duration::minutes(150));
+        assert_eq!(entries[1].duration, Duration::minutes(45));
+        assert_eq!(entries[2].label, "Review");
+        assert_eq!(entries[2].duration, Duration::minutes(40));
+    }
+}
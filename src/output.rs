@@ -0,0 +1,207 @@
+//! Where results go: stdout, or one or more files with `--output`, each in its own format.
+//! Errors and warnings stay on stderr.
+
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+
+use crate::options::Format;
+
+struct Sink {
+    format: Format,
+    writer: Box<dyn Write>,
+    /// For messages
+    name: String,
+}
+
+pub struct Output {
+    sinks: Vec<Sink>,
+}
+
+impl Output {
+    /// Opens a sink per spec, stdout in `format` if there are none. A spec is either a path or a
+    /// comma separated list of `format=<format>` and `path=<path>`, e.g.
+    /// `format=json,path=out.json`. The format defaults to `format`, the path to stdout (also
+    /// `-`). Files are truncated unless `append`.
+    pub fn open(specs: &[String], format: Format, append: bool) -> Result<Output, String> {
+        if specs.is_empty() {
+            return Ok(Output {
+                sinks: vec![Sink::stdout(format)],
+            });
+        }
+
+        let sinks = specs
+            .iter()
+            .map(|spec| {
+                let (sink_format, path) = parse_spec(spec)?;
+                match path.as_deref() {
+                    None | Some("-") => Ok(Sink::stdout(sink_format.unwrap_or(format))),
+                    Some(path) => Sink::file(path, sink_format.unwrap_or(format), append)
+                        .map_err(|e| format!("error opening {}: {}", path, e)),
+                }
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Output { sinks })
+    }
+
+    /// The formats other than `Format::Text` of all sinks, each once.
+    pub fn report_formats(&self) -> Vec<Format> {
+        let mut formats: Vec<Format> = Vec::new();
+        for sink in &self.sinks {
+            if sink.format != Format::Text && !formats.contains(&sink.format) {
+                formats.push(sink.format);
+            }
+        }
+        formats
+    }
+
+    pub fn has_text(&self) -> bool {
+        self.sinks.iter().any(|s| s.format == Format::Text)
+    }
+
+    /// Writes `text` to the sinks in `format`, exiting if that fails, like `print!` panics.
+    pub fn print_format(&mut self, format: Format, text: impl fmt::Display) {
+        for sink in self.sinks.iter_mut().filter(|s| s.format == format) {
+            if let Err(e) = write!(sink.writer, "{}", text) {
+                fail(&sink.name, e);
+            }
+        }
+    }
+
+    /// Writes `text` to the plain text sinks.
+    pub fn print(&mut self, text: impl fmt::Display) {
+        self.print_format(Format::Text, text);
+    }
+
+    /// Writes `text` and a newline to the plain text sinks.
+    pub fn println(&mut self, text: impl fmt::Display) {
+        self.print(format_args!("{}\n", text));
+    }
+
+    /// Writes `text` to all sinks, for results that only exist as plain text.
+    pub fn print_all(&mut self, text: impl fmt::Display) {
+        for sink in &mut self.sinks {
+            if let Err(e) = write!(sink.writer, "{}", text) {
+                fail(&sink.name, e);
+            }
+        }
+    }
+
+    /// Writes `text` and a newline to all sinks.
+    pub fn println_all(&mut self, text: impl fmt::Display) {
+        self.print_all(format_args!("{}\n", text));
+    }
+
+    pub fn flush(&mut self) {
+        for sink in &mut self.sinks {
+            if let Err(e) = sink.writer.flush() {
+                fail(&sink.name, e);
+            }
+        }
+    }
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl Sink {
+    fn stdout(format: Format) -> Sink {
+        Sink {
+            format,
+            writer: Box::new(io::stdout()),
+            name: "stdout".to_owned(),
+        }
+    }
+
+    fn file(path: &str, format: Format, append: bool) -> io::Result<Sink> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)?;
+        Ok(Sink {
+            format,
+            writer: Box::new(BufWriter::new(file)),
+            name: path.to_owned(),
+        })
+    }
+}
+
+/// Format and path of an `--output` spec, see `Output::open`.
+fn parse_spec(spec: &str) -> Result<(Option<Format>, Option<String>), String> {
+    let is_spec = spec.split(',').all(|p| {
+        p.split_once('=')
+            .is_some_and(|(k, _)| k == "format" || k == "path")
+    });
+    if !is_spec {
+        return Ok((None, Some(spec.to_owned())));
+    }
+
+    let mut format = None;
+    let mut path = None;
+    for (key, value) in spec.split(',').filter_map(|p| p.split_once('=')) {
+        match key {
+            "format" => format = Some(Format::from_str(value)?),
+            _ => path = Some(value.to_owned()),
+        }
+    }
+    Ok((format, path))
+}
+
+fn fail(name: &str, e: io::Error) -> ! {
+    eprintln!("error writing {}: {}", name, e);
+    std::process::exit(24);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spec() {
+        assert_eq!(
+            parse_spec("format=json,path=out.json"),
+            Ok((Some(Format::Json), Some("out.json".to_owned())))
+        );
+        assert_eq!(
+            parse_spec("format=markdown"),
+            Ok((Some(Format::Markdown), None))
+        );
+        assert_eq!(
+            parse_spec("a=b.txt"),
+            Ok((None, Some("a=b.txt".to_owned())))
+        );
+        assert!(parse_spec("format=yaml").is_err());
+    }
+
+    #[test]
+    fn test_open() {
+        let dir = std::env::temp_dir();
+        let text = dir.join(format!("dc-test-{}.txt", std::process::id()));
+        let json = dir.join(format!("dc-test-{}.json", std::process::id()));
+        let (text, json) = (text.to_str().unwrap(), json.to_str().unwrap());
+
+        for (append, line) in [(false, "1h"), (true, "2h"), (true, "3h")] {
+            let specs = [text.to_owned(), format!("format=json,path={}", json)];
+            let mut output = Output::open(&specs, Format::Text, append).unwrap();
+            assert_eq!(output.report_formats(), vec![Format::Json]);
+            output.println(line);
+            output.print_format(Format::Json, "{}\n");
+        }
+        assert_eq!(std::fs::read_to_string(text).unwrap(), "1h\n2h\n3h\n");
+        assert_eq!(std::fs::read_to_string(json).unwrap(), "{}\n{}\n{}\n");
+
+        Output::open(&[text.to_owned()], Format::Text, false)
+            .unwrap()
+            .println_all("4h");
+        assert_eq!(std::fs::read_to_string(text).unwrap(), "4h\n");
+
+        std::fs::remove_file(text).unwrap();
+        std::fs::remove_file(json).unwrap();
+    }
+}
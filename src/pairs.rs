@@ -0,0 +1,94 @@
+//! Two durations per line like `8h<TAB>7h 30m`, e.g. planned and actual time, combined per
+//! line for `--pairs`.
+
+use std::str::FromStr;
+
+use chrono::Duration;
+
+use crate::{line_duration, DurationCalculate};
+
+/// How the two durations of a line are combined.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Op {
+    /// The second minus the first, e.g. actual minus planned
+    #[default]
+    Diff,
+    Sum,
+    Min,
+    Max,
+}
+
+impl FromStr for Op {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "diff" => Ok(Op::Diff),
+            "sum" => Ok(Op::Sum),
+            "min" => Ok(Op::Min),
+            "max" => Ok(Op::Max),
+            _ => Err(format!(
+                "invalid pairs op {:?}, expected diff, sum, min or max",
+                s
+            )),
+        }
+    }
+}
+
+impl Op {
+    pub fn apply(self, a: Duration, b: Duration) -> Duration {
+        match self {
+            Op::Diff => b.saturated_sub(&a),
+            Op::Sum => a.saturated_add(&b),
+            Op::Min => a.min(b),
+            Op::Max => a.max(b),
+        }
+    }
+}
+
+/// The durations of a line split at the first `delimiter`, `None` for blank and comment lines.
+pub fn parse(line: &str, delimiter: &str) -> Result<Option<(Duration, Duration)>, String> {
+    let expression = line.split('#').next().unwrap();
+    if expression.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let (a, b) = expression
+        .split_once(delimiter)
+        .ok_or_else(|| format!("expected two durations separated by {:?}", delimiter))?;
+    match (line_duration(a)?, line_duration(b)?) {
+        (Some(a), Some(b)) => Ok(Some((a, b))),
+        _ => Err(format!(
+            "expected two durations separated by {:?}",
+            delimiter
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let (h, m) = (Duration::hours, Duration::minutes);
+        assert_eq!(parse("8h\t7h 30m", "\t"), Ok(Some((h(8), h(7) + m(30)))));
+        assert_eq!(parse("1h , 2h # done", ","), Ok(Some((h(1), h(2)))));
+        assert_eq!(parse("# planned,actual", ","), Ok(None));
+        assert_eq!(parse("  ", "\t"), Ok(None));
+        assert!(parse("8h 7h", "\t").is_err());
+        assert!(parse("8h\t", "\t").is_err());
+        assert!(parse("8h\tlots", "\t").is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let (a, b) = (Duration::hours(8), Duration::minutes(450));
+        assert_eq!(Op::Diff.apply(a, b), Duration::minutes(-30));
+        assert_eq!(Op::Sum.apply(a, b), Duration::minutes(930));
+        assert_eq!(Op::Min.apply(a, b), b);
+        assert_eq!(Op::Max.apply(a, b), a);
+        assert_eq!(Op::from_str("max"), Ok(Op::Max));
+        assert!(Op::from_str("ratio").is_err());
+    }
+}
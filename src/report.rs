@@ -0,0 +1,1047 @@
+//! Imported entries and the grouped report built from them.
+
+use std::fmt;
+
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+
+use crate::json::Value;
+use crate::options::Style;
+use crate::{DisplayableDuration, DurationCalculate};
+
+/// A single duration from an import, e.g. a calendar event or a time tracker record.
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    /// What the entry is grouped by, e.g. the project
+    pub label: String,
+    /// Free text, e.g. the event summary or the time tracker description
+    pub description: String,
+    pub start: Option<NaiveDateTime>,
+    pub duration: Duration,
+    /// Like `billable` for `+billable`, an entry counts toward each of them
+    pub tags: Vec<String>,
+}
+
+impl Entry {
+    /// Whether the entry starts within `from` and `to` (both inclusive, either one optional).
+    /// Entries without a start are always in range.
+    pub fn in_date_range(&self, from: Option<NaiveDate>, to: Option<NaiveDate>) -> bool {
+        match self.start.map(|s| s.date()) {
+            Some(date) => from.is_none_or(|f| date >= f) && to.is_none_or(|t| date <= t),
+            None => true,
+        }
+    }
+}
+
+/// Durations summed up (or otherwise aggregated) per label, in order of first appearance.
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    pub groups: Vec<(String, Duration)>,
+    pub aggregate: Aggregate,
+    /// The duration added last, the total of `Aggregate::Last`
+    pub last: Option<Duration>,
+}
+
+impl Report {
+    /// An empty report aggregating with `aggregate` instead of summing up.
+    pub fn with_aggregate(aggregate: Aggregate) -> Report {
+        Report {
+            aggregate,
+            ..Report::default()
+        }
+    }
+
+    pub fn add(&mut self, label: &str, duration: Duration) {
+        self.last = Some(duration);
+        match self.groups.iter_mut().find(|(l, _)| l == label) {
+            Some((_, d)) => *d = self.aggregate.combine(*d, duration),
+            None => self.groups.push((label.to_owned(), duration)),
+        }
+    }
+
+    /// Sorts the groups by label, for `--stable` output independent of the order of the input.
+    pub fn sort(&mut self) {
+        self.groups.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+
+    /// The aggregate over the groups, zero if there are none. For `Aggregate::Last` the
+    /// duration added last, whichever group it belongs to.
+    pub fn total(&self) -> Duration {
+        if let (Aggregate::Last, Some(last)) = (self.aggregate, self.last) {
+            return last;
+        }
+        self.groups
+            .iter()
+            .map(|(_, d)| *d)
+            .reduce(|t, d| self.aggregate.combine(t, d))
+            .unwrap_or_else(Duration::zero)
+    }
+}
+
+/// How durations are reduced to one, see `Report`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    #[default]
+    Sum,
+    Min,
+    Max,
+    Last,
+}
+
+impl FromStr for Aggregate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sum" => Ok(Aggregate::Sum),
+            "min" => Ok(Aggregate::Min),
+            "max" => Ok(Aggregate::Max),
+            "last" => Ok(Aggregate::Last),
+            _ => Err(format!(
+                "invalid aggregate {:?}, expected sum, min, max or last",
+                s
+            )),
+        }
+    }
+}
+
+impl Aggregate {
+    /// Reduces the aggregate so far `a` and the next duration `b`.
+    pub fn combine(&self, a: Duration, b: Duration) -> Duration {
+        match self {
+            Aggregate::Sum => a.saturated_add(&b),
+            Aggregate::Min => a.min(b),
+            Aggregate::Max => a.max(b),
+            Aggregate::Last => b,
+        }
+    }
+}
+
+/// Calendar period for `Report::by_period`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Period::Day),
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            _ => Err(format!(
+                "invalid period {:?}, expected day, week or month",
+                s
+            )),
+        }
+    }
+}
+
+impl Period {
+    /// Label of the period containing `date`, e.g. `2025-01-06`, `2025-W02` (ISO week) or
+    /// `2025-01`. These sort chronologically.
+    pub fn label(&self, date: NaiveDate) -> String {
+        match self {
+            Period::Day => date.format("%Y-%m-%d").to_string(),
+            Period::Week => {
+                let week = date.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Period::Month => date.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// The label of the entries without a start in `Report::by_period`.
+pub const UNDATED: &str = "(no date)";
+
+impl Report {
+    /// Aggregates `entries` per `period` in chronological order, entries without a start last.
+    pub fn by_period<'a, I: IntoIterator<Item = &'a Entry>>(
+        entries: I,
+        period: Period,
+        aggregate: Aggregate,
+    ) -> Report {
+        let mut report = Report::with_aggregate(aggregate);
+        let mut undated = Report::with_aggregate(aggregate);
+        let mut last = None;
+
+        for e in entries {
+            match e.start {
+                Some(start) => report.add(&period.label(start.date()), e.duration),
+                None => undated.add(UNDATED, e.duration),
+            }
+            last = Some(e.duration);
+        }
+
+        report.sort();
+        report.groups.append(&mut undated.groups);
+        report.last = last;
+        report
+    }
+
+    /// Rolling totals of the `days` up to and including each day from the first to the last
+    /// dated entry, labelled with that day like `2025-01-06`. Entries without a start are left
+    /// out.
+    pub fn rolling<'a, I: IntoIterator<Item = &'a Entry>>(entries: I, days: i64) -> Report {
+        let mut daily: Vec<(NaiveDate, Duration)> = entries
+            .into_iter()
+            .filter_map(|e| Some((e.start?.date(), e.duration)))
+            .collect();
+        daily.sort_by_key(|(date, _)| *date);
+
+        let mut report = Report::default();
+        let (Some((first, _)), Some((last, _))) = (daily.first(), daily.last()) else {
+            return report;
+        };
+
+        let (mut sum, mut added, mut removed) = (Duration::zero(), 0, 0);
+        for day in first.iter_days().take_while(|day| day <= last) {
+            while let Some((_, d)) = daily.get(added).filter(|(date, _)| *date <= day) {
+                sum = sum.saturated_add(d);
+                added += 1;
+            }
+            // windows reaching back beyond the calendar remove nothing
+            let start = Duration::try_days(days - 1)
+                .and_then(|w| day.checked_sub_signed(w))
+                .unwrap_or(NaiveDate::MIN);
+            while let Some((_, d)) = daily.get(removed).filter(|(date, _)| *date < start) {
+                sum = sum.saturated_sub(d);
+                removed += 1;
+            }
+            report.groups.push((Period::Day.label(day), sum));
+        }
+
+        report
+    }
+}
+
+impl Report {
+    /// Aggregates `entries` per tag, entries with several tags count toward each of them.
+    /// Entries without tags are aggregated as `(no tag)`.
+    pub fn by_tag<'a, I: IntoIterator<Item = &'a Entry>>(
+        entries: I,
+        aggregate: Aggregate,
+    ) -> Report {
+        let mut report = Report::with_aggregate(aggregate);
+        for e in entries {
+            if e.tags.is_empty() {
+                report.add("(no tag)", e.duration);
+            }
+            for tag in &e.tags {
+                report.add(tag, e.duration);
+            }
+        }
+        report
+    }
+
+    /// Aggregates `entries` per label.
+    pub fn aggregated<'a, I: IntoIterator<Item = &'a Entry>>(
+        entries: I,
+        aggregate: Aggregate,
+    ) -> Report {
+        let mut report = Report::with_aggregate(aggregate);
+        for e in entries {
+            report.add(&e.label, e.duration);
+        }
+        report
+    }
+}
+
+impl<'a> FromIterator<&'a Entry> for Report {
+    fn from_iter<I: IntoIterator<Item = &'a Entry>>(entries: I) -> Self {
+        Report::aggregated(entries, Aggregate::Sum)
+    }
+}
+
+/// Groups nested by the `/`-separated parts of their labels like `clientA/projX/review`, each
+/// node aggregating its own groups and the ones of its children.
+#[derive(Debug, Default, PartialEq)]
+pub struct Rollup {
+    /// Last part of the label, empty for the root
+    pub name: String,
+    pub duration: Duration,
+    /// In order of first appearance
+    pub children: Vec<Rollup>,
+}
+
+impl Rollup {
+    /// The tree of the groups of `report`, aggregated like the report.
+    pub fn new(report: &Report) -> Rollup {
+        let mut root = Rollup::default();
+        for (label, duration) in &report.groups {
+            let path: Vec<&str> = label
+                .split('/')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .collect();
+            let path = if path.is_empty() {
+                vec![label.as_str()]
+            } else {
+                path
+            };
+            root.insert(&path, *duration, report.aggregate);
+        }
+        root.duration = report.total();
+        root
+    }
+
+    fn insert(&mut self, path: &[&str], duration: Duration, aggregate: Aggregate) {
+        let Some((name, rest)) = path.split_first() else {
+            return;
+        };
+
+        let child = match self.children.iter().position(|c| c.name == *name) {
+            Some(i) => {
+                let child = &mut self.children[i];
+                child.duration = aggregate.combine(child.duration, duration);
+                child
+            }
+            None => {
+                self.children.push(Rollup {
+                    name: name.to_string(),
+                    duration,
+                    children: Vec::new(),
+                });
+                self.children.last_mut().unwrap()
+            }
+        };
+        child.insert(rest, duration, aggregate);
+    }
+
+    /// The nodes below the root depth first, with the names indented by their depth, e.g. for
+    /// `DisplayableReport`. Parents and children both count, so the total of this report is
+    /// meaningless.
+    pub fn flatten(&self) -> Report {
+        fn visit(node: &Rollup, depth: usize, groups: &mut Vec<(String, Duration)>) {
+            for child in &node.children {
+                groups.push((
+                    format!("{}{}", "  ".repeat(depth), child.name),
+                    child.duration,
+                ));
+                visit(child, depth + 1, groups);
+            }
+        }
+
+        let mut groups = Vec::new();
+        visit(self, 0, &mut groups);
+        Report {
+            groups,
+            ..Report::default()
+        }
+    }
+}
+
+/// A `Report` as plain text, one group per line with the labels padded to equal width. If the
+/// third field is set, the durations are aligned and followed by a bar proportional to the
+/// longest group.
+pub struct DisplayableReport<'a>(pub &'a Report, pub Style, pub bool);
+
+impl fmt::Display for DisplayableReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.0.groups.iter().map(|(l, _)| l.chars().count()).max();
+        let durations: Vec<String> = self
+            .0
+            .groups
+            .iter()
+            .map(|(_, d)| DisplayableDuration(*d, self.1).to_string())
+            .collect();
+        let duration_width = durations.iter().map(String::len).max().unwrap_or(0);
+        let max = self
+            .0
+            .groups
+            .iter()
+            .map(|(_, d)| d.num_seconds().unsigned_abs())
+            .max()
+            .unwrap_or(0);
+
+        for ((label, duration), d) in self.0.groups.iter().zip(&durations) {
+            if self.2 {
+                writeln!(
+                    f,
+                    "{:width$} {:>duration_width$} {}",
+                    label,
+                    d,
+                    Bar(duration.num_seconds().unsigned_abs(), max),
+                    width = width.unwrap_or(0)
+                )?;
+            } else {
+                writeln!(f, "{:width$} {}", label, d, width = width.unwrap_or(0))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A bar of unicode block characters, `BAR_WIDTH` characters for the maximum (second field).
+pub struct Bar(pub u64, pub u64);
+
+const BAR_WIDTH: u64 = 20;
+
+impl fmt::Display for Bar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const PARTIAL: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+        if self.1 == 0 {
+            return Ok(());
+        }
+
+        // in eighths of a character
+        let eighths = (self.0 as u128 * BAR_WIDTH as u128 * 8 / self.1 as u128) as usize;
+        write!(f, "{}", "█".repeat(eighths / 8))?;
+        match eighths % 8 {
+            0 => Ok(()),
+            partial => write!(f, "{}", PARTIAL[partial]),
+        }
+    }
+}
+
+/// The durations as one line of unicode block characters, `▁` for zero or the smallest negative
+/// duration and `█` for the longest, for the trend of the totals of a report by period.
+pub struct Sparkline<'a>(pub &'a [Duration]);
+
+impl fmt::Display for Sparkline<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+        let seconds: Vec<i64> = self.0.iter().map(Duration::num_seconds).collect();
+        let low = seconds.iter().copied().min().unwrap_or(0).min(0);
+        let high = seconds.iter().copied().max().unwrap_or(0);
+        let range = (high - low).max(1) as i128;
+
+        for s in seconds {
+            let level = (s - low) as i128 * (LEVELS.len() as i128 - 1) / range;
+            write!(f, "{}", LEVELS[level as usize])?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A `Report` as GitHub flavored Markdown table with the share of each group and a total row
+/// labelled with the second field.
+pub struct MarkdownReport<'a>(pub &'a Report, pub &'a str, pub Style);
+
+impl fmt::Display for MarkdownReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.0.total();
+        let escape = |label: &str| label.replace('|', "\\|");
+
+        writeln!(f, "| Label | Duration | Percent |")?;
+        writeln!(f, "|:------|---------:|--------:|")?;
+
+        for (label, duration) in &self.0.groups {
+            writeln!(
+                f,
+                "| {} | {} | {} |",
+                escape(label),
+                DisplayableDuration(*duration, self.2),
+                Percent(*duration, total)
+            )?;
+        }
+
+        writeln!(
+            f,
+            "| **{}** | **{}** | **{}** |",
+            escape(self.1),
+            DisplayableDuration(total, self.2),
+            Percent(total, total)
+        )
+    }
+}
+
+/// A `Report` as self-contained HTML page with a table like `MarkdownReport` and, if the fourth
+/// field is set, a bar chart as inline SVG.
+pub struct HtmlReport<'a>(pub &'a Report, pub &'a str, pub Style, pub bool);
+
+impl fmt::Display for HtmlReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.0.total();
+
+        writeln!(f, "<!DOCTYPE html>")?;
+        writeln!(f, "<html>")?;
+        writeln!(f, "<head>")?;
+        writeln!(f, "<meta charset=\"utf-8\">")?;
+        writeln!(f, "<title>Duration report</title>")?;
+        writeln!(
+            f,
+            "<style>body {{ font-family: sans-serif; }} table {{ border-collapse: collapse; }} \
+             th, td {{ padding: 0.2em 0.8em; border-bottom: 1px solid #ccc; }} \
+             td.duration, td.percent {{ text-align: right; }}</style>"
+        )?;
+        writeln!(f, "</head>")?;
+        writeln!(f, "<body>")?;
+        writeln!(
+            f,
+            "<h1>{}: {}</h1>",
+            html_escape(self.1),
+            DisplayableDuration(total, self.2)
+        )?;
+
+        writeln!(f, "<table>")?;
+        writeln!(
+            f,
+            "<thead><tr><th>Label</th><th>Duration</th><th>Percent</th></tr></thead>"
+        )?;
+        writeln!(f, "<tbody>")?;
+        for (label, duration) in &self.0.groups {
+            writeln!(
+                f,
+                "<tr><td>{}</td><td class=\"duration\">{}</td><td class=\"percent\">{}</td></tr>",
+                html_escape(label),
+                DisplayableDuration(*duration, self.2),
+                Percent(*duration, total)
+            )?;
+        }
+        writeln!(f, "</tbody>")?;
+        writeln!(
+            f,
+            "<tfoot><tr><th>{}</th><td class=\"duration\">{}</td><td class=\"percent\">{}</td></tr></tfoot>",
+            html_escape(self.1),
+            DisplayableDuration(total, self.2),
+            Percent(total, total)
+        )?;
+        writeln!(f, "</table>")?;
+
+        if self.3 {
+            write_svg_chart(f, self.0)?;
+        }
+
+        writeln!(f, "</body>")?;
+        writeln!(f, "</html>")
+    }
+}
+
+/// Horizontal bars scaled to the longest group, negative durations in red.
+fn write_svg_chart(f: &mut fmt::Formatter<'_>, report: &Report) -> fmt::Result {
+    const BAR_HEIGHT: usize = 20;
+    const LABEL_WIDTH: usize = 200;
+    const CHART_WIDTH: usize = 400;
+
+    let max = report
+        .groups
+        .iter()
+        .map(|(_, d)| d.num_seconds().unsigned_abs())
+        .max()
+        .unwrap_or(0);
+
+    writeln!(
+        f,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">",
+        LABEL_WIDTH + CHART_WIDTH,
+        BAR_HEIGHT * report.groups.len()
+    )?;
+    for (i, (label, duration)) in report.groups.iter().enumerate() {
+        let width = match max {
+            0 => 0,
+            max => (duration.num_seconds().unsigned_abs() * CHART_WIDTH as u64 / max) as usize,
+        };
+        let y = i * BAR_HEIGHT;
+        writeln!(
+            f,
+            "<text x=\"0\" y=\"{}\" font-size=\"12\">{}</text>",
+            y + BAR_HEIGHT * 3 / 4,
+            html_escape(label)
+        )?;
+        writeln!(
+            f,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            LABEL_WIDTH,
+            y + 2,
+            width,
+            BAR_HEIGHT - 4,
+            if *duration < Duration::zero() {
+                "#c33"
+            } else {
+                "#47a"
+            }
+        )?;
+    }
+    writeln!(f, "</svg>")
+}
+
+/// A `Report` as a single line JSON object with the groups and the total labelled with the
+/// second field, each with the formatted duration and whole seconds.
+pub struct JsonReport<'a>(pub &'a Report, pub &'a str, pub Style);
+
+impl fmt::Display for JsonReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let row = |label: &str, d: Duration| {
+            Value::Object(vec![
+                ("label".to_owned(), Value::String(label.to_owned())),
+                (
+                    "duration".to_owned(),
+                    Value::String(DisplayableDuration(d, self.2).to_string()),
+                ),
+                ("seconds".to_owned(), Value::Number(d.num_seconds() as f64)),
+            ])
+        };
+
+        let json = Value::Object(vec![
+            (
+                "groups".to_owned(),
+                Value::Array(self.0.groups.iter().map(|(l, d)| row(l, *d)).collect()),
+            ),
+            ("total".to_owned(), row(self.1, self.0.total())),
+        ]);
+        writeln!(f, "{}", json)
+    }
+}
+
+/// The total of a `Report` as shell variable assignments for `eval`, named with the prefix in
+/// the second field, e.g. `RESULT_SECONDS=9000; RESULT_HUMAN="2h 30m 00s"`.
+pub struct ShellReport<'a>(pub &'a Report, pub &'a str, pub Style);
+
+impl fmt::Display for ShellReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.0.total();
+        let human: String = DisplayableDuration(total, self.2)
+            .to_string()
+            .chars()
+            .flat_map(|c| match c {
+                '"' | '\\' | '$' | '`' => vec!['\\', c],
+                c => vec![c],
+            })
+            .collect();
+        writeln!(
+            f,
+            "{name}_SECONDS={}; {name}_HUMAN=\"{}\"",
+            total.num_seconds(),
+            human,
+            name = self.1
+        )
+    }
+}
+
+/// A `Report` in the Prometheus exposition format, the total as gauge named like the second
+/// field and the groups as `<name>_by_group` with a `group` label, all in seconds. The third
+/// field holds constant labels added to every sample.
+pub struct PrometheusReport<'a>(pub &'a Report, pub &'a str, pub &'a [(String, String)]);
+
+impl fmt::Display for PrometheusReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.1;
+        let labels = |extra: Option<&str>| {
+            let labels: Vec<String> = self
+                .2
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .chain(extra.map(|g| ("group", g)))
+                .map(|(k, v)| format!("{}=\"{}\"", k, prometheus_escape(v)))
+                .collect();
+            if labels.is_empty() {
+                String::new()
+            } else {
+                format!("{{{}}}", labels.join(","))
+            }
+        };
+        let seconds = |d: Duration| d.num_milliseconds() as f64 / 1000.0;
+
+        writeln!(f, "# HELP {} Total duration in seconds.", name)?;
+        writeln!(f, "# TYPE {} gauge", name)?;
+        writeln!(f, "{}{} {}", name, labels(None), seconds(self.0.total()))?;
+
+        if !self.0.groups.is_empty() {
+            writeln!(f, "# HELP {}_by_group Duration per group in seconds.", name)?;
+            writeln!(f, "# TYPE {}_by_group gauge", name)?;
+            for (label, d) in &self.0.groups {
+                writeln!(
+                    f,
+                    "{}_by_group{} {}",
+                    name,
+                    labels(Some(label)),
+                    seconds(*d)
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses constant labels like `job=timesheet,team=a` for `PrometheusReport`.
+pub fn prometheus_labels(s: &str) -> Result<Vec<(String, String)>, String> {
+    s.split(',')
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| {
+            let (k, v) = l
+                .split_once('=')
+                .ok_or_else(|| format!("invalid label {:?}, expected name=value", l))?;
+            let k = k.trim();
+            if !is_prometheus_name(k, false) || k == "group" {
+                return Err(format!("invalid label name {:?}", k));
+            }
+            Ok((k.to_owned(), v.trim().trim_matches('"').to_owned()))
+        })
+        .collect()
+}
+
+/// Whether `name` is a valid metric name or, without colons, label name.
+pub fn is_prometheus_name(name: &str, metric: bool) -> bool {
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || (metric && c == ':');
+    name.chars().next().is_some_and(|c| !c.is_ascii_digit()) && name.chars().all(valid)
+}
+
+fn prometheus_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The share of the first duration in the second one with one decimal, e.g. `12.5%`, or `-` if
+/// the second one is zero.
+pub struct Percent(pub Duration, pub Duration);
+
+impl fmt::Display for Percent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.1.is_zero() {
+            return write!(f, "-");
+        }
+
+        let share = self.0.num_milliseconds() as f64 / self.1.num_milliseconds() as f64;
+        write!(f, "{:.1}%", 100.0 * share)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::NegativeStyle;
+
+    fn entry(label: &str, minutes: i64) -> Entry {
+        Entry {
+            label: label.to_owned(),
+            description: String::new(),
+            start: None,
+            duration: Duration::minutes(minutes),
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_rolling() {
+        let dated = |d, hours: i64| Entry {
+            start: NaiveDate::from_ymd_opt(2025, 1, d)
+                .unwrap()
+                .and_hms_opt(9, 0, 0),
+            ..entry("x", hours * 60)
+        };
+        let entries = [
+            dated(3, 2),
+            dated(1, 8),
+            entry("x", 60),
+            dated(1, 1),
+            dated(4, 4),
+        ];
+        let groups: Vec<(String, i64)> = Report::rolling(&entries, 2)
+            .groups
+            .into_iter()
+            .map(|(l, d)| (l, d.num_hours()))
+            .collect();
+        let expected = [
+            ("2025-01-01", 9),
+            ("2025-01-02", 9),
+            ("2025-01-03", 2),
+            ("2025-01-04", 6),
+        ];
+
+        assert_eq!(groups, expected.map(|(l, h)| (l.to_owned(), h)));
+        assert_eq!(Report::rolling(&entries[2..3], 7), Report::default());
+        assert_eq!(
+            Report::rolling(&entries, i64::MAX).groups[3].1,
+            Duration::hours(15)
+        );
+    }
+
+    #[test]
+    fn test_by_period() {
+        let dated = |y, m, d, minutes| Entry {
+            start: NaiveDate::from_ymd_opt(y, m, d)
+                .unwrap()
+                .and_hms_opt(9, 0, 0),
+            ..entry("x", minutes)
+        };
+        let entries = [
+            dated(2025, 1, 7, 30),
+            dated(2024, 12, 30, 10),
+            entry("x", 5),
+            dated(2025, 1, 6, 15),
+            dated(2025, 1, 13, 20),
+        ];
+        let groups = |period| {
+            Report::by_period(&entries, period, Aggregate::Sum)
+                .groups
+                .into_iter()
+                .map(|(l, d)| (l, d.num_minutes()))
+                .collect::<Vec<_>>()
+        };
+        let owned = |v: &[(&str, i64)]| {
+            v.iter()
+                .map(|(l, d)| (l.to_string(), *d))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            groups(Period::Day),
+            owned(&[
+                ("2024-12-30", 10),
+                ("2025-01-06", 15),
+                ("2025-01-07", 30),
+                ("2025-01-13", 20),
+                ("(no date)", 5)
+            ])
+        );
+        assert_eq!(
+            groups(Period::Week),
+            owned(&[
+                ("2025-W01", 10),
+                ("2025-W02", 45),
+                ("2025-W03", 20),
+                ("(no date)", 5)
+            ])
+        );
+        assert_eq!(
+            groups(Period::Month),
+            owned(&[("2024-12", 10), ("2025-01", 65), ("(no date)", 5)])
+        );
+        assert!(Period::from_str("year").is_err());
+    }
+
+    #[test]
+    fn test_report() {
+        let entries = [entry("b", 30), entry("a", 15), entry("b", 45)];
+        let report: Report = entries.iter().collect();
+
+        assert_eq!(
+            report.groups,
+            vec![
+                ("b".to_owned(), Duration::minutes(75)),
+                ("a".to_owned(), Duration::minutes(15))
+            ]
+        );
+        assert_eq!(report.total(), Duration::minutes(90));
+        assert_eq!(
+            DisplayableReport(&report, Style::COMPACT, false).to_string(),
+            "b 1h15m00s\na 0h15m00s\n"
+        );
+        assert_eq!(
+            DisplayableReport(&report, Style::COMPACT, true).to_string(),
+            "b 1h15m00s ████████████████████\na 0h15m00s ████\n"
+        );
+        assert_eq!(Bar(1, 3).to_string(), "██████▋");
+        let hours = |h: &[i64]| h.iter().map(|h| Duration::hours(*h)).collect::<Vec<_>>();
+        assert_eq!(Sparkline(&hours(&[0, 4, 7, 2])).to_string(), "▁▅█▃");
+        assert_eq!(Sparkline(&hours(&[-7, 0, 7])).to_string(), "▁▄█");
+        assert_eq!(Sparkline(&hours(&[0, 0])).to_string(), "▁▁");
+        assert_eq!(Sparkline(&[]).to_string(), "");
+        assert_eq!(
+            MarkdownReport(&report, "Total", Style::default()).to_string(),
+            "| Label | Duration | Percent |
+|:------|---------:|--------:|
+| b | 1h 15m 00s | 83.3% |
+| a | 0h 15m 00s | 16.7% |
+| **Total** | **1h 30m 00s** | **100.0%** |
+"
+        );
+        assert_eq!(
+            Percent(Duration::minutes(1), Duration::zero()).to_string(),
+            "-"
+        );
+
+        let html = HtmlReport(&report, "<Total>", Style::default(), true).to_string();
+        assert!(html.contains("<h1>&lt;Total&gt;: 1h 30m 00s</h1>"));
+        assert!(html.contains(
+            "<tr><td>a</td><td class=\"duration\">0h 15m 00s</td><td class=\"percent\">16.7%</td></tr>"
+        ));
+        assert!(html.contains("<rect x=\"200\" y=\"22\" width=\"80\""));
+    }
+
+    #[test]
+    fn test_by_tag() {
+        let tagged = |minutes, tags: &[&str]| Entry {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..entry("x", minutes)
+        };
+        let entries = [
+            tagged(60, &["billable", "clientA"]),
+            tagged(30, &["clientA"]),
+            tagged(15, &[]),
+        ];
+
+        assert_eq!(
+            Report::by_tag(&entries, Aggregate::Sum).groups,
+            vec![
+                ("billable".to_owned(), Duration::minutes(60)),
+                ("clientA".to_owned(), Duration::minutes(90)),
+                ("(no tag)".to_owned(), Duration::minutes(15)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rollup() {
+        let entries = [
+            entry("clientA/projX/review", 60),
+            entry("clientA/projY", 30),
+            entry("clientB", 15),
+            entry("clientA/projX/dev", 90),
+            entry("clientA/projY/review", 10),
+        ];
+        let report: Report = entries.iter().collect();
+        let rollup = Rollup::new(&report);
+
+        assert_eq!(rollup.duration, Duration::minutes(205));
+        assert_eq!(
+            DisplayableReport(&rollup.flatten(), Style::COMPACT, false).to_string(),
+            "clientA    3h10m00s
+  projX    2h30m00s
+    review 1h00m00s
+    dev    1h30m00s
+  projY    0h40m00s
+    review 0h10m00s
+clientB    0h15m00s
+"
+        );
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let entries = [
+            entry("b", 30),
+            entry("a", 15),
+            entry("b", 45),
+            entry("b", 20),
+        ];
+        let groups = |aggregate| {
+            let report = Report::aggregated(&entries, aggregate);
+            (report.groups.clone(), report.total())
+        };
+        let minutes = |b, a| {
+            vec![
+                ("b".to_owned(), Duration::minutes(b)),
+                ("a".to_owned(), Duration::minutes(a)),
+            ]
+        };
+
+        assert_eq!(
+            groups(Aggregate::Sum),
+            (minutes(95, 15), Duration::minutes(110))
+        );
+        assert_eq!(
+            groups(Aggregate::Min),
+            (minutes(20, 15), Duration::minutes(15))
+        );
+        assert_eq!(
+            groups(Aggregate::Max),
+            (minutes(45, 15), Duration::minutes(45))
+        );
+        assert_eq!(
+            groups(Aggregate::Last),
+            (minutes(20, 15), Duration::minutes(20))
+        );
+        let undated_last = [
+            Entry {
+                start: NaiveDate::from_ymd_opt(2025, 1, 6)
+                    .unwrap()
+                    .and_hms_opt(9, 0, 0),
+                ..entry("a", 30)
+            },
+            Entry {
+                start: NaiveDate::from_ymd_opt(2025, 1, 7)
+                    .unwrap()
+                    .and_hms_opt(9, 0, 0),
+                ..entry("a", 45)
+            },
+            entry("b", 15),
+        ];
+        assert_eq!(
+            Report::by_period(&undated_last, Period::Day, Aggregate::Last).total(),
+            Duration::minutes(15)
+        );
+        assert_eq!(
+            Report::with_aggregate(Aggregate::Min).total(),
+            Duration::zero()
+        );
+        assert!(Aggregate::from_str("avg").is_err());
+    }
+
+    #[test]
+    fn test_json_report() {
+        let entries = [entry("b", 30), entry("a", 15)];
+        let report: Report = entries.iter().collect();
+
+        assert_eq!(
+            JsonReport(&report, "Total", Style::COMPACT).to_string(),
+            concat!(
+                r#"{"groups":[{"label":"b","duration":"0h30m00s","seconds":1800},"#,
+                r#"{"label":"a","duration":"0h15m00s","seconds":900}],"#,
+                r#""total":{"label":"Total","duration":"0h45m00s","seconds":2700}}"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_shell_report() {
+        let entries = [entry("b", 30), entry("a", 15)];
+        let report: Report = entries.iter().collect();
+
+        assert_eq!(
+            ShellReport(&report, "RESULT", Style::default()).to_string(),
+            "RESULT_SECONDS=2700; RESULT_HUMAN=\"0h 45m 00s\"\n"
+        );
+        let style = Style {
+            negative_style: NegativeStyle::Parentheses,
+            ..Style::default()
+        };
+        let report = Report {
+            groups: vec![("a".to_owned(), Duration::minutes(-5))],
+            ..Report::default()
+        };
+        assert_eq!(
+            ShellReport(&report, "D", style).to_string(),
+            "D_SECONDS=-300; D_HUMAN=\"(0h 05m 00s)\"\n"
+        );
+    }
+
+    #[test]
+    fn test_prometheus_report() {
+        let entries = [entry("b", 30), entry("a \"x\"", 15)];
+        let report: Report = entries.iter().collect();
+        let labels = prometheus_labels("job=timesheet, team=\"a\"").unwrap();
+
+        assert_eq!(
+            PrometheusReport(&report, "work_seconds", &labels).to_string(),
+            r#"# HELP work_seconds Total duration in seconds.
+# TYPE work_seconds gauge
+work_seconds{job="timesheet",team="a"} 2700
+# HELP work_seconds_by_group Duration per group in seconds.
+# TYPE work_seconds_by_group gauge
+work_seconds_by_group{job="timesheet",team="a",group="b"} 1800
+work_seconds_by_group{job="timesheet",team="a",group="a \"x\""} 900
+"#
+        );
+        assert_eq!(
+            PrometheusReport(&Report::default(), "t", &[]).to_string(),
+            "# HELP t Total duration in seconds.\n# TYPE t gauge\nt 0\n"
+        );
+        assert!(prometheus_labels("job").is_err());
+        assert!(prometheus_labels("group=a").is_err());
+        assert!(prometheus_labels("1x=a").is_err());
+        assert!(is_prometheus_name("ns:work_seconds", true));
+        assert!(!is_prometheus_name("work-seconds", true));
+    }
+}
@@ -0,0 +1,229 @@
+//! Resident mode answering duration expressions over a Unix domain socket, one line per request.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use chrono::Duration;
+
+use crate::json::Value;
+use crate::options::Style;
+use crate::state::milliseconds;
+use crate::{check_typos, DisplayableDuration, DurationCalculate, DurationParse};
+
+/// Named running totals, shared by all connections.
+pub type Accumulators = Arc<Mutex<HashMap<String, Duration>>>;
+
+/// Answers a single request line:
+///
+/// * `<expression>` - the duration, e.g. `2h - 15m`
+/// * `add <name> <expression>` - adds to the accumulator `name` and returns its new total
+/// * `get <name>` - the total of accumulator `name`, zero if it doesn't exist
+/// * `reset <name>` - removes accumulator `name`
+/// * `state export` - all accumulators as JSON like `{"accumulators":{"build":240000}}`, in
+///   milliseconds, e.g. to back them up or move them to another server
+/// * `state import <json>` - replaces all accumulators with those of an export, also of the
+///   command line's `state export`
+///
+/// Responses are `ok <duration>` (`ok <json>` for exports, `ok <n> accumulators` for imports)
+/// or `error <message>`.
+pub fn respond(line: &str, accumulators: &Accumulators, style: Style) -> String {
+    let parse = |expression: &str| {
+        check_typos(expression, "request")?;
+        Duration::from_str(expression.trim())
+            .ok_or_else(|| format!("cannot parse {:?} as duration", expression.trim()))
+    };
+    let mut words = line.trim().splitn(3, char::is_whitespace);
+
+    let result = match (words.next(), words.next(), words.next()) {
+        (Some("add"), Some(name), expression) => parse(expression.unwrap_or("")).map(|d| {
+            let mut accumulators = accumulators.lock().unwrap();
+            let total = accumulators
+                .entry(name.to_owned())
+                .or_insert_with(Duration::zero);
+            *total = total.saturated_add(&d);
+            *total
+        }),
+        (Some("get"), Some(name), None) => Ok(accumulators
+            .lock()
+            .unwrap()
+            .get(name)
+            .copied()
+            .unwrap_or_else(Duration::zero)),
+        (Some("reset"), Some(name), None) => {
+            accumulators.lock().unwrap().remove(name);
+            Ok(Duration::zero())
+        }
+        (Some("state"), Some("export"), None) => return format!("ok {}", export(accumulators)),
+        (Some("state"), Some("import"), Some(json)) => {
+            return match import(json, accumulators) {
+                Ok(n) => format!("ok {} accumulators", n),
+                Err(e) => format!("error {}", e),
+            };
+        }
+        _ => parse(line),
+    };
+
+    match result {
+        Ok(d) => format!("ok {}", DisplayableDuration(d, style)),
+        Err(e) => format!("error {}", e),
+    }
+}
+
+/// The accumulators as JSON object of their names and totals in milliseconds, sorted by name.
+fn export(accumulators: &Accumulators) -> Value {
+    let mut members: Vec<(String, Value)> = accumulators
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, d)| (name.clone(), Value::Number(d.num_milliseconds() as f64)))
+        .collect();
+    members.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Value::Object(vec![("accumulators".to_owned(), Value::Object(members))])
+}
+
+/// Replaces the accumulators with those of an `export`, returning how many there are. Nothing
+/// changes if `json` is invalid.
+fn import(json: &str, accumulators: &Accumulators) -> Result<usize, String> {
+    let invalid =
+        || "invalid state, expected {\"accumulators\":{\"<name>\":<milliseconds>}}".to_owned();
+    let members = match Value::parse(json)?.get("accumulators") {
+        Some(Value::Object(members)) => members.clone(),
+        _ => return Err(invalid()),
+    };
+
+    let mut imported = HashMap::new();
+    for (name, value) in members {
+        imported.insert(name, milliseconds(&value).ok_or_else(invalid)?);
+    }
+
+    let n = imported.len();
+    *accumulators.lock().unwrap() = imported;
+    Ok(n)
+}
+
+/// Listens on the socket at `path`, answering each connection in its own thread until killed.
+pub fn serve(path: &str, style: Style) -> io::Result<()> {
+    let listener = UnixListener::bind(path)?;
+    let accumulators = Accumulators::default();
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let accumulators = Arc::clone(&accumulators);
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &accumulators, style) {
+                eprintln!("connection error: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+fn handle(stream: UnixStream, accumulators: &Accumulators, style: Style) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        writeln!(writer, "{}", respond(&line?, accumulators, style))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_respond() {
+        let accumulators = Accumulators::default();
+        let cases = vec![
+            ("2h - 15m", "ok 1h45m00s"),
+            ("add build 3m 20s", "ok 0h03m20s"),
+            ("add build 40s", "ok 0h04m00s"),
+            ("get build", "ok 0h04m00s"),
+            ("get other", "ok 0h00m00s"),
+            ("reset build", "ok 0h00m00s"),
+            ("get build", "ok 0h00m00s"),
+            ("add build soon", "error cannot parse \"soon\" as duration"),
+            ("soon", "error cannot parse \"soon\" as duration"),
+        ];
+
+        for (request, expected) in cases {
+            assert_eq!(
+                respond(request, &accumulators, Style::COMPACT),
+                expected,
+                "{}",
+                request
+            );
+        }
+    }
+
+    #[test]
+    fn test_state() {
+        let accumulators = Accumulators::default();
+        let respond = |request: &str| respond(request, &accumulators, Style::COMPACT);
+        respond("add build 4m");
+        respond("add test -30s");
+
+        let export = respond("state export");
+        assert_eq!(
+            export,
+            r#"ok {"accumulators":{"build":240000,"test":-30000}}"#
+        );
+
+        let other = Accumulators::default();
+        let import = format!("state import {}", &export["ok ".len()..]);
+        assert_eq!(
+            super::respond(&import, &other, Style::COMPACT),
+            "ok 2 accumulators"
+        );
+        assert_eq!(*other.lock().unwrap(), *accumulators.lock().unwrap());
+
+        assert_eq!(
+            respond("state import {\"accumulators\":{}}"),
+            "ok 0 accumulators"
+        );
+        assert_eq!(respond("get build"), "ok 0h00m00s");
+        for invalid in [
+            "{}",
+            "{\"accumulators\":{\"a\":1.5}}",
+            "{\"accumulators\":{\"a\":\"1h\"}}",
+        ] {
+            respond("add build 1h");
+            let request = format!("state import {}", invalid);
+            assert!(respond(&request).starts_with("error "));
+            assert_eq!(respond("get build"), "ok 1h00m00s", "{}", invalid);
+            respond("reset build");
+        }
+        assert!(respond("state import [").starts_with("error "));
+    }
+
+    #[test]
+    fn test_serve() {
+        let path = std::env::temp_dir().join(format!("dc-test-{}.sock", std::process::id()));
+        let path = path.to_str().unwrap().to_owned();
+        let _ = std::fs::remove_file(&path);
+
+        let listening = path.clone();
+        thread::spawn(move || serve(&listening, Style::COMPACT));
+        let stream = (0..100)
+            .find_map(|_| {
+                thread::sleep(std::time::Duration::from_millis(10));
+                UnixStream::connect(&path).ok()
+            })
+            .unwrap();
+
+        let mut writer = stream.try_clone().unwrap();
+        writeln!(writer, "add a 1h").unwrap();
+        writeln!(writer, "add a 30m").unwrap();
+        let lines: Vec<String> = BufReader::new(stream)
+            .lines()
+            .take(2)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(lines, vec!["ok 1h00m00s", "ok 1h30m00s"]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
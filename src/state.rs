@@ -0,0 +1,225 @@
+//! Session state of `--state <file>`, kept as JSON between runs so a session can go on later, be
+//! backed up, inspected or moved to another machine with `state export` and `state import`.
+
+use std::io;
+
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::json::Value;
+use crate::DurationCalculate;
+
+/// What a session has calculated so far, exported like
+///
+/// ```json
+/// {"accumulators":{"mon":29700000},"variables":{"rest":9000000},"history":[3600000,null]}
+/// ```
+///
+/// with durations in milliseconds and `null` in the history for lines without a result.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct State {
+    /// Sums of keyed lines like `mon=8h 15m`, in order of appearance
+    pub accumulators: Vec<(String, Duration)>,
+    /// Values of `--batch` assignments like `rest := 40h - mon`, in order of appearance
+    pub variables: Vec<(String, Duration)>,
+    /// Result of each `--batch` line, `None` for lines without one, see `expand_history`
+    pub history: Vec<Option<Duration>>,
+}
+
+impl State {
+    /// Reads the state saved at `path`, empty if there is no file yet.
+    pub fn load(path: &str) -> Result<State, String> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => State::parse(&content).map_err(|e| format!("{} in {}", e, path)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(State::default()),
+            Err(e) => Err(format!("cannot read state {}: {}", path, e)),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, format!("{}\n", self.to_json()))
+            .map_err(|e| format!("cannot write state {}: {}", path, e))
+    }
+
+    /// Parses an export of `to_json`. Missing members are empty.
+    pub fn parse(json: &str) -> Result<State, String> {
+        let Value::Object(members) = Value::parse(json)? else {
+            return Err("invalid state, expected a JSON object".to_owned());
+        };
+
+        let mut state = State::default();
+        for (key, value) in &members {
+            let invalid = || format!("invalid state member {:?}", key);
+            match (key.as_str(), value) {
+                ("accumulators", Value::Object(m)) => {
+                    state.accumulators = named(m).ok_or_else(invalid)?
+                }
+                ("variables", Value::Object(m)) => {
+                    state.variables = named(m).ok_or_else(invalid)?
+                }
+                ("history", Value::Array(results)) => {
+                    state.history = results
+                        .iter()
+                        .map(|r| match r {
+                            Value::Null => Some(None),
+                            r => milliseconds(r).map(Some),
+                        })
+                        .collect::<Option<_>>()
+                        .ok_or_else(invalid)?
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(state)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let ms = |d: &Duration| Value::Number(d.num_milliseconds() as f64);
+        let named = |values: &[(String, Duration)]| {
+            Value::Object(values.iter().map(|(n, d)| (n.clone(), ms(d))).collect())
+        };
+        let history = self
+            .history
+            .iter()
+            .map(|d| d.as_ref().map_or(Value::Null, ms))
+            .collect();
+
+        Value::Object(vec![
+            ("accumulators".to_owned(), named(&self.accumulators)),
+            ("variables".to_owned(), named(&self.variables)),
+            ("history".to_owned(), Value::Array(history)),
+        ])
+    }
+
+    /// Adds `d` to the accumulator `key` and returns its new sum.
+    pub fn add(&mut self, key: &str, d: Duration) -> Duration {
+        match self.accumulators.iter_mut().find(|(k, _)| k == key) {
+            Some((_, sum)) => {
+                *sum = sum.saturated_add(&d);
+                *sum
+            }
+            None => {
+                self.accumulators.push((key.to_owned(), d));
+                d
+            }
+        }
+    }
+
+    pub fn set(&mut self, name: &str, d: Duration) {
+        match self.variables.iter_mut().find(|(n, _)| n == name) {
+            Some((_, value)) => *value = d,
+            None => self.variables.push((name.to_owned(), d)),
+        }
+    }
+
+    /// The variables and accumulators for `expand_keys`, a variable before an accumulator of the
+    /// same name.
+    pub fn names(&self) -> Vec<(String, Duration)> {
+        self.variables
+            .iter()
+            .chain(&self.accumulators)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Splits the name off a variable assignment like `rest := 40h - mon`.
+pub fn split_variable(line: &str) -> (Option<&str>, &str) {
+    lazy_static! {
+        static ref VARIABLE_PATTERN: Regex = Regex::new(r"^\s*([\pL_]\w*)\s*:=").unwrap();
+    }
+
+    match VARIABLE_PATTERN.captures(line) {
+        Some(caps) => (Some(caps.get(1).unwrap().as_str()), &line[caps[0].len()..]),
+        None => (None, line),
+    }
+}
+
+/// Names with a duration each, `None` if one of them has none.
+fn named(members: &[(String, Value)]) -> Option<Vec<(String, Duration)>> {
+    members
+        .iter()
+        .map(|(name, value)| Some((name.clone(), milliseconds(value)?)))
+        .collect()
+}
+
+/// A duration exported in whole milliseconds, `None` for other values.
+pub fn milliseconds(value: &Value) -> Option<Duration> {
+    match value {
+        Value::Number(ms) if ms.fract() == 0.0 && ms.abs() <= MAX_SAFE_MILLISECONDS => {
+            Some(Duration::milliseconds(*ms as i64))
+        }
+        _ => None,
+    }
+}
+
+/// Most milliseconds a JSON number holds exactly, about 285,000 years.
+const MAX_SAFE_MILLISECONDS: f64 = 9_007_199_254_740_991.0;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let json = r#"{"accumulators":{"mon":29700000,"tue":-60000},"variables":{"rest":9000000},"history":[3600000,null]}"#;
+        let state = State::parse(json).unwrap();
+
+        assert_eq!(
+            state.accumulators,
+            vec![
+                ("mon".to_owned(), Duration::minutes(495)),
+                ("tue".to_owned(), Duration::minutes(-1))
+            ]
+        );
+        assert_eq!(
+            state.variables,
+            vec![("rest".to_owned(), Duration::minutes(150))]
+        );
+        assert_eq!(state.history, vec![Some(Duration::hours(1)), None]);
+        assert_eq!(state.to_json().to_string(), json);
+
+        assert_eq!(State::parse("{}"), Ok(State::default()));
+        for invalid in [
+            "[]",
+            r#"{"accumulators":{"mon":1.5}}"#,
+            r#"{"variables":{"rest":"1h"}}"#,
+            r#"{"history":[true]}"#,
+            r#"{"sums":{}}"#,
+        ] {
+            assert!(State::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_add_set() {
+        let mut state = State::default();
+        assert_eq!(state.add("mon", Duration::hours(8)), Duration::hours(8));
+        assert_eq!(
+            state.add("mon", Duration::minutes(30)),
+            Duration::minutes(510)
+        );
+        state.set("mon", Duration::hours(1));
+        state.set("mon", Duration::hours(2));
+
+        assert_eq!(
+            state.names(),
+            vec![
+                ("mon".to_owned(), Duration::hours(2)),
+                ("mon".to_owned(), Duration::minutes(510))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_variable() {
+        assert_eq!(
+            split_variable("rest := 40h - mon"),
+            (Some("rest"), " 40h - mon")
+        );
+        assert_eq!(split_variable("mon=8h"), (None, "mon=8h"));
+        assert_eq!(split_variable("1h # a := b"), (None, "1h # a := b"));
+    }
+}
@@ -0,0 +1,302 @@
+//! Statistics over the durations of single lines or entries.
+
+use std::fmt;
+
+use chrono::Duration;
+
+use crate::options::Style;
+use crate::report::Bar;
+use crate::{DisplayableDuration, DurationCalculate};
+
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+    /// The durations in input order
+    pub durations: Vec<Duration>,
+}
+
+impl Stats {
+    pub fn new(durations: Vec<Duration>) -> Stats {
+        Stats { durations }
+    }
+
+    pub fn sum(&self) -> Duration {
+        self.durations
+            .iter()
+            .fold(Duration::zero(), |s, d| s.saturated_add(d))
+    }
+
+    /// `None` if there are no durations.
+    pub fn mean(&self) -> Option<Duration> {
+        let count = self.durations.len() as i128;
+        let nanos: i128 = self.durations.iter().map(|d| nanos(*d)).sum::<i128>();
+
+        (count > 0).then(|| Duration::nanoseconds((nanos / count) as i64))
+    }
+
+    /// Population variance in square seconds, `None` if there are no durations. Squares of
+    /// millisecond deviations fit into `i128`, their sum is accumulated as `f64` to not overflow.
+    pub fn variance(&self) -> Option<f64> {
+        let mean = self.mean()?.num_milliseconds() as i128;
+        let squares: f64 = self
+            .durations
+            .iter()
+            .map(|d| {
+                let deviation = d.num_milliseconds() as i128 - mean;
+                (deviation * deviation) as f64
+            })
+            .sum();
+
+        Some(squares / self.durations.len() as f64 / 1_000_000.0)
+    }
+
+    /// Population standard deviation, `None` if there are no durations.
+    pub fn stddev(&self) -> Option<Duration> {
+        // at most the range of the durations, which fits
+        self.variance()
+            .map(|v| Duration::milliseconds((v.sqrt() * 1000.0).round() as i64))
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        self.durations.iter().min().copied()
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.durations.iter().max().copied()
+    }
+
+    /// The smallest duration with at least `percentile` percent of all durations less or equal
+    /// (nearest rank), `None` if there are no durations. `percentile` must be in (0, 100].
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut sorted = self.durations.clone();
+        sorted.sort();
+
+        let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+        sorted.get(rank.clamp(1, sorted.len().max(1)) - 1).copied()
+    }
+
+    /// Number of durations per bucket of `width`, starting at the bucket of the minimum, empty
+    /// buckets in between included. Buckets include their start and exclude their end. `width`
+    /// is widened to a multiple of itself for ranges of more than `MAX_BUCKETS` buckets, see
+    /// `bucket_width`.
+    pub fn histogram(&self, width: Duration) -> Vec<(Duration, usize)> {
+        let width = nanos(self.bucket_width(width));
+        let Some((first, last)) = self.buckets(width) else {
+            return Vec::new();
+        };
+
+        let mut counts = vec![0; (last - first + 1) as usize];
+        for d in &self.durations {
+            counts[(nanos(*d).div_euclid(width) - first) as usize] += 1;
+        }
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| (from_nanos((first + i as i128) * width), count))
+            .collect()
+    }
+
+    /// `width`, or the smallest multiple of it with at most `MAX_BUCKETS` buckets between the
+    /// minimum and the maximum.
+    pub fn bucket_width(&self, width: Duration) -> Duration {
+        let mut width = nanos(width);
+        while let Some((first, last)) = self.buckets(width) {
+            let count = last - first + 1;
+            if count <= MAX_BUCKETS {
+                break;
+            }
+            width *= (count + MAX_BUCKETS - 1) / MAX_BUCKETS;
+        }
+        from_nanos(width)
+    }
+
+    /// The first and the last bucket of `width` nanoseconds, `None` without durations.
+    fn buckets(&self, width: i128) -> Option<(i128, i128)> {
+        let bucket = |d: &Duration| nanos(*d).div_euclid(width);
+        Some((
+            self.durations.iter().map(bucket).min()?,
+            self.durations.iter().map(bucket).max()?,
+        ))
+    }
+}
+
+/// Most buckets of a histogram, so a small width for a wide range doesn't take all memory.
+const MAX_BUCKETS: i128 = 1000;
+
+/// Durations are at most `i64::MAX` nanoseconds apart, but sums of them are not.
+fn nanos(d: Duration) -> i128 {
+    d.num_nanoseconds()
+        .map(i128::from)
+        .unwrap_or_else(|| d.num_milliseconds() as i128 * 1_000_000)
+}
+
+/// The reverse of `nanos`, saturating beyond the longest durations.
+fn from_nanos(nanos: i128) -> Duration {
+    match i64::try_from(nanos) {
+        Ok(n) => Duration::nanoseconds(n),
+        Err(_) => i64::try_from(nanos / 1_000_000)
+            .ok()
+            .and_then(Duration::try_milliseconds)
+            .unwrap_or(if nanos < 0 {
+                Duration::MIN
+            } else {
+                Duration::MAX
+            }),
+    }
+}
+
+/// `Stats` as plain text, count, sum, mean, minimum, maximum, deviation and variance, followed
+/// by the percentiles in the fourth field. With a bucket width in the third field, followed by a histogram.
+pub struct DisplayableStats<'a>(
+    pub &'a Stats,
+    pub Style,
+    pub Option<Duration>,
+    pub &'a [f64],
+);
+
+impl fmt::Display for DisplayableStats<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let duration = |d: Option<Duration>| match d {
+            Some(d) => DisplayableDuration(d, self.1).to_string(),
+            None => "-".to_owned(),
+        };
+
+        writeln!(f, "count {}", self.0.durations.len())?;
+        writeln!(f, "sum   {}", duration(Some(self.0.sum())))?;
+        writeln!(f, "mean  {}", duration(self.0.mean()))?;
+        writeln!(f, "min   {}", duration(self.0.min()))?;
+        writeln!(f, "max   {}", duration(self.0.max()))?;
+        writeln!(f, "sd    {}", duration(self.0.stddev()))?;
+        match self.0.variance() {
+            Some(v) => writeln!(f, "var   {:.0} s²", v)?,
+            None => writeln!(f, "var   -")?,
+        }
+        for p in self.3 {
+            writeln!(f, "p{:<4} {}", p, duration(self.0.percentile(*p)))?;
+        }
+
+        if let Some(width) = self.2 {
+            let width = self.0.bucket_width(width);
+            let histogram = self.0.histogram(width);
+            let max = histogram.iter().map(|(_, c)| *c).max().unwrap_or(0);
+            let rows: Vec<(String, usize)> = histogram
+                .iter()
+                .map(|(start, count)| {
+                    let end = start.saturated_add(&width);
+                    let range = format!(
+                        "{} - {}",
+                        DisplayableDuration(*start, self.1),
+                        DisplayableDuration(end, self.1)
+                    );
+                    (range, *count)
+                })
+                .collect();
+            let width = rows.iter().map(|(r, _)| r.len()).max().unwrap_or(0);
+            let count_width = max.to_string().len();
+
+            writeln!(f)?;
+            for (range, count) in rows {
+                writeln!(
+                    f,
+                    "{:width$} {:>count_width$} {}",
+                    range,
+                    count,
+                    Bar(count as u64, max as u64)
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::new(
+            [10, 20, 50, 5, 40]
+                .iter()
+                .map(|m| Duration::minutes(*m))
+                .collect(),
+        );
+
+        assert_eq!(stats.sum(), Duration::minutes(125));
+        assert_eq!(stats.mean(), Some(Duration::minutes(25)));
+        assert_eq!(stats.min(), Some(Duration::minutes(5)));
+        assert_eq!(stats.max(), Some(Duration::minutes(50)));
+        // deviations -15, -5, 25, -20, 15 minutes
+        assert_eq!(stats.variance(), Some(300.0 * 3600.0));
+        assert_eq!(
+            stats.stddev(),
+            Some(Duration::milliseconds(
+                (300f64.sqrt() * 60_000.0).round() as i64
+            ))
+        );
+        assert_eq!(
+            stats.histogram(Duration::minutes(15)),
+            vec![
+                (Duration::zero(), 2),
+                (Duration::minutes(15), 1),
+                (Duration::minutes(30), 1),
+                (Duration::minutes(45), 1)
+            ]
+        );
+
+        let empty = Stats::new(Vec::new());
+        assert_eq!(empty.mean(), None);
+        assert!(empty.histogram(Duration::minutes(15)).is_empty());
+        assert_eq!(
+            DisplayableStats(&empty, Style::COMPACT, None, &[50.0]).to_string(),
+            "count 0\nsum   0h00m00s\nmean  -\nmin   -\nmax   -\nsd    -\nvar   -\np50   -\n"
+        );
+    }
+
+    #[test]
+    fn test_percentile() {
+        let stats = Stats::new((1..=10).rev().map(Duration::seconds).collect());
+
+        assert_eq!(stats.percentile(50.0), Some(Duration::seconds(5)));
+        assert_eq!(stats.percentile(90.0), Some(Duration::seconds(9)));
+        assert_eq!(stats.percentile(99.0), Some(Duration::seconds(10)));
+        assert_eq!(stats.percentile(100.0), Some(Duration::seconds(10)));
+        assert_eq!(stats.percentile(0.1), Some(Duration::seconds(1)));
+        assert_eq!(Stats::new(Vec::new()).percentile(50.0), None);
+    }
+
+    #[test]
+    fn test_variance_large() {
+        let stats = Stats::new(vec![
+            Duration::MAX,
+            Duration::MIN,
+            Duration::MAX,
+            Duration::MIN,
+        ]);
+        assert!(stats.variance().unwrap().is_finite());
+        assert!(stats.stddev().unwrap() > Duration::days(100_000_000));
+    }
+
+    #[test]
+    fn test_histogram_negative() {
+        let stats = Stats::new(vec![-Duration::minutes(5), Duration::minutes(5)]);
+        assert_eq!(
+            stats.histogram(Duration::minutes(10)),
+            vec![(-Duration::minutes(10), 1), (Duration::zero(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_histogram_wide() {
+        let stats = Stats::new(vec![Duration::zero(), Duration::days(3650)]);
+        let width = stats.bucket_width(Duration::seconds(1));
+        assert_eq!(width, Duration::seconds(315_361));
+        let histogram = stats.histogram(Duration::seconds(1));
+        assert_eq!(histogram.len(), 1000);
+        assert_eq!(histogram[0], (Duration::zero(), 1));
+        assert_eq!(histogram[999], (width * 999, 1));
+
+        let stats = Stats::new(vec![Duration::MIN, Duration::MAX]);
+        assert_eq!(stats.histogram(Duration::milliseconds(1)).len(), 1000);
+    }
+}
@@ -0,0 +1,88 @@
+//! Shapes of the duration output, see `DisplayableDuration`.
+
+use std::str::FromStr;
+
+/// How durations are displayed, see `DisplayableDuration`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Style {
+    /// Without spaces like `0h01m00s`, or as `compact_style` says
+    pub compact: bool,
+    /// Shape of the compact output
+    pub compact_style: CompactStyle,
+    /// Minimum number of digits of the hours, padded with zeros like `002h`
+    pub hour_width: u8,
+    /// Minutes and seconds without leading zero like `1h 5m 0s`
+    pub unpadded: bool,
+    /// How negative durations are marked
+    pub negative_style: NegativeStyle,
+}
+
+impl Style {
+    /// Compact like `1h15m00s`, the shape most tests compare against
+    pub const COMPACT: Style = Style {
+        compact: true,
+        compact_style: CompactStyle::Units,
+        hour_width: 0,
+        unpadded: false,
+        negative_style: NegativeStyle::Minus,
+    };
+}
+
+/// Variants of the compact output of durations.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CompactStyle {
+    /// `0h01m00s`
+    #[default]
+    Units,
+    /// `0:01:00`
+    Colon,
+    /// `0h_01m_00s`
+    Underscore,
+    /// `1m00s`, hours only if there are any
+    Narrow,
+}
+
+/// Marks of negative durations, accounting-style outputs want parentheses or a trailing minus.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum NegativeStyle {
+    /// `-2h 05m 00s`
+    #[default]
+    Minus,
+    /// `(2h 05m 00s)`
+    Parentheses,
+    /// `2h 05m 00s-`
+    Trailing,
+}
+
+impl FromStr for NegativeStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "minus" => Ok(NegativeStyle::Minus),
+            "parentheses" => Ok(NegativeStyle::Parentheses),
+            "trailing" => Ok(NegativeStyle::Trailing),
+            _ => Err(format!(
+                "invalid negative style {:?}, expected minus, parentheses or trailing",
+                s
+            )),
+        }
+    }
+}
+
+impl FromStr for CompactStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "units" => Ok(CompactStyle::Units),
+            "colon" => Ok(CompactStyle::Colon),
+            "underscore" => Ok(CompactStyle::Underscore),
+            "narrow" => Ok(CompactStyle::Narrow),
+            _ => Err(format!(
+                "invalid compact style {:?}, expected units, colon, underscore or narrow",
+                s
+            )),
+        }
+    }
+}
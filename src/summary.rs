@@ -0,0 +1,95 @@
+//! Counts of the duration lines read from stdin and files, printed to stderr on exit with
+//! `--summary`, so stdout stays clean for the totals in pipelines. Also when `--batch` and
+//! `--pairs` exit because of failed lines, but not on other errors.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static READ: AtomicUsize = AtomicUsize::new(0);
+static SKIPPED: AtomicUsize = AtomicUsize::new(0);
+static FAILED: AtomicUsize = AtomicUsize::new(0);
+/// In nanoseconds
+static PARSING: AtomicU64 = AtomicU64::new(0);
+
+/// Counts a line read, which is `skipped` if it's blank, just a comment or has no duration.
+pub fn count_line(skipped: bool) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    READ.fetch_add(1, Ordering::Relaxed);
+    if skipped {
+        SKIPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts a line read that cannot be parsed, where that's not an error right away like in
+/// `--batch`.
+pub fn count_failed() {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    READ.fetch_add(1, Ordering::Relaxed);
+    FAILED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Adds the time spent reading and parsing lines.
+pub fn add_parsing_time(elapsed: Duration) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+    PARSING.fetch_add(nanos, Ordering::Relaxed);
+}
+
+/// Like `lines: 1200 read, 1178 parsed, 20 skipped, 2 failed in 12.3ms`.
+fn summary(read: usize, skipped: usize, failed: usize, parsing: Duration) -> String {
+    format!(
+        "lines: {} read, {} parsed, {} skipped, {} failed in {:.1?}",
+        read,
+        read - skipped - failed,
+        skipped,
+        failed,
+        parsing
+    )
+}
+
+/// Prints the summary to stderr if counting, e.g. right before exiting with an error.
+pub fn print() {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let read = READ.load(Ordering::Relaxed);
+    let skipped = SKIPPED.load(Ordering::Relaxed);
+    let failed = FAILED.load(Ordering::Relaxed);
+    let parsing = Duration::from_nanos(PARSING.load(Ordering::Relaxed));
+    eprintln!("{}", summary(read, skipped, failed, parsing));
+}
+
+/// Starts counting and returns a guard that prints the summary to stderr when dropped, i.e.
+/// when `main` returns. Not on errors, which exit right away, unless they `print` it before.
+pub fn start() -> Guard {
+    ENABLED.store(true, Ordering::Relaxed);
+    Guard
+}
+
+pub struct Guard;
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        print();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(
+            summary(4, 1, 1, Duration::from_micros(1500)),
+            "lines: 4 read, 2 parsed, 1 skipped, 1 failed in 1.5ms"
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Import of timewarrior's `timew export` JSON.
+
+use chrono::NaiveDateTime;
+
+use crate::json::Value;
+use crate::report::Entry;
+
+/// Returns the intervals of a `timew export`, labelled with their tags. An open interval lasts
+/// until `now`, which has to be UTC like the timestamps of the export.
+///
+/// The configuration header timewarrior passes to extensions is skipped, so the same input works
+/// for a report extension.
+pub fn entries(content: &str, now: NaiveDateTime) -> Result<Vec<Entry>, String> {
+    let content = content.trim_start_matches('\u{feff}');
+    let json = match content.find("\n[") {
+        Some(i) if !content.trim_start().starts_with('[') => &content[i + 1..],
+        _ => content,
+    };
+
+    let intervals = Value::parse(json)?;
+    let intervals = intervals
+        .as_array()
+        .ok_or("expected an array of intervals")?;
+
+    let mut entries = Vec::new();
+
+    for (i, interval) in intervals.iter().enumerate() {
+        let timestamp = |key: &str| {
+            interval
+                .get(key)
+                .and_then(Value::as_str)
+                .map(|t| {
+                    NaiveDateTime::parse_from_str(t, "%Y%m%dT%H%M%SZ")
+                        .map_err(|_| format!("invalid {} {:?} in interval {}", key, t, i + 1))
+                })
+                .transpose()
+        };
+
+        let start = timestamp("start")?.ok_or_else(|| format!("no start in interval {}", i + 1))?;
+        let end = timestamp("end")?.unwrap_or(now);
+
+        let tags: Vec<&str> = interval
+            .get("tags")
+            .and_then(Value::as_array)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+
+        entries.push(Entry {
+            label: if tags.is_empty() {
+                "(no tags)".to_owned()
+            } else {
+                tags.join(" ")
+            },
+            description: interval
+                .get("annotation")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_owned(),
+            start: Some(start),
+            duration: end - start,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        });
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDate};
+
+    #[test]
+    fn test_entries() {
+        let now = NaiveDate::from_ymd_opt(2025, 1, 6)
+            .unwrap()
+            .and_hms_opt(15, 0, 0)
+            .unwrap();
+        let export = r#"temp.report.start: 20250106T000000Z
+verbose: on
+
+[
+{"id":3,"start":"20250106T090000Z","end":"20250106T103000Z","tags":["acme","meeting"],"annotation":"Kickoff"},
+{"id":2,"start":"20250106T110000Z","end":"20250106T111500Z"},
+{"id":1,"start":"20250106T140000Z","tags":["acme"]}
+]
+"#;
+        let entries = entries(export, now).unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].label, "acme meeting");
+        assert_eq!(entries[0].description, "Kickoff");
+        assert_eq!(entries[0].duration, Duration::minutes(90));
+        assert_eq!(entries[1].label, "(no tags)");
+        assert_eq!(entries[2].duration, Duration::hours(1));
+
+        assert!(super::entries(r#"[{"end":"20250106T090000Z"}]"#, now).is_err());
+        assert!(super::entries(r#"{"start":"20250106T090000Z"}"#, now).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+//! How an expression was understood, as JSON for editor integrations, see `--trace-json`.
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::json::Value;
+use crate::options::Style;
+use crate::{evaluate, expand, Bound, DisplayableDuration, Step};
+
+/// Seconds with millisecond precision.
+fn seconds(d: Duration) -> Value {
+    Value::Number(d.num_milliseconds() as f64 / 1000.0)
+}
+
+/// The evaluation of `input` as JSON object with the `input`, the `expanded` line with only
+/// signs and tokens left, the sign `groups` with their `tokens`, each with its value and the
+/// `sum` so far, and the `total`. Instead of the total, there's an `error` if the input cannot
+/// be parsed.
+pub fn trace(input: &str, now: NaiveDateTime, style: Style) -> Value {
+    let mut groups: Vec<(String, Vec<Value>)> = Vec::new();
+    let result = evaluate(input, now, Bound::Middle, &mut |step: Step| {
+        if groups.len() <= step.group {
+            groups.push((step.sign.to_owned(), Vec::new()));
+        }
+        groups[step.group].1.push(Value::Object(vec![
+            ("token".to_owned(), Value::String(step.token.to_owned())),
+            (
+                "seconds".to_owned(),
+                step.value.map_or(Value::Null, seconds),
+            ),
+            ("sum".to_owned(), seconds(step.sum)),
+        ]));
+    });
+
+    let expanded = (!input.is_empty())
+        .then(|| expand(input, now, Bound::Middle))
+        .flatten()
+        .map_or(Value::Null, |e| Value::String(e.trim().to_owned()));
+    let groups = groups
+        .into_iter()
+        .map(|(sign, tokens)| {
+            Value::Object(vec![
+                ("sign".to_owned(), Value::String(sign)),
+                ("tokens".to_owned(), Value::Array(tokens)),
+            ])
+        })
+        .collect();
+
+    let mut members = vec![
+        ("input".to_owned(), Value::String(input.to_owned())),
+        ("expanded".to_owned(), expanded),
+        ("groups".to_owned(), Value::Array(groups)),
+    ];
+    members.push(match result {
+        Some(d) => (
+            "total".to_owned(),
+            Value::Object(vec![
+                (
+                    "duration".to_owned(),
+                    Value::String(DisplayableDuration(d, style).to_string()),
+                ),
+                ("seconds".to_owned(), seconds(d)),
+            ]),
+        ),
+        None => ("error".to_owned(), Value::String("cannot parse".to_owned())),
+    });
+
+    Value::Object(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_trace() {
+        let now = NaiveDate::from_ymd_opt(2025, 1, 8)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+
+        assert_eq!(
+            trace("2h 30m - 1.5s # lunch", now, Style::default()).to_string(),
+            r#"{"input":"2h 30m - 1.5s # lunch","expanded":"+2h 30m - 1.5s","groups":[{"sign":"+","tokens":[{"token":"2h","seconds":7200,"sum":7200},{"token":"30m","seconds":1800,"sum":9000}]},{"sign":"-","tokens":[{"token":"1.5s","seconds":1.5,"sum":8998.5}]}],"total":{"duration":"2h 29m 58s","seconds":8998.5}}"#
+        );
+        assert_eq!(
+            trace("2h +", now, Style::default()).to_string(),
+            r#"{"input":"2h +","expanded":"+2h +","groups":[],"error":"cannot parse"}"#
+        );
+    }
+}
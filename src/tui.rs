@@ -0,0 +1,314 @@
+//! Interactive viewer for the entries, their group subtotals and the total. Only built with the
+//! `tui` feature, as it talks to the terminal directly.
+//!
+//! Keys: `j`/`k` or arrows scroll, space/`b` page, `u` toggles compact units, `/` filters by
+//! label (enter to apply, escape to clear), `q` quits.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::io::AsRawFd;
+
+use crate::options::Style;
+use crate::report::{Entry, Report};
+use crate::DisplayableDuration;
+
+/// What is shown, independent of the terminal.
+struct View<'a> {
+    entries: &'a [Entry],
+    style: Style,
+    filter: String,
+    /// Typing a filter
+    editing: bool,
+    /// First line of the scrolling part
+    offset: usize,
+}
+
+impl View<'_> {
+    fn matching(&self) -> impl Iterator<Item = &Entry> {
+        let filter = self.filter.to_lowercase();
+        self.entries
+            .iter()
+            .filter(move |e| e.label.to_lowercase().contains(&filter))
+    }
+
+    /// The scrolling part: entries, then group subtotals.
+    fn body(&self) -> Vec<String> {
+        let entries: Vec<&Entry> = self.matching().collect();
+        let report: Report = entries.iter().copied().collect();
+        let width = entries
+            .iter()
+            .map(|e| e.label.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines: Vec<String> = entries
+            .iter()
+            .map(|e| {
+                let date = e.start.map_or("          ".to_owned(), |s| {
+                    s.format("%Y-%m-%d").to_string()
+                });
+                format!(
+                    "{} {:width$} {:>12}  {}",
+                    date,
+                    e.label,
+                    DisplayableDuration(e.duration, self.style).to_string(),
+                    e.description.trim()
+                )
+            })
+            .collect();
+
+        lines.push(String::new());
+        lines.push("Groups".to_owned());
+        let width = report
+            .groups
+            .iter()
+            .map(|(l, _)| l.chars().count())
+            .max()
+            .unwrap_or(0);
+        lines.extend(report.groups.iter().map(|(label, d)| {
+            format!(
+                "{:width$} {:>12}",
+                label,
+                DisplayableDuration(*d, self.style).to_string()
+            )
+        }));
+        lines
+    }
+
+    /// The whole screen of `height` lines, each cut to `width` characters.
+    fn render(&self, width: usize, height: usize) -> Vec<String> {
+        let body = self.body();
+        let visible = height.saturating_sub(2);
+        let total: Report = self.matching().collect();
+
+        let mut screen: Vec<String> = body
+            .iter()
+            .skip(self.offset)
+            .take(visible)
+            .cloned()
+            .collect();
+        screen.resize(visible, String::new());
+        screen.push(format!(
+            "Total {}  ({} of {} entries)",
+            DisplayableDuration(total.total(), self.style),
+            self.matching().count(),
+            self.entries.len()
+        ));
+        screen.push(if self.editing {
+            format!("/{}", self.filter)
+        } else {
+            "q quit  j/k scroll  u units  / filter".to_owned()
+        });
+
+        screen
+            .into_iter()
+            .map(|l| l.chars().take(width).collect())
+            .collect()
+    }
+
+    /// Handles a key, returns `false` to quit.
+    fn key(&mut self, key: Key, page: usize) -> bool {
+        let last = self.body().len().saturating_sub(1);
+
+        if self.editing {
+            match key {
+                Key::Char('\r') | Key::Char('\n') => self.editing = false,
+                Key::Escape => {
+                    self.editing = false;
+                    self.filter.clear();
+                }
+                Key::Backspace => {
+                    self.filter.pop();
+                }
+                Key::Char(c) if !c.is_control() => self.filter.push(c),
+                _ => {}
+            }
+            self.offset = 0;
+            return true;
+        }
+
+        match key {
+            Key::Char('q') => return false,
+            Key::Char('j') | Key::Down => self.offset = (self.offset + 1).min(last),
+            Key::Char('k') | Key::Up => self.offset = self.offset.saturating_sub(1),
+            Key::Char(' ') => self.offset = (self.offset + page).min(last),
+            Key::Char('b') => self.offset = self.offset.saturating_sub(page),
+            Key::Char('u') => self.style.compact = !self.style.compact,
+            Key::Char('/') => self.editing = true,
+            Key::Escape => self.filter.clear(),
+            _ => {}
+        }
+        true
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Key {
+    Char(char),
+    Up,
+    Down,
+    Backspace,
+    Escape,
+}
+
+/// Decodes a single read from the terminal, escape sequences other than arrows are ignored.
+fn decode_key(bytes: &[u8]) -> Option<Key> {
+    match bytes {
+        [0x1b, b'[', b'A', ..] => Some(Key::Up),
+        [0x1b, b'[', b'B', ..] => Some(Key::Down),
+        [0x1b] => Some(Key::Escape),
+        [0x1b, ..] => None,
+        [0x7f] | [0x08] => Some(Key::Backspace),
+        _ => std::str::from_utf8(bytes)
+            .ok()?
+            .chars()
+            .next()
+            .map(Key::Char),
+    }
+}
+
+/// The terminal in raw mode on the alternate screen, restored on drop.
+struct Terminal {
+    tty: File,
+    original: libc::termios,
+}
+
+impl Terminal {
+    fn open() -> io::Result<Terminal> {
+        let tty = File::options().read(true).write(true).open("/dev/tty")?;
+        let fd = tty.as_raw_fd();
+
+        // SAFETY: fd is a valid open terminal, termios is plain old data filled by tcgetattr
+        let original = unsafe {
+            let mut termios = std::mem::zeroed::<libc::termios>();
+            if libc::tcgetattr(fd, &mut termios) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            termios
+        };
+        let mut raw = original;
+        // SAFETY: as above
+        unsafe {
+            libc::cfmakeraw(&mut raw);
+            if libc::tcsetattr(fd, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        let mut terminal = Terminal { tty, original };
+        write!(terminal.tty, "\x1b[?1049h\x1b[?25l")?;
+        Ok(terminal)
+    }
+
+    /// Columns and rows, 80x24 if unknown.
+    fn size(&self) -> (usize, usize) {
+        // SAFETY: TIOCGWINSZ fills the winsize struct passed
+        let size = unsafe {
+            let mut size = std::mem::zeroed::<libc::winsize>();
+            (libc::ioctl(self.tty.as_raw_fd(), libc::TIOCGWINSZ, &mut size) == 0).then_some(size)
+        };
+        match size {
+            Some(s) if s.ws_col > 0 && s.ws_row > 0 => (s.ws_col as usize, s.ws_row as usize),
+            _ => (80, 24),
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = write!(self.tty, "\x1b[?25h\x1b[?1049l");
+        // SAFETY: restores the attributes read in open
+        unsafe {
+            libc::tcsetattr(self.tty.as_raw_fd(), libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Shows `entries` until `q` is pressed. Keys are read from the terminal, so stdin may be a
+/// redirect.
+pub fn run(entries: &[Entry], style: Style) -> io::Result<()> {
+    let mut terminal = Terminal::open()?;
+    let mut view = View {
+        entries,
+        style,
+        filter: String::new(),
+        editing: false,
+        offset: 0,
+    };
+    let mut buffer = [0; 16];
+
+    loop {
+        let (width, height) = terminal.size();
+        let screen = view.render(width, height);
+        write!(terminal.tty, "\x1b[H\x1b[2J{}", screen.join("\r\n"))?;
+        terminal.tty.flush()?;
+
+        let n = terminal.tty.read(&mut buffer)?;
+        if n == 0 {
+            return Ok(());
+        }
+        if let Some(key) = decode_key(&buffer[..n]) {
+            if !view.key(key, height.saturating_sub(2)) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn entry(label: &str, minutes: i64) -> Entry {
+        Entry {
+            label: label.to_owned(),
+            description: format!("{}m # {}", minutes, label),
+            start: None,
+            duration: Duration::minutes(minutes),
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_view() {
+        let entries = [entry("dev", 90), entry("ops", 30), entry("dev", 15)];
+        let mut view = View {
+            entries: &entries,
+            style: Style::COMPACT,
+            filter: String::new(),
+            editing: false,
+            offset: 0,
+        };
+
+        let screen = view.render(40, 10);
+        assert_eq!(screen.len(), 10);
+        assert_eq!(screen[0], "           dev     1h30m00s  90m # dev");
+        assert_eq!(screen[5], "dev     1h45m00s");
+        assert_eq!(screen[8], "Total 2h15m00s  (3 of 3 entries)");
+
+        for key in [
+            Key::Char('/'),
+            Key::Char('o'),
+            Key::Char('\r'),
+            Key::Char('u'),
+        ] {
+            assert!(view.key(key, 8));
+        }
+        let screen = view.render(80, 10);
+        assert_eq!(screen[8], "Total 0h 30m 00s  (1 of 3 entries)");
+
+        assert!(view.key(Key::Char('j'), 8));
+        assert_eq!(view.offset, 1);
+        assert!(!view.key(Key::Char('q'), 8));
+    }
+
+    #[test]
+    fn test_decode_key() {
+        assert_eq!(decode_key(b"\x1b[A"), Some(Key::Up));
+        assert_eq!(decode_key(b"\x1b"), Some(Key::Escape));
+        assert_eq!(decode_key(b"\x1b[5~"), None);
+        assert_eq!(decode_key(b"q"), Some(Key::Char('q')));
+        assert_eq!(decode_key(b"\x7f"), Some(Key::Backspace));
+    }
+}
@@ -0,0 +1,433 @@
+//! Checks of duration expressions without computing them, for `--check` and editor plugins.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::{
+    duplicate_units_policy, is_calendar_unit, is_custom_unit, is_rejected_calendar_unit,
+    line_duration, months, sane_policy, Policy, TAG,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    /// The line cannot be used as it is
+    Error,
+    /// The line is used, but probably not meant like that
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Character offset in the line, starting at 0
+    pub column: usize,
+    pub message: String,
+    /// Replacement for the token at `column`, if there is a likely one
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)?;
+        match &self.suggestion {
+            Some(s) => write!(f, ", did you mean {:?}?", s),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Units and the number of them making up the next larger unit, if any.
+const UNITS: [(&str, Option<i64>); 10] = [
+    ("µs", Some(1000)),
+    ("us", Some(1000)),
+    ("ms", Some(1000)),
+    ("s", Some(60)),
+    ("m", Some(60)),
+    ("min", Some(60)),
+    ("h", Some(24)),
+    ("d", Some(7)),
+    ("w", None),
+    ("y", None),
+];
+
+/// Spelled out unit names and the unit they stand for, for suggestions.
+const NAMES: [(&str, &str); 21] = [
+    ("usec", "us"),
+    ("msec", "ms"),
+    ("sec", "s"),
+    ("secs", "s"),
+    ("second", "s"),
+    ("seconds", "s"),
+    ("mins", "min"),
+    ("minute", "min"),
+    ("minutes", "min"),
+    ("hr", "h"),
+    ("hrs", "h"),
+    ("hour", "h"),
+    ("hours", "h"),
+    ("day", "d"),
+    ("days", "d"),
+    ("wk", "w"),
+    ("week", "w"),
+    ("weeks", "w"),
+    ("yr", "y"),
+    ("year", "y"),
+    ("years", "y"),
+];
+
+/// Rank of a unit from small to large, `None` for unknown units.
+fn rank(unit: &str) -> Option<usize> {
+    let unit = match unit {
+        "µs" => "us",
+        "min" => "m",
+        u => u,
+    };
+    ["us", "ms", "s", "m", "h", "d", "w", "y"]
+        .iter()
+        .position(|u| *u == unit)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(row[j + 1])
+            };
+            previous = current;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// The unit closest to an unknown one, if any is close enough.
+fn nearest_unit(unit: &str) -> Option<&'static str> {
+    UNITS
+        .iter()
+        .map(|(u, _)| (*u, *u))
+        .chain(NAMES)
+        .map(|(name, u)| (edit_distance(unit, name), u))
+        .filter(|(distance, _)| *distance <= 2 && *distance < unit.chars().count())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, u)| u)
+}
+
+/// A line up to the comment with its tags blanked out rather than removed, to keep the columns.
+fn masked(input: &str) -> Cow<'_, str> {
+    lazy_static! {
+        static ref TAG_PATTERN: Regex = Regex::new(TAG).unwrap();
+    }
+
+    TAG_PATTERN.replace_all(
+        input.split('#').next().unwrap(),
+        |caps: &regex::Captures| " ".repeat(caps[0].chars().count()),
+    )
+}
+
+/// The tokens of a masked line, grouped into composites like `1h 30m`, i.e. consecutive tokens
+/// separated by whitespace only.
+fn composites(line: &str) -> Vec<Vec<regex::Captures<'_>>> {
+    lazy_static! {
+        static ref TOKEN_PATTERN: Regex = Regex::new(
+            r"(?P<count>\d+(?:_\d+)*(?:\.\d+(?:_\d+)*)?(?:[eE][+-]?\d+)?)\s*(?P<unit>\pL+)"
+        )
+        .unwrap();
+    }
+
+    let mut composites: Vec<Vec<regex::Captures>> = Vec::new();
+    let mut last_end = None;
+    for caps in TOKEN_PATTERN.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        match (last_end, composites.last_mut()) {
+            (Some(end), Some(composite)) if line[end..m.start()].trim().is_empty() => {
+                composite.push(caps)
+            }
+            _ => composites.push(vec![caps]),
+        }
+        last_end = Some(m.end());
+    }
+
+    composites
+}
+
+/// What a unit stands for, so `m` and `min` are the same, but an uppercase `M` for months is not.
+fn unit_key(unit: &str) -> String {
+    if is_custom_unit(unit) || unit == "M" && is_calendar_unit(unit, months()) {
+        return unit.to_owned();
+    }
+    match unit.to_lowercase().as_str() {
+        "min" => "m".to_owned(),
+        "µs" => "us".to_owned(),
+        u => u.to_owned(),
+    }
+}
+
+/// Units used more than once within a composite like the second `h` of `3h 2h`, usually typos,
+/// with their column.
+pub fn duplicate_units(input: &str) -> Vec<(usize, String)> {
+    let line = masked(input);
+    let mut duplicates = Vec::new();
+
+    for composite in composites(&line) {
+        let mut seen = Vec::new();
+        for caps in composite {
+            let unit = caps.name("unit").unwrap();
+            let key = unit_key(unit.as_str());
+            if seen.contains(&key) {
+                duplicates.push((
+                    line[..unit.start()].chars().count(),
+                    unit.as_str().to_owned(),
+                ));
+            } else {
+                seen.push(key);
+            }
+        }
+    }
+
+    duplicates
+}
+
+/// Components beyond their natural bound with their column and a message: units next to a
+/// larger one like the `90m` of `1h 90m`, and minutes or seconds of `h:mm:ss` beyond 59. With
+/// `sane`, also single components like `75m` or `36h`, and hours of `h:mm:ss` beyond 23.
+pub fn out_of_range(input: &str, sane: bool) -> Vec<(usize, String)> {
+    lazy_static! {
+        static ref CLOCK_PATTERN: Regex =
+            Regex::new(r"\b(?P<h>\d+):(?P<m>\d{2}):(?P<s>\d{2})(?:\.\d+)?\b").unwrap();
+    }
+
+    let line = masked(input);
+    let column = |offset: usize| line[..offset].chars().count();
+    let mut found = Vec::new();
+
+    for composite in composites(&line) {
+        let largest = composite
+            .iter()
+            .filter_map(|caps| rank(&caps["unit"].to_lowercase()))
+            .max();
+
+        for caps in &composite {
+            let unit = caps["unit"].to_lowercase();
+            let limit = UNITS.iter().find(|(u, _)| *u == unit).and_then(|(_, l)| *l);
+            let count: f64 = caps["count"].replace('_', "").parse().unwrap_or(0.0);
+            let (Some(limit), Some(unit_rank)) = (limit, rank(&unit)) else {
+                continue;
+            };
+            if count < limit as f64 {
+                continue;
+            }
+
+            let token = caps.get(0).unwrap();
+            if largest > Some(unit_rank) {
+                found.push((
+                    column(token.start()),
+                    format!(
+                        "{} out of range next to a larger unit, expected less than {}",
+                        token.as_str(),
+                        limit
+                    ),
+                ));
+            } else if sane && Some(unit_rank) <= rank("h") {
+                found.push((
+                    column(token.start()),
+                    format!(
+                        "{} out of range, expected less than {}",
+                        token.as_str(),
+                        limit
+                    ),
+                ));
+            }
+        }
+    }
+
+    for caps in CLOCK_PATTERN.captures_iter(&line) {
+        let hours = caps.name("h").unwrap();
+        if sane && !hours.as_str().parse::<u64>().is_ok_and(|h| h <= 23) {
+            found.push((
+                column(hours.start()),
+                format!("hours {} out of range, expected 0-23", hours.as_str()),
+            ));
+        }
+        for (name, what) in [("m", "minutes"), ("s", "seconds")] {
+            let component = caps.name(name).unwrap();
+            if component.as_str() > "59" {
+                found.push((
+                    column(component.start()),
+                    format!(
+                        "{} {} out of range, expected 00-59",
+                        what,
+                        component.as_str()
+                    ),
+                ));
+            }
+        }
+    }
+
+    found.sort();
+    found
+}
+
+/// Diagnostics for a line like `2025-01-06 1h 90m +dev # standup`: unknown units, components
+/// out of range next to a larger unit like the `90m`, minutes and seconds of `h:mm:ss` beyond
+/// 59 (more with `--sane`), units used twice in a composite as `--dup-units` says, and lines
+/// that cannot be parsed otherwise. Empty if the line is fine.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let line = masked(input);
+    let line = line.as_ref();
+    let column = |offset: usize| line[..offset].chars().count();
+    let mut diagnostics = Vec::new();
+    let composites = composites(line);
+
+    for caps in composites.iter().flatten() {
+        let unit = caps["unit"].to_lowercase();
+
+        if !UNITS.iter().any(|(u, _)| *u == unit) && !is_custom_unit(&caps["unit"]) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                column: column(caps.name("unit").unwrap().start()),
+                message: format!("unknown unit {:?}", &caps["unit"]),
+                suggestion: nearest_unit(&unit).map(String::from),
+            });
+        } else if is_rejected_calendar_unit(&caps["unit"]) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                column: column(caps.name("unit").unwrap().start()),
+                message: format!("calendar unit {:?} with --no-calendar-units", &caps["unit"]),
+                suggestion: None,
+            });
+        }
+    }
+
+    let severity = match duplicate_units_policy() {
+        Policy::Allow => None,
+        Policy::Warn => Some(Severity::Warning),
+        Policy::Error => Some(Severity::Error),
+    };
+    if let Some(severity) = severity {
+        diagnostics.extend(
+            duplicate_units(input)
+                .into_iter()
+                .map(|(column, unit)| Diagnostic {
+                    severity,
+                    column,
+                    message: format!("duplicate unit {:?}", unit),
+                    suggestion: None,
+                }),
+        );
+    }
+
+    // out of range components are always worth a warning, unlike those only `--sane` rejects
+    let (sane, severity) = match sane_policy() {
+        Policy::Allow => (false, Severity::Warning),
+        Policy::Warn => (true, Severity::Warning),
+        Policy::Error => (true, Severity::Error),
+    };
+    diagnostics.extend(
+        out_of_range(input, sane)
+            .into_iter()
+            .map(|(column, message)| Diagnostic {
+                severity,
+                column,
+                message,
+                suggestion: None,
+            }),
+    );
+
+    if diagnostics.iter().all(|d| d.severity != Severity::Error) {
+        if let Err(message) = line_duration(input) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                column: 0,
+                message,
+                suggestion: None,
+            });
+        }
+    }
+
+    diagnostics.sort_by_key(|d| d.column);
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate("2025-01-06 1h 30m +v2beta # standup 5x"), vec![]);
+        assert_eq!(validate("30m daily for 2w"), vec![]);
+
+        assert_eq!(
+            validate("2hrs + 1h 90m"),
+            vec![
+                Diagnostic {
+                    severity: Severity::Error,
+                    column: 1,
+                    message: "unknown unit \"hrs\"".to_owned(),
+                    suggestion: Some("h".to_owned()),
+                },
+                Diagnostic {
+                    severity: Severity::Warning,
+                    column: 10,
+                    message: "90m out of range next to a larger unit, expected less than 60"
+                        .to_owned(),
+                    suggestion: None,
+                },
+            ]
+        );
+        assert_eq!(
+            validate("5 minuts")[0].to_string(),
+            "error: unknown unit \"minuts\", did you mean \"min\"?"
+        );
+        assert_eq!(validate("3 xyzzy")[0].suggestion, None);
+        assert_eq!(
+            validate("1:75:00")[0].to_string(),
+            "warning: minutes 75 out of range, expected 00-59"
+        );
+        assert_eq!(
+            validate("2h +")[0].to_string(),
+            "error: cannot parse \"2h +\" as duration"
+        );
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        assert_eq!(out_of_range("75m + 36:10:00 + 1h 30m", false), vec![]);
+        assert_eq!(
+            out_of_range("75m + 36:10:00 + 1h 30m + 90s", true),
+            vec![
+                (0, "75m out of range, expected less than 60".to_owned()),
+                (6, "hours 36 out of range, expected 0-23".to_owned()),
+                (26, "90s out of range, expected less than 60".to_owned()),
+            ]
+        );
+        assert_eq!(out_of_range("10d + 3w", true), vec![]);
+        assert_eq!(
+            out_of_range("1w 10d", false),
+            vec![(
+                3,
+                "10d out of range next to a larger unit, expected less than 7".to_owned()
+            )]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Working time between two timestamps according to a weekly work schedule.
+
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Weekday};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::clock;
+
+/// A weekly work schedule, i.e. a list of working time ranges for each weekday.
+#[derive(Debug, PartialEq)]
+pub struct Schedule {
+    /// indexed by `Weekday::num_days_from_monday`
+    days: [Vec<(NaiveTime, NaiveTime)>; 7],
+}
+
+impl Default for Schedule {
+    /// Mon-Fri 09:00-17:00
+    fn default() -> Self {
+        Schedule::from_str("Mon-Fri 09:00-17:00").unwrap()
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    /// Parses a comma separated list of weekdays or weekday ranges, each followed by one or more
+    /// time ranges, e.g. `Mon-Thu 09:00-12:00 13:00-17:00, Fri 09:00-13:00`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lazy_static! {
+            static ref ENTRY_PATTERN: Regex = Regex::new(
+                r"^(?P<from_day>[A-Za-z]+)(?:-(?P<to_day>[A-Za-z]+))?(?P<ranges>(?:\s+\d{1,2}:\d{2}-\d{1,2}:\d{2})+)$"
+            )
+            .unwrap();
+            static ref RANGE_PATTERN: Regex =
+                Regex::new(r"(?P<from>\d{1,2}:\d{2})-(?P<to>\d{1,2}:\d{2})").unwrap();
+        }
+
+        let mut schedule = Schedule {
+            days: Default::default(),
+        };
+
+        for entry in s.split(',').map(str::trim) {
+            let caps = ENTRY_PATTERN
+                .captures(entry)
+                .ok_or_else(|| format!("invalid schedule entry {:?}", entry))?;
+
+            let weekday = |name: &str| {
+                Weekday::from_str(name)
+                    .map(|d| d.num_days_from_monday() as usize)
+                    .map_err(|_| format!("invalid weekday {:?}", name))
+            };
+            let from_day = weekday(&caps["from_day"])?;
+            let to_day = match caps.name("to_day") {
+                Some(d) => weekday(d.as_str())?,
+                None => from_day,
+            };
+
+            for range in RANGE_PATTERN.captures_iter(&caps["ranges"]) {
+                let time = |t: &str| {
+                    NaiveTime::parse_from_str(t, "%H:%M")
+                        .map_err(|_| format!("invalid time {:?}", t))
+                };
+                let (from, to) = (time(&range["from"])?, time(&range["to"])?);
+                if from >= to {
+                    return Err(format!("empty time range {:?}", &range[0]));
+                }
+
+                // Mon-Fri, but also Sat-Mon
+                let mut day = from_day;
+                loop {
+                    schedule.days[day].push((from, to));
+                    if day == to_day {
+                        break;
+                    }
+                    day = (day + 1) % 7;
+                }
+            }
+        }
+
+        Ok(schedule)
+    }
+}
+
+impl Schedule {
+    /// Returns the working time between `start` and `end`, negative if `end` is before `start`.
+    /// Overlapping ranges on the same day are counted twice.
+    pub fn working_time(&self, start: NaiveDateTime, end: NaiveDateTime) -> Duration {
+        if end < start {
+            return -self.working_time(end, start);
+        }
+
+        let mut total = Duration::zero();
+        let mut date = start.date();
+
+        while date <= end.date() {
+            for &(from, to) in &self.days[date.weekday().num_days_from_monday() as usize] {
+                let from = date.and_time(from).max(start);
+                let to = date.and_time(to).min(end);
+                if from < to {
+                    total += clock::elapsed(from, to);
+                }
+            }
+            date = date.succ_opt().unwrap();
+        }
+
+        total
+    }
+
+    /// Whether there is any working time range on `day`.
+    pub fn is_working_day(&self, day: Weekday) -> bool {
+        !self.days[day.num_days_from_monday() as usize].is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(d: u32, h: u32, m: u32) -> NaiveDateTime {
+        // 2025-01-06 is a Monday
+        NaiveDate::from_ymd_opt(2025, 1, d)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_from_str() {
+        let s = Schedule::from_str("Mon-Tue 09:00-12:00 13:00-17:00, Sat-Sun 10:00-11:00").unwrap();
+        let morning = (at(6, 9, 0).time(), at(6, 12, 0).time());
+        let afternoon = (at(6, 13, 0).time(), at(6, 17, 0).time());
+        let weekend = (at(6, 10, 0).time(), at(6, 11, 0).time());
+        assert_eq!(s.days[0], vec![morning, afternoon]);
+        assert_eq!(s.days[1], vec![morning, afternoon]);
+        assert!(s.days[2].is_empty());
+        assert_eq!(s.days[5], vec![weekend]);
+        assert_eq!(s.days[6], vec![weekend]);
+
+        assert!(Schedule::from_str("Mon-Fri").is_err());
+        assert!(Schedule::from_str("Someday 09:00-17:00").is_err());
+        assert!(Schedule::from_str("Mon 17:00-09:00").is_err());
+    }
+
+    #[test]
+    fn test_working_time() {
+        let s = Schedule::default();
+        let cases = vec![
+            (at(6, 9, 0), at(6, 17, 0), Duration::hours(8)),
+            (at(6, 7, 0), at(6, 10, 30), Duration::minutes(90)),
+            (at(6, 16, 0), at(7, 10, 0), Duration::hours(2)),
+            // Friday afternoon to Monday morning
+            (at(10, 15, 0), at(13, 11, 0), Duration::hours(4)),
+            (at(11, 9, 0), at(12, 17, 0), Duration::zero()),
+            (at(6, 0, 0), at(13, 0, 0), Duration::hours(40)),
+            (at(7, 12, 0), at(6, 12, 0), -Duration::hours(8)),
+        ];
+
+        for (start, end, expected) in cases {
+            assert_eq!(s.working_time(start, end), expected, "{} {}", start, end);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Elapsed time across daylight saving time transitions, in a time zone set with `--tz`.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Runs `work-hours` for a schedule of Sundays 01:00-04:00 in Europe/Berlin on the given Sunday
+/// and returns stdout. Needs the time zone data of the system, e.g. the `tzdata` package.
+fn sunday(date: &str, wall_clock: bool) -> String {
+    let zoneinfo = "/usr/share/zoneinfo/Europe/Berlin";
+    assert!(
+        Path::new(zoneinfo).exists(),
+        "missing time zone data {}",
+        zoneinfo
+    );
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_duration-calculator-rs"));
+    if wall_clock {
+        command.arg("--wall-clock");
+    }
+    let output = command
+        .args([
+            "--tz",
+            "Europe/Berlin",
+            "work-hours",
+            "--schedule",
+            "Sun 01:00-04:00",
+            date,
+            &format!("{}T12:00", date),
+        ])
+        .env(
+            "XDG_CONFIG_HOME",
+            std::env::temp_dir().join("dc-clock-no-config"),
+        )
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", date);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_daylight_saving_time() {
+    assert_eq!(sunday("2025-03-30", false), "2h 00m 00s\n");
+    assert_eq!(sunday("2025-10-26", false), "4h 00m 00s\n");
+    assert_eq!(sunday("2025-03-23", false), "3h 00m 00s\n");
+}
+
+#[test]
+fn test_wall_clock() {
+    assert_eq!(sunday("2025-03-30", true), "3h 00m 00s\n");
+    assert_eq!(sunday("2025-10-26", true), "3h 00m 00s\n");
+}
+
+#[test]
+fn test_unknown_time_zone() {
+    for zone in ["Europe/Atlantis", "../zoneinfo/UTC", ""] {
+        let output = Command::new(env!("CARGO_BIN_EXE_duration-calculator-rs"))
+            .args(["--tz", zone, "1h"])
+            .env(
+                "XDG_CONFIG_HOME",
+                std::env::temp_dir().join("dc-clock-no-config"),
+            )
+            .stdin(Stdio::null())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(49), "{}", zone);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            format!("unknown time zone {:?}\n", zone)
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! `max`, `min` and `clamp` with `total`, the sum of stdin, in the arguments.
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs the calculator with `args` and `stdin`, without a configuration file.
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_duration-calculator-rs"))
+        .args(args)
+        .env(
+            "XDG_CONFIG_HOME",
+            std::env::temp_dir().join("dc-functions-no-config"),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_total() {
+    let output = run(&["max(0s, 40h - total)"], "30h\n5h\n");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "35h 00m 00s\n40h 00m 00s\n"
+    );
+
+    let output = run(&["max(0s, 40h - total)"], "42h\n");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42h 00m 00s\n");
+
+    // an accumulator of that name wins, keyed lines aren't part of the sum of stdin
+    let output = run(&["clamp(total, 1h, 2h)"], "total=90m\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "0h 00m 00s\n1h 30m 00s\n"
+    );
+}
+
+#[test]
+fn test_unparsable() {
+    let output = run(&["max(0s, 40h - week)"], "");
+    assert_eq!(output.status.code(), Some(48));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "cannot parse \"max(0s, 40h - week)\" from arguments as duration\n"
+    );
+}
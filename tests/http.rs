@@ -0,0 +1,99 @@
+//! The HTTP API keeps answering after requests the command line would fail on.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// Kills the server when the test ends, also if it fails.
+struct Server(Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Starts the server with `args` on a free port of localhost and returns it with its address.
+fn start(args: &[&str]) -> (Server, String) {
+    let address = TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .unwrap()
+        .to_string();
+    let child = Command::new(env!("CARGO_BIN_EXE_duration-calculator-rs"))
+        .args(["--http", &address])
+        .args(args)
+        .env(
+            "XDG_CONFIG_HOME",
+            std::env::temp_dir().join("dc-http-no-config"),
+        )
+        .stdin(Stdio::null())
+        .spawn()
+        .unwrap();
+    (Server(child), address)
+}
+
+/// Sends a request and returns the whole response, retrying while the server starts.
+fn request(address: &str, method: &str, target: &str, body: &str) -> String {
+    let mut stream = (0..50)
+        .find_map(|_| {
+            TcpStream::connect(address)
+                .map_err(|_| thread::sleep(Duration::from_millis(100)))
+                .ok()
+        })
+        .expect("server not listening");
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        target,
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn test_errors_keep_serving() {
+    let (_server, address) = start(&["--dup-units", "error"]);
+
+    let response = request(&address, "POST", "/sum", "1h 2h\n");
+    assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+    assert!(response
+        .ends_with(r#"{"error":"duplicate unit \"h\" in line 1 of the request body: \"1h 2h\""}"#));
+
+    let response = request(&address, "POST", "/sum", "1h\nsoon\n");
+    assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+
+    let response = request(&address, "GET", "/parse?q=1h%202h", "");
+    assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+    assert!(response
+        .ends_with(r#"{"error":"duplicate unit \"h\" in the query parameter q: \"1h 2h\""}"#));
+
+    let response = request(&address, "GET", "/parse?q=1h", "");
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+}
+
+#[test]
+fn test_idle_connection_dropped() {
+    let (_server, address) = start(&[]);
+    // wait until the server listens
+    request(&address, "GET", "/parse?q=1h", "");
+
+    let mut stream = TcpStream::connect(&address).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(30)))
+        .unwrap();
+    write!(stream, "GET /parse?q=1h HTTP/1.1\r\n").unwrap();
+    let mut response = String::new();
+    // closed by the server without an answer rather than waiting for the rest of the headers
+    assert_eq!(stream.read_to_string(&mut response).unwrap(), 0);
+
+    let response = request(&address, "GET", "/parse?q=1h", "");
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+}
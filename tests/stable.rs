@@ -0,0 +1,90 @@
+//! Byte-exact outputs of canonical inputs with `--stable`, the layout scripts may rely on. A
+//! change here breaks them, see "Stable output" in the README.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Runs the calculator with `args` and `stdin`, without a configuration file, and returns stdout.
+fn run(args: &[&str], stdin: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_duration-calculator-rs"))
+        .arg("--stable")
+        .args(args)
+        .env(
+            "XDG_CONFIG_HOME",
+            std::env::temp_dir().join("dc-stable-no-config"),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{:?}", args);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Writes a file of duration lines named `name` into a directory of the test.
+fn file(test: &str, name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dc-stable-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn test_plain() {
+    assert_eq!(run(&["30m"], "1h\n"), "1h 00m 00s\n1h 30m 00s\n");
+}
+
+#[test]
+fn test_groups_sorted_by_label() {
+    let a = file(
+        "groups",
+        "a.txt",
+        "2h # review\n1h # design\n30m # review\n",
+    );
+    let b = file("groups", "b.txt", "45m # design\n");
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    assert_eq!(
+        run(&["--group", "-f", a, "-f", b, "15m"], ""),
+        "design 1h 45m 00s\nreview 2h 30m 00s\n0h 00m 00s\n4h 30m 00s\n"
+    );
+    assert_eq!(
+        run(&["--json", "-f", a, "15m"], ""),
+        concat!(
+            r#"{"groups":[{"label":"design","duration":"1h 00m 00s","seconds":3600},"#,
+            r#"{"label":"review","duration":"2h 30m 00s","seconds":9000},"#,
+            r#"{"label":"arguments","duration":"0h 15m 00s","seconds":900}],"#,
+            r#""total":{"label":"Total","duration":"3h 45m 00s","seconds":13500}}"#,
+            "\n"
+        )
+    );
+}
+
+#[test]
+fn test_tags_sorted() {
+    assert_eq!(
+        run(&["--by-tag"], "1h +b\n30m +a +b\n15m\n"),
+        "(no tag) 0h 15m 00s\na        0h 30m 00s\nb        1h 30m 00s\n1h 45m 00s\n"
+    );
+}
+
+#[test]
+fn test_diff_sorted() {
+    let a = file("diff", "a.txt", "2h # review\n1h # design\n");
+    let b = file("diff", "b.txt", "45m # design\n");
+    let output = run(
+        &["diff", "--group", a.to_str().unwrap(), b.to_str().unwrap()],
+        "",
+    );
+    let labels: Vec<&str> = output.lines().skip(1).map(|l| &l[..6]).collect();
+    assert_eq!(labels, ["design", "review"]);
+}
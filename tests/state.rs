@@ -0,0 +1,95 @@
+//! Sessions of `--state` going on across runs, and their export and import.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// Runs the calculator with `--state <state>`, `args` and `stdin`, without a configuration file.
+fn run(state: &PathBuf, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_duration-calculator-rs"))
+        .arg("--state")
+        .arg(state)
+        .args(args)
+        .env(
+            "XDG_CONFIG_HOME",
+            std::env::temp_dir().join("dc-state-no-config"),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// A state file of this test that doesn't exist yet.
+fn state_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("dc-state-{}-{}.json", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_session() {
+    let state = state_file("session");
+
+    let output = run(&state, &["mon + tue"], "mon=8h 15m\ntue=7h 50m\n");
+    assert_eq!(stdout(&output), "0h 00m 00s\n16h 05m 00s\n");
+
+    let output = run(
+        &state,
+        &["--batch", "-"],
+        "rest := 40h - mon - tue\nmon=30m\n",
+    );
+    assert_eq!(stdout(&output), "23h 55m 00s\n8h 45m 00s\n");
+
+    let output = run(&state, &["--batch", "-"], "$prev + rest\n$1 - 55m\n");
+    assert_eq!(stdout(&output), "32h 40m 00s\n23h 00m 00s\n");
+
+    let output = run(&state, &["state", "export"], "");
+    assert_eq!(
+        stdout(&output),
+        r#"{"accumulators":{"mon":31500000,"tue":28200000},"variables":{"rest":86100000},"history":[86100000,31500000,117600000,82800000]}"#.to_owned() + "\n"
+    );
+    let _ = std::fs::remove_file(&state);
+}
+
+#[test]
+fn test_import() {
+    let (from, to) = (state_file("from"), state_file("to"));
+    run(&from, &["--batch", "-"], "week=35h\nrest := 40h - week\n");
+    let export = stdout(&run(&from, &["state", "export"], ""));
+
+    // on stdin
+    stdout(&run(&to, &["state", "import"], &export));
+    assert_eq!(stdout(&run(&to, &["state", "export"], "")), export);
+    let output = run(&to, &["--batch", "-"], "rest + $1\n");
+    assert_eq!(stdout(&output), "40h 00m 00s\n");
+
+    // as argument, replacing the session
+    stdout(&run(&to, &["state", "import", "{}"], ""));
+    assert_eq!(
+        stdout(&run(&to, &["state", "export"], "")),
+        "{\"accumulators\":{},\"variables\":{},\"history\":[]}\n"
+    );
+
+    let output = run(&to, &["state", "import", r#"{"history":["1h"]}"#], "");
+    assert_eq!(output.status.code(), Some(50));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "cannot import state: invalid state member \"history\"\n"
+    );
+    let _ = std::fs::remove_file(&from);
+    let _ = std::fs::remove_file(&to);
+}
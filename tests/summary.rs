@@ -0,0 +1,71 @@
+//! The line counts of `--summary` in modes other than summing up, also when they fail.
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs the calculator with `--summary`, `args` and `stdin`, without a configuration file.
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_duration-calculator-rs"))
+        .arg("--summary")
+        .args(args)
+        .env(
+            "XDG_CONFIG_HOME",
+            std::env::temp_dir().join("dc-summary-no-config"),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// The summary line of stderr without the time it took.
+fn counts(output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let summary = stderr.lines().last().unwrap_or("");
+    summary
+        .rsplit_once(" in ")
+        .map_or(summary, |(counts, _)| counts)
+        .to_owned()
+}
+
+#[test]
+fn test_batch() {
+    let output = run(&["--batch", "-"], "1h\n\n# notes\n30m\n");
+    assert!(output.status.success());
+    assert_eq!(
+        counts(&output),
+        "lines: 4 read, 2 parsed, 2 skipped, 0 failed"
+    );
+
+    let output = run(&["--batch", "-"], "1h\nsoon\n");
+    assert_eq!(output.status.code(), Some(40));
+    assert_eq!(
+        counts(&output),
+        "lines: 2 read, 1 parsed, 0 skipped, 1 failed"
+    );
+}
+
+#[test]
+fn test_pairs_and_annotate() {
+    let output = run(&["--pairs", "-"], "8h\t7h\n\n8h 7h\n");
+    assert_eq!(output.status.code(), Some(45));
+    assert_eq!(
+        counts(&output),
+        "lines: 3 read, 1 parsed, 1 skipped, 1 failed"
+    );
+
+    let output = run(&["--annotate"], "1h\n2h +\n");
+    assert!(output.status.success());
+    assert_eq!(
+        counts(&output),
+        "lines: 2 read, 1 parsed, 0 skipped, 1 failed"
+    );
+}